@@ -21,5 +21,19 @@ fn main() {
     }
 
     let target_system = std::env::var("CARGO_CFG_TARGET_OS").unwrap();
+
+    // RunLoop::watch_fd and everything built on top of it (child processes,
+    // signal handling, single instance) is only available on backends that can
+    // watch file descriptors
+    let unix = std::env::var("CARGO_CFG_TARGET_FAMILY")
+        .unwrap_or_default()
+        .split(',')
+        .any(|f| f == "unix");
+    let null_backend = std::env::var("CARGO_FEATURE_NULL_BACKEND").is_ok();
+    println!("cargo:rustc-check-cfg=cfg(fd_watch)");
+    if target_system == "linux" || (unix && null_backend) {
+        cargo_emit::rustc_cfg!("fd_watch");
+    }
+
     gen_keyboard_map::generate_keyboard_map(&target_system).unwrap();
 }
@@ -0,0 +1,19 @@
+#![no_main]
+
+// Run with `cargo fuzz run standard_codec` from nativeshell directory.
+
+use libfuzzer_sys::fuzz_target;
+use nativeshell::codec::{MessageCodec, StandardMethodCodec};
+
+fuzz_target!(|data: &[u8]| {
+    let codec = StandardMethodCodec;
+    let _ = codec.try_decode_method_call(data);
+    let _ = codec.try_decode_envelope(data);
+    let _ = codec.try_decode_message_ref(data);
+
+    // Whatever decodes successfully must be decodable after encoding it again
+    if let Ok(value) = codec.try_decode_message(data) {
+        let encoded = codec.encode_message(&value);
+        assert!(codec.try_decode_message(&encoded).is_ok());
+    }
+});
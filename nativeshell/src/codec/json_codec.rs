@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+
+use log::error;
+
+use super::{
+    DecodeError, DecodeErrorKind, MessageCodec, MethodCall, MethodCallError, MethodCallResult,
+    MethodCodec, Value,
+};
+
+// Wire compatible with Flutter JSONMessageCodec; Messages are UTF-8 encoded JSON.
+pub struct JsonMessageCodec;
+
+// Wire compatible with Flutter JSONMethodCodec; Method calls are encoded as
+// {"method": name, "args": args}, success envelope as [result] and error envelope
+// as [code, message, details].
+pub struct JsonMethodCodec;
+
+impl MessageCodec<Value> for JsonMessageCodec {
+    fn encode_message(&self, v: &Value) -> Vec<u8> {
+        encode_json(&value_to_json(v))
+    }
+
+    fn decode_message(&self, buf: &[u8]) -> Option<Value> {
+        decode_json(buf).map(json_to_value)
+    }
+}
+
+impl MessageCodec<Value> for JsonMethodCodec {
+    fn encode_message(&self, v: &Value) -> Vec<u8> {
+        JsonMessageCodec.encode_message(v)
+    }
+
+    fn decode_message(&self, buf: &[u8]) -> Option<Value> {
+        JsonMessageCodec.decode_message(buf)
+    }
+}
+
+impl MethodCodec<Value> for JsonMethodCodec {
+    fn decode_method_call_or_error(
+        &self,
+        buf: &[u8],
+    ) -> std::result::Result<MethodCall<Value>, Option<Vec<u8>>> {
+        // JSON decoder doesn't report where the message is malformed
+        self.decode_method_call(buf).ok_or_else(|| {
+            let error = DecodeError {
+                offset: 0,
+                kind: DecodeErrorKind::UnexpectedValue {
+                    expected: "method call",
+                },
+            };
+            Some(error.encode_envelope(self))
+        })
+    }
+
+    fn decode_method_call(&self, buf: &[u8]) -> Option<MethodCall<Value>> {
+        let mut call = match decode_json(buf) {
+            Some(serde_json::Value::Object(call)) => call,
+            _ => {
+                error!("Invalid method call");
+                return None;
+            }
+        };
+        let args = call
+            .remove("args")
+            .map(json_to_value)
+            .unwrap_or(Value::Null);
+        match call.remove("method") {
+            Some(serde_json::Value::String(method)) => Some(MethodCall { method, args }),
+            _ => {
+                error!("Invalid method call");
+                None
+            }
+        }
+    }
+
+    fn encode_success_envelope(&self, v: &Value) -> Vec<u8> {
+        encode_json(&serde_json::Value::Array(vec![value_to_json(v)]))
+    }
+
+    fn encode_error_envelope(&self, code: &str, message: Option<&str>, details: &Value) -> Vec<u8> {
+        encode_json(&serde_json::Value::Array(vec![
+            code.into(),
+            message.map(|m| m.into()).unwrap_or(serde_json::Value::Null),
+            value_to_json(details),
+        ]))
+    }
+
+    fn encode_method_call(&self, v: &MethodCall<Value>) -> Vec<u8> {
+        let mut call = serde_json::Map::new();
+        call.insert("method".into(), v.method.clone().into());
+        call.insert("args".into(), value_to_json(&v.args));
+        encode_json(&serde_json::Value::Object(call))
+    }
+
+    fn decode_envelope(&self, buf: &[u8]) -> Option<MethodCallResult<Value>> {
+        let envelope = match decode_json(buf) {
+            Some(serde_json::Value::Array(envelope)) => envelope,
+            _ => return None,
+        };
+        let mut envelope = envelope.into_iter();
+        match (
+            envelope.next(),
+            envelope.next(),
+            envelope.next(),
+            envelope.next(),
+        ) {
+            (Some(result), None, None, None) => Some(Ok(json_to_value(result))),
+            (Some(serde_json::Value::String(code)), Some(message), Some(details), None) => {
+                Some(Err(MethodCallError {
+                    code,
+                    message: match message {
+                        serde_json::Value::String(message) => Some(message),
+                        _ => None,
+                    },
+                    details: json_to_value(details),
+                }))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn encode_json(value: &serde_json::Value) -> Vec<u8> {
+    // serializing serde_json::Value into memory can not fail
+    serde_json::to_vec(value).unwrap()
+}
+
+fn decode_json(buf: &[u8]) -> Option<serde_json::Value> {
+    // Empty message decodes to null, same as in Flutter
+    if buf.is_empty() {
+        return Some(serde_json::Value::Null);
+    }
+    match serde_json::from_slice(buf) {
+        Ok(value) => Some(value),
+        Err(err) => {
+            error!("Invalid JSON message: {err}");
+            None
+        }
+    }
+}
+
+// JSON can't represent everything Value can; Typed lists are converted to plain
+// lists, map keys are converted to strings and non-finite doubles to null.
+pub fn value_to_json(value: &Value) -> serde_json::Value {
+    match value {
+        Value::Null => serde_json::Value::Null,
+        Value::Bool(v) => (*v).into(),
+        Value::I64(v) => (*v).into(),
+        Value::F64(v) => serde_json::Number::from_f64(*v)
+            .map(serde_json::Value::Number)
+            .unwrap_or(serde_json::Value::Null),
+        Value::String(v) => v.as_str().into(),
+        Value::U8List(v) => v.iter().map(|v| serde_json::Value::from(*v)).collect(),
+        Value::I32List(v) => v.iter().map(|v| serde_json::Value::from(*v)).collect(),
+        Value::I64List(v) => v.iter().map(|v| serde_json::Value::from(*v)).collect(),
+        Value::F64List(v) => v.iter().map(|v| value_to_json(&Value::F64(*v))).collect(),
+        Value::F32List(v) => v
+            .iter()
+            .map(|v| value_to_json(&Value::F64((*v).into())))
+            .collect(),
+        Value::List(v) => v.iter().map(value_to_json).collect(),
+        Value::Map(v) => serde_json::Value::Object(
+            v.iter()
+                .map(|(k, v)| (json_key(k), value_to_json(v)))
+                .collect(),
+        ),
+        // Type tag is lost, only the payload is sent
+        Value::Custom(v) => value_to_json(&v.value),
+    }
+}
+
+fn json_key(key: &Value) -> String {
+    match key {
+        Value::String(key) => key.clone(),
+        key => value_to_json(key).to_string(),
+    }
+}
+
+pub fn json_to_value(value: serde_json::Value) -> Value {
+    match value {
+        serde_json::Value::Null => Value::Null,
+        serde_json::Value::Bool(v) => Value::Bool(v),
+        serde_json::Value::Number(v) => match v.as_i64() {
+            Some(v) => Value::I64(v),
+            None => Value::F64(v.as_f64().unwrap_or(f64::NAN)),
+        },
+        serde_json::Value::String(v) => Value::String(v),
+        serde_json::Value::Array(v) => Value::List(v.into_iter().map(json_to_value).collect()),
+        serde_json::Value::Object(v) => Value::Map(
+            v.into_iter()
+                .map(|(k, v)| (Value::String(k), json_to_value(v)))
+                .collect::<HashMap<_, _>>(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use velcro::hash_map;
+
+    use super::{JsonMessageCodec, JsonMethodCodec};
+    use crate::codec::{MessageCodec, MethodCall, MethodCodec, Value};
+
+    #[test]
+    fn test_message_round_trip() {
+        let value = Value::Map(hash_map! {
+            "list".into(): Value::List(vec![1i64.into(), 2.5f64.into(), "x".into(), Value::Null]),
+            "bool".into(): true.into(),
+            "nested".into(): Value::Map(hash_map! { "a".into(): (-5i64).into() }),
+        });
+        let encoded = JsonMessageCodec.encode_message(&value);
+        assert_eq!(JsonMessageCodec.decode_message(&encoded), Some(value));
+    }
+
+    #[test]
+    fn test_message_conversions() {
+        let value = Value::Map(hash_map! {
+            Value::I64(10): Value::I32List(vec![1, 2]),
+        });
+        let encoded = JsonMessageCodec.encode_message(&value);
+        assert_eq!(encoded, br#"{"10":[1,2]}"#);
+        assert_eq!(JsonMessageCodec.decode_message(b""), Some(Value::Null));
+        assert_eq!(JsonMessageCodec.decode_message(b"{"), None);
+    }
+
+    #[test]
+    fn test_method_call() {
+        let encoded = br#"{"method":"foo","args":{"x":1}}"#;
+        let call = JsonMethodCodec.decode_method_call(encoded).unwrap();
+        assert_eq!(call.method, "foo");
+        assert_eq!(call.args, Value::Map(hash_map! { "x".into(): 1i64.into() }));
+
+        let encoded = JsonMethodCodec.encode_method_call(&MethodCall {
+            method: "bar".into(),
+            args: Value::Null,
+        });
+        let call = JsonMethodCodec.decode_method_call(&encoded).unwrap();
+        assert_eq!(call.method, "bar");
+        assert_eq!(call.args, Value::Null);
+
+        assert!(JsonMethodCodec.decode_method_call(b"[1]").is_none());
+    }
+
+    #[test]
+    fn test_envelopes() {
+        let encoded = JsonMethodCodec.encode_success_envelope(&"ok".into());
+        assert_eq!(encoded, br#"["ok"]"#);
+        let decoded = JsonMethodCodec.decode_envelope(&encoded).unwrap();
+        assert_eq!(decoded.unwrap(), Value::String("ok".into()));
+
+        let encoded = JsonMethodCodec.encode_error_envelope("code", None, &1i64.into());
+        assert_eq!(encoded, br#"["code",null,1]"#);
+        let error = JsonMethodCodec
+            .decode_envelope(&encoded)
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(error.code, "code");
+        assert_eq!(error.message, None);
+        assert_eq!(error.details, Value::I64(1));
+
+        assert!(JsonMethodCodec.decode_envelope(b"[1,2]").is_none());
+    }
+}
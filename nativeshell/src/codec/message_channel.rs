@@ -58,9 +58,10 @@ impl<V> MessageChannel<V> {
             engine
                 .binary_messenger()
                 .register_channel_handler(channel_name, move |data, reply| {
-                    let message = codec.decode_message(data).unwrap();
-                    let reply = MessageReply { reply, codec };
-                    callback(message, reply);
+                    if let Some(message) = codec.decode_message(data) {
+                        let reply = MessageReply::new(reply, codec);
+                        callback(message, reply);
+                    }
                 });
         }
         res
@@ -77,10 +78,38 @@ where
 {
     reply: BinaryMessengerReply,
     codec: &'static dyn MessageCodec<V>,
+    on_send: Option<Box<dyn FnOnce(&mut V)>>,
 }
 
 impl<V> MessageReply<V> {
-    pub fn send(self, value: V) {
+    fn new(reply: BinaryMessengerReply, codec: &'static dyn MessageCodec<V>) -> Self {
+        Self {
+            reply,
+            codec,
+            on_send: None,
+        }
+    }
+
+    // Callback invoked with reply right before it is encoded; Can modify the
+    // reply. Callbacks added later run first.
+    pub(crate) fn on_send<F>(mut self, callback: F) -> Self
+    where
+        F: FnOnce(&mut V) + 'static,
+    {
+        let previous = self.on_send.take();
+        self.on_send = Some(Box::new(move |value| {
+            callback(value);
+            if let Some(previous) = previous {
+                previous(value);
+            }
+        }));
+        self
+    }
+
+    pub fn send(mut self, mut value: V) {
+        if let Some(on_send) = self.on_send.take() {
+            on_send(&mut value);
+        }
         let encoded = self.codec.encode_message(&value);
         self.reply.send(&encoded);
     }
@@ -99,3 +128,53 @@ impl<V> Drop for MessageChannel<V> {
         }
     }
 }
+
+//
+//
+//
+
+// Channel handler receiving raw message data; There is no codec involved, the
+// data is not copied.
+pub struct BinaryMessageChannel {
+    context: Context,
+    channel_name: String,
+    engine_handle: EngineHandle,
+}
+
+impl BinaryMessageChannel {
+    pub fn new_with_engine_manager<F>(
+        context: Context,
+        engine_handle: EngineHandle,
+        channel_name: &str,
+        callback: F,
+        engine_manager: &EngineManager,
+    ) -> Self
+    where
+        F: Fn(&[u8], BinaryMessengerReply) + 'static,
+    {
+        if let Some(engine) = engine_manager.get_engine(engine_handle) {
+            engine
+                .binary_messenger()
+                .register_channel_handler(channel_name, callback);
+        }
+        BinaryMessageChannel {
+            context,
+            channel_name: channel_name.into(),
+            engine_handle,
+        }
+    }
+}
+
+impl Drop for BinaryMessageChannel {
+    fn drop(&mut self) {
+        if let Some(context) = self.context.get() {
+            let engine_manager = context.engine_manager.borrow();
+            let engine = engine_manager.get_engine(self.engine_handle);
+            if let Some(engine) = engine {
+                engine
+                    .binary_messenger()
+                    .unregister_channel_handler(&self.channel_name);
+            }
+        }
+    }
+}
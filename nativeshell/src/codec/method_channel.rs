@@ -1,8 +1,13 @@
 use std::marker::PhantomData;
 
+use log::error;
+
 use crate::shell::{BinaryMessengerReply, Context, ContextRef, EngineHandle, EngineManager};
 
-use super::{MethodCall, MethodCallError, MethodCallResult, MethodCodec};
+use super::{
+    MethodCall, MethodCallError, MethodCallResult, MethodCodec, StandardMethodCodec, Value,
+    ValueRef,
+};
 
 // Low level interface to method channel on single engine
 pub struct EngineMethodChannel<V>
@@ -59,9 +64,56 @@ impl<V> EngineMethodChannel<V> {
             engine
                 .binary_messenger()
                 .register_channel_handler(channel_name, move |data, reply| {
-                    let message = codec.decode_method_call(data).unwrap();
-                    let reply = MethodCallReply { reply, codec };
-                    callback(message, reply);
+                    match codec.decode_method_call_or_error(data) {
+                        Ok(message) => {
+                            let reply = MethodCallReply::new(reply, codec);
+                            callback(message, reply);
+                        }
+                        Err(Some(envelope)) => reply.send(&envelope),
+                        // Dropping the reply sends empty response to caller
+                        Err(None) => {}
+                    }
+                });
+        }
+        res
+    }
+}
+
+impl EngineMethodChannel<Value> {
+    // Method channel using StandardMethodCodec where call arguments borrow from
+    // the incoming message buffer instead of being copied.
+    pub fn new_with_engine_manager_ref<F>(
+        context: Context,
+        engine_handle: EngineHandle,
+        channel_name: &str,
+        callback: F,
+        engine_manager: &EngineManager,
+    ) -> Self
+    where
+        F: Fn(MethodCall<ValueRef>, MethodCallReply<Value>) + 'static,
+    {
+        let res = EngineMethodChannel {
+            context,
+            channel_name: channel_name.into(),
+            engine_handle,
+            _data: PhantomData {},
+        };
+
+        let engine = engine_manager.get_engine(engine_handle);
+        if let Some(engine) = engine {
+            engine
+                .binary_messenger()
+                .register_channel_handler(channel_name, move |data, reply| {
+                    match StandardMethodCodec::read_method_call_ref(data) {
+                        Ok(message) => {
+                            let reply = MethodCallReply::new(reply, &StandardMethodCodec);
+                            callback(message, reply);
+                        }
+                        Err(err) => {
+                            error!("Invalid method call: {err}");
+                            reply.send(&err.encode_envelope(&StandardMethodCodec));
+                        }
+                    }
                 });
         }
         res
@@ -74,10 +126,38 @@ where
 {
     reply: BinaryMessengerReply,
     codec: &'static dyn MethodCodec<V>,
+    on_send: Option<Box<dyn FnOnce(&mut MethodCallResult<V>)>>,
 }
 
 impl<V> MethodCallReply<V> {
-    pub fn send(self, value: MethodCallResult<V>) {
+    pub(crate) fn new(reply: BinaryMessengerReply, codec: &'static dyn MethodCodec<V>) -> Self {
+        Self {
+            reply,
+            codec,
+            on_send: None,
+        }
+    }
+
+    // Callback invoked with result right before it is encoded; Can modify the
+    // result. Callbacks added later run first.
+    pub(crate) fn on_send<F>(mut self, callback: F) -> Self
+    where
+        F: FnOnce(&mut MethodCallResult<V>) + 'static,
+    {
+        let previous = self.on_send.take();
+        self.on_send = Some(Box::new(move |value| {
+            callback(value);
+            if let Some(previous) = previous {
+                previous(value);
+            }
+        }));
+        self
+    }
+
+    pub fn send(mut self, mut value: MethodCallResult<V>) {
+        if let Some(on_send) = self.on_send.take() {
+            on_send(&mut value);
+        }
         let encoded = self.codec.encode_method_call_result(&value);
         self.reply.send(&encoded);
     }
@@ -1,17 +1,23 @@
 use crate::Error;
 
-pub use self::value::Value;
+pub use self::value::{Value, ValueRef};
 pub mod value;
 
+mod json_codec;
 mod message_channel;
 mod method_channel;
 mod sender;
 mod standard_codec;
+mod string_codec;
+mod thread_safe_sender;
 
+pub use json_codec::*;
 pub use message_channel::*;
 pub use method_channel::*;
 pub use sender::*;
 pub use standard_codec::*;
+pub use string_codec::*;
+pub use thread_safe_sender::*;
 
 pub struct MethodCall<V> {
     pub method: String,
@@ -20,7 +26,7 @@ pub struct MethodCall<V> {
 
 pub type MethodCallResult<V> = Result<V, MethodCallError<V>>;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct MethodCallError<V> {
     pub code: String,
     pub message: Option<String>,
@@ -64,6 +70,69 @@ where
     }
 }
 
+// Describes why decoding a message failed; offset is position in message buffer
+// where the offending value starts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeError {
+    pub offset: usize,
+    pub kind: DecodeErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeErrorKind {
+    UnexpectedEnd { expected: &'static str },
+    UnexpectedValue { expected: &'static str },
+    UnknownType(u8),
+    InvalidUtf8,
+    NestingTooDeep,
+}
+
+impl DecodeError {
+    // Error code of envelope replied to method call that failed to decode
+    pub const ERROR_CODE: &'static str = "decode_error";
+
+    // Error envelope replied to method call that failed to decode; Details
+    // contain offset and kind of the error.
+    pub fn encode_envelope<C>(&self, codec: &C) -> Vec<u8>
+    where
+        C: MethodCodec<Value> + ?Sized,
+    {
+        let kind = match &self.kind {
+            DecodeErrorKind::UnexpectedEnd { .. } => "unexpectedEnd",
+            DecodeErrorKind::UnexpectedValue { .. } => "unexpectedValue",
+            DecodeErrorKind::UnknownType(_) => "unknownType",
+            DecodeErrorKind::InvalidUtf8 => "invalidUtf8",
+            DecodeErrorKind::NestingTooDeep => "nestingTooDeep",
+        };
+        let details = Value::Map(
+            vec![
+                ("offset".into(), Value::I64(self.offset as i64)),
+                ("kind".into(), kind.into()),
+            ]
+            .into_iter()
+            .collect(),
+        );
+        codec.encode_error_envelope(Self::ERROR_CODE, Some(&self.to_string()), &details)
+    }
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            DecodeErrorKind::UnexpectedEnd { expected } => {
+                write!(f, "Unexpected end of message reading {expected}")
+            }
+            DecodeErrorKind::UnexpectedValue { expected } => {
+                write!(f, "Unexpected value, expected {expected}")
+            }
+            DecodeErrorKind::UnknownType(t) => write!(f, "Unknown value type {t}"),
+            DecodeErrorKind::InvalidUtf8 => write!(f, "Invalid UTF-8 string"),
+            DecodeErrorKind::NestingTooDeep => write!(f, "Values are nested too deep"),
+        }?;
+        write!(f, " at offset {}", self.offset)
+    }
+}
+
 pub trait MessageCodec<V>: Send + Sync {
     /// Methods for plain messages
     fn encode_message(&self, v: &V) -> Vec<u8>;
@@ -72,6 +141,17 @@ pub trait MessageCodec<V>: Send + Sync {
 
 pub trait MethodCodec<V>: Send + Sync {
     fn decode_method_call(&self, buf: &[u8]) -> Option<MethodCall<V>>;
+
+    // Like decode_method_call, but on failure returns error envelope that is
+    // sent back to caller instead; None if the codec can't report decoding
+    // errors, in which case caller gets empty reply.
+    fn decode_method_call_or_error(
+        &self,
+        buf: &[u8],
+    ) -> std::result::Result<MethodCall<V>, Option<Vec<u8>>> {
+        self.decode_method_call(buf).ok_or(None)
+    }
+
     fn encode_success_envelope(&self, v: &V) -> Vec<u8>;
     fn encode_error_envelope(&self, code: &str, message: Option<&str>, details: &V) -> Vec<u8>;
 
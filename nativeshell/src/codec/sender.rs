@@ -1,11 +1,16 @@
-use log::warn;
+use std::{cell::RefCell, rc::Rc, time::Duration};
+
+use log::{error, warn};
 
 use crate::{
-    shell::{Context, EngineHandle},
+    shell::{AsyncMethodCallError, AsyncMethodCallResult, Context, EngineHandle, Handle},
     Error, Result,
 };
 
-use super::{MessageCodec, MethodCall, MethodCallResult, MethodCodec};
+use super::{
+    MessageCodec, MethodCall, MethodCallResult, MethodCodec, ThreadSafeEventSender,
+    ThreadSafeMessageSender, ThreadSafeMethodInvoker,
+};
 
 // Cloneable invoker that can call channel methods
 #[derive(Clone)]
@@ -34,6 +39,18 @@ impl<V> MethodInvoker<V> {
         }
     }
 
+    // Returns invoker that can be moved to and used from other threads.
+    pub fn to_thread_safe(&self) -> Result<ThreadSafeMethodInvoker<V>> {
+        let context = self.context.get().ok_or(Error::InvalidContext)?;
+        let sender = context.run_loop.borrow().new_sender();
+        Ok(ThreadSafeMethodInvoker::new(
+            sender,
+            self.engine_handle,
+            self.channel_name.clone(),
+            self.codec,
+        ))
+    }
+
     pub fn call_method<F>(&self, method: &str, args: V, reply: F) -> Result<()>
     where
         F: FnOnce(MethodCallResult<V>) + 'static,
@@ -55,8 +72,10 @@ impl<V> MethodInvoker<V> {
                             // This can happen during hot restart. For now ignore.
                             warn!("Received empty response from isolate");
                         } else {
-                            let message = codec.decode_envelope(message).unwrap();
-                            reply(message);
+                            match codec.decode_envelope(message) {
+                                Some(message) => reply(message),
+                                None => error!("Received malformed response from isolate"),
+                            }
                         }
                     },
                 )
@@ -67,6 +86,157 @@ impl<V> MethodInvoker<V> {
             Err(Error::InvalidContext)
         }
     }
+
+    // Like call_method, but the reply is also invoked when there is no response
+    // within timeout or the engine is destroyed while waiting for response.
+    // Dropping returned handle cancels the call; Reply will not be invoked after.
+    pub fn call_method_with_timeout<F>(
+        &self,
+        method: &str,
+        args: V,
+        timeout: Option<Duration>,
+        reply: F,
+    ) -> Result<Handle>
+    where
+        F: FnOnce(AsyncMethodCallResult<V>) + 'static,
+    {
+        let context = self.context.get().ok_or(Error::InvalidContext)?;
+        let state = Rc::new(RefCell::new(PendingCall::<V> {
+            reply: Some(Box::new(reply)),
+            timeout: None,
+            engine_destroyed: None,
+            destroyed: false,
+        }));
+
+        let engine_handle = self.engine_handle;
+        let state_clone = state.clone();
+        let context_clone = self.context.clone();
+        let engine_destroyed = context
+            .engine_manager
+            .borrow_mut()
+            .register_destroy_engine_notification(move |engine| {
+                if engine != engine_handle {
+                    return;
+                }
+                // Engine manager is borrowed while notifying; Unregistering the
+                // notification and invoking the reply must wait until next run
+                // loop turn
+                let handle = {
+                    let mut state = state_clone.borrow_mut();
+                    state.destroyed = true;
+                    state.engine_destroyed.take()
+                };
+                if let Some(context) = context_clone.get() {
+                    let state = state_clone.clone();
+                    context
+                        .run_loop
+                        .borrow()
+                        .schedule_now(move || {
+                            drop(handle);
+                            PendingCall::complete(
+                                &state,
+                                Err(AsyncMethodCallError::EngineDestroyed),
+                            );
+                        })
+                        .detach();
+                }
+            });
+        state
+            .borrow_mut()
+            .engine_destroyed
+            .replace(engine_destroyed);
+
+        if let Some(timeout) = timeout {
+            let state_clone = state.clone();
+            let handle = context.run_loop.borrow().schedule(timeout, move || {
+                if let Some(mut handle) = state_clone.borrow_mut().timeout.take() {
+                    handle.detach();
+                }
+                PendingCall::complete(&state_clone, Err(AsyncMethodCallError::Timeout));
+            });
+            state.borrow_mut().timeout.replace(handle);
+        }
+
+        let encoded = self.codec.encode_method_call(&MethodCall {
+            method: method.into(),
+            args,
+        });
+        let codec = self.codec;
+        let state_clone = state.clone();
+        let res = {
+            let engine_manager = context.engine_manager.borrow();
+            let res = match engine_manager.get_engine(self.engine_handle) {
+                Some(engine) => engine.binary_messenger().send_message(
+                    &self.channel_name,
+                    &encoded,
+                    move |message| {
+                        let result = if message.is_empty() {
+                            Err(AsyncMethodCallError::NoReply)
+                        } else {
+                            match codec.decode_envelope(message) {
+                                Some(Ok(value)) => Ok(value),
+                                Some(Err(error)) => {
+                                    Err(AsyncMethodCallError::MethodCallError(error))
+                                }
+                                None => {
+                                    error!("Received malformed response from isolate");
+                                    Err(AsyncMethodCallError::NoReply)
+                                }
+                            }
+                        };
+                        PendingCall::complete(&state_clone, result);
+                    },
+                ),
+                None => Err(Error::InvalidEngineHandle),
+            };
+            res
+        };
+        match res {
+            Ok(()) => Ok(Handle::new(move || PendingCall::cancel(&state))),
+            Err(error) => {
+                // Release the timer and notification, they reference the state
+                PendingCall::cancel(&state);
+                Err(error)
+            }
+        }
+    }
+}
+
+struct PendingCall<V> {
+    reply: Option<Box<dyn FnOnce(AsyncMethodCallResult<V>)>>,
+    timeout: Option<Handle>,
+    engine_destroyed: Option<Handle>,
+    // Set when engine is destroyed; Only the scheduled EngineDestroyed
+    // completion may complete the call afterwards
+    destroyed: bool,
+}
+
+impl<V> PendingCall<V> {
+    // First of reply, timeout and engine destruction completes the call.
+    fn complete(state: &Rc<RefCell<Self>>, result: AsyncMethodCallResult<V>) {
+        if state.borrow().destroyed && !matches!(result, Err(AsyncMethodCallError::EngineDestroyed))
+        {
+            return;
+        }
+        let reply = state.borrow_mut().reply.take();
+        Self::cancel(state);
+        if let Some(reply) = reply {
+            reply(result);
+        }
+    }
+
+    fn cancel(state: &Rc<RefCell<Self>>) {
+        let (reply, timeout, engine_destroyed) = {
+            let mut state = state.borrow_mut();
+            (
+                state.reply.take(),
+                state.timeout.take(),
+                state.engine_destroyed.take(),
+            )
+        };
+        // dropped outside of the borrow
+        drop((reply, timeout, engine_destroyed));
+    }
 }
 
 //
@@ -99,15 +269,39 @@ impl<V> EventSender<V> {
         }
     }
 
+    pub fn to_thread_safe(&self) -> Result<ThreadSafeEventSender<V>> {
+        let context = self.context.get().ok_or(Error::InvalidContext)?;
+        let sender = context.run_loop.borrow().new_sender();
+        Ok(ThreadSafeEventSender::new(
+            sender,
+            self.engine_handle,
+            self.channel_name.clone(),
+            self.codec,
+        ))
+    }
+
     pub fn send_event(&self, message: &V) -> Result<()> {
+        self.post_encoded(&self.codec.encode_success_envelope(message))
+    }
+
+    // Delivered to Dart stream listener as PlatformException.
+    pub fn send_error(&self, code: &str, message: Option<&str>, details: &V) -> Result<()> {
+        self.post_encoded(&self.codec.encode_error_envelope(code, message, details))
+    }
+
+    // Closes the stream on Dart side.
+    pub fn end_of_stream(&self) -> Result<()> {
+        self.post_encoded(&[])
+    }
+
+    fn post_encoded(&self, encoded: &[u8]) -> Result<()> {
         if let Some(context) = self.context.get() {
-            let encoded = self.codec.encode_success_envelope(message);
             let engine_manager = context.engine_manager.borrow();
             let engine = engine_manager.get_engine(self.engine_handle);
             if let Some(engine) = engine {
                 engine
                     .binary_messenger()
-                    .post_message(&self.channel_name, &encoded)
+                    .post_message(&self.channel_name, encoded)
             } else {
                 Err(Error::InvalidEngineHandle)
             }
@@ -147,6 +341,17 @@ impl<V> MessageSender<V> {
         }
     }
 
+    pub fn to_thread_safe(&self) -> Result<ThreadSafeMessageSender<V>> {
+        let context = self.context.get().ok_or(Error::InvalidContext)?;
+        let sender = context.run_loop.borrow().new_sender();
+        Ok(ThreadSafeMessageSender::new(
+            sender,
+            self.engine_handle,
+            self.channel_name.clone(),
+            self.codec,
+        ))
+    }
+
     pub fn send_message<F>(&self, message: &V, reply: F) -> Result<()>
     where
         F: FnOnce(V) + 'static,
@@ -160,9 +365,9 @@ impl<V> MessageSender<V> {
                 engine.binary_messenger().send_message(
                     &self.channel_name,
                     &encoded,
-                    move |message| {
-                        let message = codec.decode_message(message).unwrap();
-                        reply(message);
+                    move |message| match codec.decode_message(message) {
+                        Some(message) => reply(message),
+                        None => error!("Received malformed message reply"),
                     },
                 )
             } else {
@@ -190,3 +395,179 @@ impl<V> MessageSender<V> {
         }
     }
 }
+
+//
+//
+//
+
+// Sends raw message data; There is no codec involved, the data is not copied.
+#[derive(Clone)]
+pub struct BinaryMessageSender {
+    context: Context,
+    engine_handle: EngineHandle,
+    channel_name: String,
+}
+
+impl BinaryMessageSender {
+    pub fn new(context: Context, engine_handle: EngineHandle, channel_name: String) -> Self {
+        Self {
+            context,
+            engine_handle,
+            channel_name,
+        }
+    }
+
+    pub fn send_message<F>(&self, message: &[u8], reply: F) -> Result<()>
+    where
+        F: FnOnce(&[u8]) + 'static,
+    {
+        if let Some(context) = self.context.get() {
+            let engine_manager = context.engine_manager.borrow();
+            let engine = engine_manager.get_engine(self.engine_handle);
+            if let Some(engine) = engine {
+                engine
+                    .binary_messenger()
+                    .send_message(&self.channel_name, message, reply)
+            } else {
+                Err(Error::InvalidEngineHandle)
+            }
+        } else {
+            Err(Error::InvalidContext)
+        }
+    }
+
+    pub fn post_message(&self, message: &[u8]) -> Result<()> {
+        if let Some(context) = self.context.get() {
+            let engine_manager = context.engine_manager.borrow();
+            let engine = engine_manager.get_engine(self.engine_handle);
+            if let Some(engine) = engine {
+                engine
+                    .binary_messenger()
+                    .post_message(&self.channel_name, message)
+            } else {
+                Err(Error::InvalidEngineHandle)
+            }
+        } else {
+            Err(Error::InvalidContext)
+        }
+    }
+}
+
+#[cfg(all(test, feature = "null-backend"))]
+mod tests {
+    use std::{cell::RefCell, rc::Rc, thread, time::Duration};
+
+    use crate::{
+        codec::Value,
+        shell::{AsyncMethodCallError, AsyncMethodCallResult, Context, ContextOptions, FakeDart},
+    };
+
+    type Results = Rc<RefCell<Vec<AsyncMethodCallResult<Value>>>>;
+
+    fn collect(results: &Results) -> impl FnOnce(AsyncMethodCallResult<Value>) + 'static {
+        let results = results.clone();
+        move |r| results.borrow_mut().push(r)
+    }
+
+    #[test]
+    fn test_call_method_with_timeout() {
+        let context = Context::new(ContextOptions::default()).unwrap();
+        let dart = FakeDart::new(&context).unwrap();
+        dart.set_method_handler("dart", |call| Ok(call.args));
+        let invoker = context
+            .message_manager
+            .borrow()
+            .get_method_invoker(dart.engine(), "dart");
+        let results: Results = Rc::new(RefCell::new(Vec::new()));
+
+        // Reply arrives before timeout
+        let handle = invoker
+            .call_method_with_timeout(
+                "echo",
+                Value::I64(1),
+                Some(Duration::from_secs(60)),
+                collect(&results),
+            )
+            .unwrap();
+        dart.pump();
+        assert!(matches!(
+            results
+                .borrow_mut()
+                .drain(..)
+                .collect::<Vec<_>>()
+                .as_slice(),
+            [Ok(Value::I64(1))]
+        ));
+        drop(handle);
+
+        // Timer is due before the call is delivered; Late reply is ignored
+        let handle = invoker
+            .call_method_with_timeout(
+                "echo",
+                Value::I64(2),
+                Some(Duration::from_millis(1)),
+                collect(&results),
+            )
+            .unwrap();
+        thread::sleep(Duration::from_millis(5));
+        dart.pump();
+        assert!(matches!(
+            results
+                .borrow_mut()
+                .drain(..)
+                .collect::<Vec<_>>()
+                .as_slice(),
+            [Err(AsyncMethodCallError::Timeout)]
+        ));
+        assert_eq!(dart.take_method_calls("dart").len(), 2);
+        drop(handle);
+
+        // Dropping handle cancels the call
+        let handle = invoker
+            .call_method_with_timeout("echo", Value::I64(3), None, collect(&results))
+            .unwrap();
+        drop(handle);
+        dart.pump();
+        assert!(results.borrow().is_empty());
+        assert_eq!(dart.take_method_calls("dart").len(), 1);
+
+        dart.shut_down().unwrap();
+    }
+
+    #[test]
+    fn test_call_method_engine_destroyed() {
+        let context = Context::new(ContextOptions::default()).unwrap();
+        let dart = FakeDart::new(&context).unwrap();
+        let other = FakeDart::new(&context).unwrap();
+        let results: Results = Rc::new(RefCell::new(Vec::new()));
+
+        let results_clone = results.clone();
+        let context_clone = context.weak();
+        let _handle = context
+            .message_manager
+            .borrow()
+            .get_method_invoker(dart.engine(), "dart")
+            .call_method_with_timeout(
+                "echo",
+                Value::Null,
+                Some(Duration::from_secs(60)),
+                move |r| {
+                    // Engine manager must not be borrowed while replying
+                    let context = context_clone.get().unwrap();
+                    assert_eq!(context.engine_manager.borrow().get_all_engines().len(), 1);
+                    results_clone.borrow_mut().push(r);
+                },
+            )
+            .unwrap();
+
+        dart.shut_down().unwrap();
+        assert!(results.borrow().is_empty());
+        other.pump();
+        assert!(matches!(
+            results.borrow().as_slice(),
+            [Err(AsyncMethodCallError::EngineDestroyed)]
+        ));
+
+        other.shut_down().unwrap();
+    }
+}
@@ -1,10 +1,20 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
 
 use log::error;
+use once_cell::sync::Lazy;
+
+use crate::{util::OkLog, Result};
 
 // Based on code from flutter-rs
 
-use super::{MessageCodec, MethodCall, MethodCallError, MethodCallResult, MethodCodec, Value};
+use super::{
+    value::{CustomValue, TypedListElement, TypedListRef, ValueError, ValueRef},
+    DecodeError, DecodeErrorKind, MessageCodec, MethodCall, MethodCallError, MethodCallResult,
+    MethodCodec, Value,
+};
 
 const VALUE_NULL: u8 = 0;
 const VALUE_TRUE: u8 = 1;
@@ -20,57 +30,75 @@ const VALUE_INT64LIST: u8 = 10;
 const VALUE_FLOAT64LIST: u8 = 11;
 const VALUE_LIST: u8 = 12;
 const VALUE_MAP: u8 = 13;
+const VALUE_FLOAT32LIST: u8 = 14;
+
+// Type tags from this value up are available for custom types
+const VALUE_FIRST_CUSTOM: u8 = 128;
 
 pub struct StandardMethodCodec;
-#[derive(Debug)]
-enum DecodeError {
-    Invalid,
-    Ended,
+
+// Limits recursion when decoding nested lists and maps so that malicious or
+// corrupted message can not overflow the stack.
+const MAX_NESTING_DEPTH: usize = 128;
+
+// Encoder and decoder for a custom type tag, equivalent of overriding writeValue
+// and readValueOfType in Dart StandardMessageCodec subclass. The type tag itself
+// is written and read by the codec; Extension only handles the payload.
+pub trait StandardCodecExtension: Send + Sync {
+    fn write_value(&self, writer: &mut StandardCodecWriter, value: &Value);
+    fn read_value(
+        &self,
+        reader: &mut StandardCodecReader,
+    ) -> std::result::Result<Value, DecodeError>;
 }
 
+static EXTENSIONS: Lazy<RwLock<HashMap<u8, Arc<dyn StandardCodecExtension>>>> =
+    Lazy::new(Default::default);
+
 impl MessageCodec<Value> for StandardMethodCodec {
     fn encode_message(&self, v: &Value) -> Vec<u8> {
-        let mut writer = Writer::new(Vec::new());
+        let mut writer = StandardCodecWriter::new(Vec::new());
         StandardMethodCodec::write_value(&mut writer, v);
-        writer.0
+        writer.data
     }
 
     fn decode_message(&self, buf: &[u8]) -> Option<Value> {
-        let mut reader = Reader::new(buf);
-        StandardMethodCodec::read_value(&mut reader).ok()
+        self.try_decode_message(buf).ok_log()
     }
 }
 
 impl MethodCodec<Value> for StandardMethodCodec {
     fn encode_method_call(&self, v: &MethodCall<Value>) -> Vec<u8> {
-        let mut writer = Writer::new(Vec::new());
+        let mut writer = StandardCodecWriter::new(Vec::new());
 
         StandardMethodCodec::write_string(&mut writer, &v.method);
         StandardMethodCodec::write_value(&mut writer, &v.args);
-        writer.0
+        writer.data
     }
 
     fn decode_method_call(&self, buf: &[u8]) -> Option<MethodCall<Value>> {
-        let mut reader = Reader::new(buf);
-        let method: Value = StandardMethodCodec::read_value(&mut reader).unwrap();
-        let args: Value = StandardMethodCodec::read_value(&mut reader).unwrap();
+        self.try_decode_method_call(buf).ok_log()
+    }
 
-        if let Value::String(method) = method {
-            return Some(MethodCall { method, args });
-        }
-        error!("Invalid method call");
-        None
+    fn decode_method_call_or_error(
+        &self,
+        buf: &[u8],
+    ) -> std::result::Result<MethodCall<Value>, Option<Vec<u8>>> {
+        Self::read_method_call(buf).map_err(|err| {
+            error!("Invalid method call: {err}");
+            Some(err.encode_envelope(self))
+        })
     }
 
     fn encode_success_envelope(&self, result: &Value) -> Vec<u8> {
-        let mut writer = Writer::new(Vec::new());
+        let mut writer = StandardCodecWriter::new(Vec::new());
         writer.write_u8(0);
         StandardMethodCodec::write_value(&mut writer, result);
-        writer.0
+        writer.data
     }
 
     fn encode_error_envelope(&self, code: &str, message: Option<&str>, v: &Value) -> Vec<u8> {
-        let mut writer = Writer::new(Vec::new());
+        let mut writer = StandardCodecWriter::new(Vec::new());
         writer.write_u8(1);
         StandardMethodCodec::write_value(&mut writer, &Value::String(code.to_owned()));
         match message {
@@ -80,20 +108,111 @@ impl MethodCodec<Value> for StandardMethodCodec {
             None => StandardMethodCodec::write_value(&mut writer, &Value::Null),
         }
         StandardMethodCodec::write_value(&mut writer, v);
-        writer.0
+        writer.data
     }
 
     fn decode_envelope(&self, buf: &[u8]) -> Option<MethodCallResult<Value>> {
-        let mut reader = Reader::new(buf);
-        let n = reader.read_u8();
+        self.try_decode_envelope(buf).ok_log()
+    }
+}
+
+impl StandardMethodCodec {
+    // Like encode_message, but fails instead of encoding null in place of
+    // custom values without registered extension.
+    pub fn try_encode_message(&self, v: &Value) -> Result<Vec<u8>> {
+        let mut writer = StandardCodecWriter::new(Vec::new());
+        StandardMethodCodec::write_value(&mut writer, v);
+        match writer.unknown_type {
+            Some(type_tag) => Err(ValueError::Message(format!(
+                "No extension registered for custom type {type_tag}"
+            ))
+            .into()),
+            None => Ok(writer.data),
+        }
+    }
+
+    // Like decode_message, but reports where and why decoding failed.
+    pub fn try_decode_message(&self, buf: &[u8]) -> Result<Value> {
+        // Empty message decodes to null, same as in Flutter
+        if buf.is_empty() {
+            return Ok(Value::Null);
+        }
+        let mut reader = StandardCodecReader::new(buf);
+        Ok(Self::read_value(&mut reader)?)
+    }
+
+    // Decodes message without copying strings, byte lists and typed lists;
+    // The returned value borrows from the message buffer.
+    pub fn try_decode_message_ref<'a>(&self, buf: &'a [u8]) -> Result<ValueRef<'a>> {
+        if buf.is_empty() {
+            return Ok(ValueRef::Null);
+        }
+        let mut reader = StandardCodecReader::new(buf);
+        Ok(Self::read_value_ref(&mut reader)?)
+    }
+
+    pub fn try_decode_method_call(&self, buf: &[u8]) -> Result<MethodCall<Value>> {
+        Ok(Self::read_method_call(buf)?)
+    }
+
+    pub fn try_decode_method_call_ref<'a>(
+        &self,
+        buf: &'a [u8],
+    ) -> Result<MethodCall<ValueRef<'a>>> {
+        Ok(Self::read_method_call_ref(buf)?)
+    }
+
+    fn read_method_call(buf: &[u8]) -> std::result::Result<MethodCall<Value>, DecodeError> {
+        let mut reader = StandardCodecReader::new(buf);
+        let offset = reader.pos;
+        let method = Self::read_value(&mut reader)?;
+        let args = Self::read_value(&mut reader)?;
+
+        match method {
+            Value::String(method) => Ok(MethodCall { method, args }),
+            _ => Err(DecodeError {
+                offset,
+                kind: DecodeErrorKind::UnexpectedValue {
+                    expected: "method name",
+                },
+            }),
+        }
+    }
+
+    pub(crate) fn read_method_call_ref(
+        buf: &[u8],
+    ) -> std::result::Result<MethodCall<ValueRef<'_>>, DecodeError> {
+        let mut reader = StandardCodecReader::new(buf);
+        let offset = reader.pos;
+        let method = Self::read_value_ref(&mut reader)?;
+        let args = Self::read_value_ref(&mut reader)?;
+
+        match method {
+            ValueRef::String(method) => Ok(MethodCall {
+                method: method.into(),
+                args,
+            }),
+            _ => Err(DecodeError {
+                offset,
+                kind: DecodeErrorKind::UnexpectedValue {
+                    expected: "method name",
+                },
+            }),
+        }
+    }
+
+    pub fn try_decode_envelope(&self, buf: &[u8]) -> Result<MethodCallResult<Value>> {
+        let mut reader = StandardCodecReader::new(buf);
+        let offset = reader.pos;
+        let n = reader.read_u8("envelope")?;
         if n == 0 {
-            let ret = StandardMethodCodec::read_value(&mut reader).unwrap();
-            Some(MethodCallResult::Ok(ret))
+            let ret = Self::read_value(&mut reader)?;
+            Ok(MethodCallResult::Ok(ret))
         } else if n == 1 {
-            let code = StandardMethodCodec::read_value(&mut reader).unwrap();
-            let message = StandardMethodCodec::read_value(&mut reader).unwrap();
-            let details = StandardMethodCodec::read_value(&mut reader).unwrap();
-            Some(MethodCallResult::Err(MethodCallError {
+            let code = Self::read_value(&mut reader)?;
+            let message = Self::read_value(&mut reader)?;
+            let details = Self::read_value(&mut reader)?;
+            Ok(MethodCallResult::Err(MethodCallError {
                 code: match code {
                     Value::String(s) => s,
                     _ => "".into(),
@@ -105,86 +224,216 @@ impl MethodCodec<Value> for StandardMethodCodec {
                 details,
             }))
         } else {
-            None
+            Err(DecodeError {
+                offset,
+                kind: DecodeErrorKind::UnexpectedValue {
+                    expected: "envelope",
+                },
+            }
+            .into())
         }
     }
-}
 
-impl StandardMethodCodec {
-    fn read_value(reader: &mut Reader) -> Result<Value, DecodeError> {
-        if reader.ended() {
-            return Err(DecodeError::Ended);
+    // Large integers are encoded as hexadecimal strings; Values that fit in i64
+    // are returned as numbers, other as strings.
+    fn read_large_int<'a>(
+        reader: &mut StandardCodecReader<'a>,
+    ) -> std::result::Result<std::result::Result<i64, &'a str>, DecodeError> {
+        let len = reader.read_size("large int")?;
+        let string = reader.read_str(len, "large int")?;
+        Ok(i64::from_str_radix(string, 16).map_err(|_| string))
+    }
+
+    fn read_custom(
+        reader: &mut StandardCodecReader,
+        offset: usize,
+        type_tag: u8,
+    ) -> std::result::Result<CustomValue, DecodeError> {
+        match Self::get_extension(type_tag) {
+            Some(extension) => reader.read_nested(offset, |reader| {
+                Ok(CustomValue {
+                    type_tag,
+                    value: Box::new(extension.read_value(reader)?),
+                })
+            }),
+            None => Err(DecodeError {
+                offset,
+                kind: DecodeErrorKind::UnknownType(type_tag),
+            }),
         }
+    }
 
-        let t = reader.read_u8();
+    fn read_value(reader: &mut StandardCodecReader) -> std::result::Result<Value, DecodeError> {
+        let offset = reader.pos;
+        let t = reader.read_u8("value")?;
         Ok(match t {
             VALUE_NULL => Value::Null,
             VALUE_FALSE => Value::Bool(false),
             VALUE_TRUE => Value::Bool(true),
-            VALUE_INT32 => Value::I64(reader.read_i32().into()),
-            VALUE_INT64 => Value::I64(reader.read_i64()),
-            VALUE_LARGEINT => panic!("Not implemented"),
+            VALUE_INT32 => Value::I64(reader.read_i32("int32")?.into()),
+            VALUE_INT64 => Value::I64(reader.read_i64("int64")?),
+            VALUE_LARGEINT => match Self::read_large_int(reader)? {
+                Ok(value) => Value::I64(value),
+                Err(string) => Value::String(string.into()),
+            },
             VALUE_FLOAT64 => {
                 reader.align_to(8);
-                Value::F64(reader.read_f64())
+                Value::F64(reader.read_f64("float64")?)
             }
             VALUE_STRING => {
-                let len = reader.read_size();
-                Value::String(reader.read_string(len))
+                let len = reader.read_size("string")?;
+                Value::String(reader.read_string(len, "string")?)
             }
             VALUE_UINT8LIST => {
-                let len = reader.read_size();
-                Value::U8List(reader.read_u8_list(len))
+                let len = reader.read_size("uint8 list")?;
+                Value::U8List(reader.read_bytes(len, "uint8 list")?.to_vec())
             }
             VALUE_INT32LIST => {
-                let len = reader.read_size();
-                Value::I32List(reader.read_i32_list(len))
+                let len = reader.read_size("int32 list")?;
+                Value::I32List(reader.read_typed_list(len, "int32 list")?.to_vec())
             }
             VALUE_INT64LIST => {
-                let len = reader.read_size();
-                Value::I64List(reader.read_i64_list(len))
+                let len = reader.read_size("int64 list")?;
+                Value::I64List(reader.read_typed_list(len, "int64 list")?.to_vec())
             }
             VALUE_FLOAT64LIST => {
-                let len = reader.read_size();
-                Value::F64List(reader.read_f64_list(len))
+                let len = reader.read_size("float64 list")?;
+                Value::F64List(reader.read_typed_list(len, "float64 list")?.to_vec())
             }
-            VALUE_LIST => {
-                let len = reader.read_size();
-                let mut list = Vec::new();
+            VALUE_LIST => reader.read_nested(offset, |reader| {
+                let len = reader.read_size("list")?;
+                // every value takes at least one byte
+                reader.check_remaining(len, "list")?;
+                let mut list = Vec::with_capacity(len);
                 for _ in 0..len {
-                    if let Ok(e) = Self::read_value(reader) {
-                        list.push(e);
-                    } else {
-                        return Err(DecodeError::Invalid);
-                    }
+                    list.push(Self::read_value(reader)?);
                 }
-                Value::List(list)
-            }
-            VALUE_MAP => {
-                let len = reader.read_size();
-                let mut map = HashMap::new();
+                Ok(Value::List(list))
+            })?,
+            VALUE_MAP => reader.read_nested(offset, |reader| {
+                let len = reader.read_size("map")?;
+                // every entry takes at least two bytes
+                reader.check_remaining(len.saturating_mul(2), "map")?;
+                let mut map = HashMap::with_capacity(len);
                 for _ in 0..len {
-                    let k = Self::read_value(reader);
-                    let v = Self::read_value(reader);
-                    if k.is_err() || v.is_err() {
-                        return Err(DecodeError::Invalid);
-                    }
-                    let k = k.unwrap();
-                    let v = v.unwrap();
-
+                    let k = Self::read_value(reader)?;
+                    let v = Self::read_value(reader)?;
                     map.insert(k, v);
                 }
-                Value::Map(map)
+                Ok(Value::Map(map))
+            })?,
+            VALUE_FLOAT32LIST => {
+                let len = reader.read_size("float32 list")?;
+                Value::F32List(reader.read_typed_list(len, "float32 list")?.to_vec())
+            }
+            t => Value::Custom(Self::read_custom(reader, offset, t)?),
+        })
+    }
+
+    fn read_value_ref<'a>(
+        reader: &mut StandardCodecReader<'a>,
+    ) -> std::result::Result<ValueRef<'a>, DecodeError> {
+        let offset = reader.pos;
+        let t = reader.read_u8("value")?;
+        Ok(match t {
+            VALUE_NULL => ValueRef::Null,
+            VALUE_FALSE => ValueRef::Bool(false),
+            VALUE_TRUE => ValueRef::Bool(true),
+            VALUE_INT32 => ValueRef::I64(reader.read_i32("int32")?.into()),
+            VALUE_INT64 => ValueRef::I64(reader.read_i64("int64")?),
+            VALUE_LARGEINT => match Self::read_large_int(reader)? {
+                Ok(value) => ValueRef::I64(value),
+                Err(string) => ValueRef::String(string),
+            },
+            VALUE_FLOAT64 => {
+                reader.align_to(8);
+                ValueRef::F64(reader.read_f64("float64")?)
+            }
+            VALUE_STRING => {
+                let len = reader.read_size("string")?;
+                ValueRef::String(reader.read_str(len, "string")?)
+            }
+            VALUE_UINT8LIST => {
+                let len = reader.read_size("uint8 list")?;
+                ValueRef::U8List(reader.read_bytes(len, "uint8 list")?)
+            }
+            VALUE_INT32LIST => {
+                let len = reader.read_size("int32 list")?;
+                ValueRef::I32List(reader.read_typed_list(len, "int32 list")?)
+            }
+            VALUE_INT64LIST => {
+                let len = reader.read_size("int64 list")?;
+                ValueRef::I64List(reader.read_typed_list(len, "int64 list")?)
+            }
+            VALUE_FLOAT64LIST => {
+                let len = reader.read_size("float64 list")?;
+                ValueRef::F64List(reader.read_typed_list(len, "float64 list")?)
+            }
+            VALUE_LIST => reader.read_nested(offset, |reader| {
+                let len = reader.read_size("list")?;
+                // every value takes at least one byte
+                reader.check_remaining(len, "list")?;
+                let mut list = Vec::with_capacity(len);
+                for _ in 0..len {
+                    list.push(Self::read_value_ref(reader)?);
+                }
+                Ok(ValueRef::List(list))
+            })?,
+            VALUE_MAP => reader.read_nested(offset, |reader| {
+                let len = reader.read_size("map")?;
+                // every entry takes at least two bytes
+                reader.check_remaining(len.saturating_mul(2), "map")?;
+                let mut map = Vec::with_capacity(len);
+                for _ in 0..len {
+                    let k = Self::read_value_ref(reader)?;
+                    let v = Self::read_value_ref(reader)?;
+                    map.push((k, v));
+                }
+                Ok(ValueRef::Map(map))
+            })?,
+            VALUE_FLOAT32LIST => {
+                let len = reader.read_size("float32 list")?;
+                ValueRef::F32List(reader.read_typed_list(len, "float32 list")?)
             }
-            _ => Value::Null,
+            t => ValueRef::Custom(Self::read_custom(reader, offset, t)?),
         })
     }
-    fn write_string(writer: &mut Writer, s: &str) {
+
+    // Registers encoder and decoder for custom type; Type tag must be at least 128,
+    // lower values are reserved for standard types. Extensions are global and used
+    // by all channels that use StandardMethodCodec.
+    pub fn register_extension<E>(type_tag: u8, extension: E)
+    where
+        E: StandardCodecExtension + 'static,
+    {
+        assert!(
+            type_tag >= VALUE_FIRST_CUSTOM,
+            "Type tag {} is reserved for standard types",
+            type_tag
+        );
+        EXTENSIONS
+            .write()
+            .unwrap()
+            .insert(type_tag, Arc::new(extension));
+    }
+
+    pub fn unregister_extension(type_tag: u8) {
+        EXTENSIONS.write().unwrap().remove(&type_tag);
+    }
+
+    fn get_extension(type_tag: u8) -> Option<Arc<dyn StandardCodecExtension>> {
+        if type_tag < VALUE_FIRST_CUSTOM {
+            return None;
+        }
+        EXTENSIONS.read().unwrap().get(&type_tag).cloned()
+    }
+
+    fn write_string(writer: &mut StandardCodecWriter, s: &str) {
         writer.write_u8(VALUE_STRING);
         writer.write_size(s.len());
         writer.write_string(s);
     }
-    fn write_value(writer: &mut Writer, v: &Value) {
+    fn write_value(writer: &mut StandardCodecWriter, v: &Value) {
         match v {
             Value::Null => {
                 writer.write_u8(VALUE_NULL);
@@ -241,6 +490,14 @@ impl StandardMethodCodec {
                     writer.write_f64(*n);
                 }
             }
+            Value::F32List(list) => {
+                writer.write_u8(VALUE_FLOAT32LIST);
+                writer.write_size(list.len());
+                writer.align_to(4);
+                for n in list {
+                    writer.write_f32(*n);
+                }
+            }
             Value::List(list) => {
                 writer.write_u8(VALUE_LIST);
                 writer.write_size(list.len());
@@ -256,38 +513,66 @@ impl StandardMethodCodec {
                     Self::write_value(writer, v);
                 });
             }
+            Value::Custom(custom) => match Self::get_extension(custom.type_tag) {
+                Some(extension) => {
+                    writer.write_u8(custom.type_tag);
+                    extension.write_value(writer, &custom.value);
+                }
+                None => {
+                    // Nothing would be able to decode the value, not even this
+                    // codec; Write null instead
+                    error!(
+                        "No extension registered for custom type {}, encoding null",
+                        custom.type_tag
+                    );
+                    writer.unknown_type.get_or_insert(custom.type_tag);
+                    writer.write_u8(VALUE_NULL);
+                }
+            },
         }
     }
 }
 
-struct Writer(Vec<u8>);
+// Writer used to encode messages; Passed to StandardCodecExtension to write
+// custom values.
+pub struct StandardCodecWriter {
+    data: Vec<u8>,
+    // First custom type tag that had no extension registered
+    unknown_type: Option<u8>,
+}
 
-impl Writer {
-    fn new(v: Vec<u8>) -> Self {
-        Writer(v)
+impl StandardCodecWriter {
+    fn new(data: Vec<u8>) -> Self {
+        StandardCodecWriter {
+            data,
+            unknown_type: None,
+        }
+    }
+    pub fn write_u8(&mut self, n: u8) {
+        self.data.push(n);
     }
-    fn write_u8(&mut self, n: u8) {
-        self.0.push(n);
+    pub fn write_u16(&mut self, n: u16) {
+        self.data.extend_from_slice(&n.to_ne_bytes());
     }
-    fn write_u16(&mut self, n: u16) {
-        self.0.extend_from_slice(&n.to_ne_bytes());
+    pub fn write_u32(&mut self, n: u32) {
+        self.data.extend_from_slice(&n.to_ne_bytes());
     }
-    fn write_u32(&mut self, n: u32) {
-        self.0.extend_from_slice(&n.to_ne_bytes());
+    pub fn write_i32(&mut self, n: i32) {
+        self.data.extend_from_slice(&n.to_ne_bytes());
     }
-    fn write_i32(&mut self, n: i32) {
-        self.0.extend_from_slice(&n.to_ne_bytes());
+    pub fn write_u64(&mut self, n: u64) {
+        self.data.extend_from_slice(&n.to_ne_bytes());
     }
-    fn write_u64(&mut self, n: u64) {
-        self.0.extend_from_slice(&n.to_ne_bytes());
+    pub fn write_i64(&mut self, n: i64) {
+        self.data.extend_from_slice(&n.to_ne_bytes());
     }
-    fn write_i64(&mut self, n: i64) {
-        self.0.extend_from_slice(&n.to_ne_bytes());
+    pub fn write_f32(&mut self, n: f32) {
+        self.write_u32(n.to_bits());
     }
-    fn write_f64(&mut self, n: f64) {
+    pub fn write_f64(&mut self, n: f64) {
         self.write_u64(n.to_bits());
     }
-    fn write_size(&mut self, n: usize) {
+    pub fn write_size(&mut self, n: usize) {
         if n < 254 {
             self.write_u8(n as u8);
         } else if n <= u16::max_value() as usize {
@@ -301,11 +586,17 @@ impl Writer {
             panic!("Not implemented");
         }
     }
-    fn write_string(&mut self, s: &str) {
-        self.0.extend_from_slice(s.as_bytes());
+    pub fn write_string(&mut self, s: &str) {
+        self.data.extend_from_slice(s.as_bytes());
+    }
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.data.extend_from_slice(bytes);
+    }
+    pub fn write_value(&mut self, value: &Value) {
+        StandardMethodCodec::write_value(self, value);
     }
-    fn align_to(&mut self, align: usize) {
-        let m = self.0.len() % align;
+    pub fn align_to(&mut self, align: usize) {
+        let m = self.data.len() % align;
         if m == 0 {
             return;
         }
@@ -316,102 +607,123 @@ impl Writer {
     }
 }
 
-struct Reader<'a> {
+// Bounds checked reader over message buffer; Reads past the end of message fail
+// with DecodeError instead of panicking.
+pub struct StandardCodecReader<'a> {
     buf: &'a [u8],
     pos: usize,
+    depth: usize,
 }
 
-impl<'a> Reader<'a> {
+type ReadResult<T> = std::result::Result<T, DecodeError>;
+
+impl<'a> StandardCodecReader<'a> {
     fn new(buf: &'a [u8]) -> Self {
-        Reader { buf, pos: 0 }
-    }
-    fn read_u8(&mut self) -> u8 {
-        let n = self.buf[self.pos];
-        self.pos += 1;
-        n
-    }
-    fn read_u16(&mut self) -> u16 {
-        self.pos += 2;
-        let s = &self.buf[self.pos - 2..self.pos];
-        u16::from_ne_bytes(clone_into_array(s))
-    }
-    fn read_u32(&mut self) -> u32 {
-        self.pos += 4;
-        let s = &self.buf[self.pos - 4..self.pos];
-        u32::from_ne_bytes(clone_into_array(s))
-    }
-    fn read_i32(&mut self) -> i32 {
-        self.pos += 4;
-        let s = &self.buf[self.pos - 4..self.pos];
-        i32::from_ne_bytes(clone_into_array(s))
-    }
-    fn read_u64(&mut self) -> u64 {
-        self.pos += 8;
-        let s = &self.buf[self.pos - 8..self.pos];
-        u64::from_ne_bytes(clone_into_array(s))
-    }
-    fn read_i64(&mut self) -> i64 {
-        self.pos += 8;
-        let s = &self.buf[self.pos - 8..self.pos];
-        i64::from_ne_bytes(clone_into_array(s))
-    }
-    fn read_f64(&mut self) -> f64 {
-        let n = self.read_u64();
-        f64::from_bits(n)
-    }
-    fn read_size(&mut self) -> usize {
-        let n = self.read_u8();
-        match n {
-            254 => self.read_u16() as usize,
-            255 => self.read_u32() as usize,
-            _ => n as usize,
+        StandardCodecReader {
+            buf,
+            pos: 0,
+            depth: 0,
         }
     }
-    fn read_string(&mut self, len: usize) -> String {
-        if len == 0 {
-            String::from("")
+    fn check_remaining(&self, len: usize, expected: &'static str) -> ReadResult<()> {
+        if self.pos <= self.buf.len() && len <= self.buf.len() - self.pos {
+            Ok(())
         } else {
-            let v = &self.buf[self.pos..self.pos + len];
-            self.pos += len;
-            String::from_utf8_lossy(v).to_string()
+            Err(DecodeError {
+                // position may be past the end after alignment
+                offset: self.pos.min(self.buf.len()),
+                kind: DecodeErrorKind::UnexpectedEnd { expected },
+            })
         }
     }
-    fn read_u8_list(&mut self, len: usize) -> Vec<u8> {
-        let mut v = Vec::with_capacity(len);
-        for _ in 0..len {
-            v.push(self.read_u8());
-        }
-        v
+    pub fn read_bytes(&mut self, len: usize, expected: &'static str) -> ReadResult<&'a [u8]> {
+        self.check_remaining(len, expected)?;
+        let res = &self.buf[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(res)
     }
-    fn read_i32_list(&mut self, len: usize) -> Vec<i32> {
-        let mut v = Vec::with_capacity(len);
-        self.align_to(4);
-        for _ in 0..len {
-            v.push(self.read_i32());
-        }
-        v
+    pub fn read_u8(&mut self, expected: &'static str) -> ReadResult<u8> {
+        Ok(self.read_bytes(1, expected)?[0])
     }
-    fn read_i64_list(&mut self, len: usize) -> Vec<i64> {
-        let mut v = Vec::with_capacity(len);
-        self.align_to(8);
-        for _ in 0..len {
-            v.push(self.read_i64());
-        }
-        v
-    }
-    fn read_f64_list(&mut self, len: usize) -> Vec<f64> {
-        let mut v = Vec::with_capacity(len);
-        self.align_to(8);
-        for _ in 0..len {
-            let n = self.read_u64();
-            v.push(f64::from_bits(n));
-        }
-        v
+    pub fn read_u16(&mut self, expected: &'static str) -> ReadResult<u16> {
+        let s = self.read_bytes(2, expected)?;
+        Ok(u16::from_ne_bytes(clone_into_array(s)))
+    }
+    pub fn read_u32(&mut self, expected: &'static str) -> ReadResult<u32> {
+        let s = self.read_bytes(4, expected)?;
+        Ok(u32::from_ne_bytes(clone_into_array(s)))
+    }
+    pub fn read_i32(&mut self, expected: &'static str) -> ReadResult<i32> {
+        let s = self.read_bytes(4, expected)?;
+        Ok(i32::from_ne_bytes(clone_into_array(s)))
+    }
+    pub fn read_u64(&mut self, expected: &'static str) -> ReadResult<u64> {
+        let s = self.read_bytes(8, expected)?;
+        Ok(u64::from_ne_bytes(clone_into_array(s)))
+    }
+    pub fn read_i64(&mut self, expected: &'static str) -> ReadResult<i64> {
+        let s = self.read_bytes(8, expected)?;
+        Ok(i64::from_ne_bytes(clone_into_array(s)))
     }
-    fn ended(&self) -> bool {
-        self.pos >= self.buf.len()
+    pub fn read_f32(&mut self, expected: &'static str) -> ReadResult<f32> {
+        let n = self.read_u32(expected)?;
+        Ok(f32::from_bits(n))
     }
-    fn align_to(&mut self, align: usize) {
+    pub fn read_f64(&mut self, expected: &'static str) -> ReadResult<f64> {
+        let n = self.read_u64(expected)?;
+        Ok(f64::from_bits(n))
+    }
+    pub fn read_size(&mut self, expected: &'static str) -> ReadResult<usize> {
+        let n = self.read_u8(expected)?;
+        Ok(match n {
+            254 => self.read_u16(expected)? as usize,
+            255 => self.read_u32(expected)? as usize,
+            _ => n as usize,
+        })
+    }
+    pub fn read_str(&mut self, len: usize, expected: &'static str) -> ReadResult<&'a str> {
+        let offset = self.pos;
+        let v = self.read_bytes(len, expected)?;
+        std::str::from_utf8(v).map_err(|_| DecodeError {
+            offset,
+            kind: DecodeErrorKind::InvalidUtf8,
+        })
+    }
+    pub fn read_string(&mut self, len: usize, expected: &'static str) -> ReadResult<String> {
+        self.read_str(len, expected).map(|s| s.into())
+    }
+    fn read_typed_list<T: TypedListElement>(
+        &mut self,
+        len: usize,
+        expected: &'static str,
+    ) -> ReadResult<TypedListRef<'a, T>> {
+        self.align_to(T::SIZE);
+        let bytes = self.read_bytes(len.saturating_mul(T::SIZE), expected)?;
+        Ok(TypedListRef::new(bytes))
+    }
+    pub fn read_value(&mut self) -> ReadResult<Value> {
+        StandardMethodCodec::read_value(self)
+    }
+    // Position of next byte to be read; Useful for reporting decode errors.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+    fn read_nested<T, F>(&mut self, offset: usize, f: F) -> ReadResult<T>
+    where
+        F: FnOnce(&mut Self) -> ReadResult<T>,
+    {
+        if self.depth >= MAX_NESTING_DEPTH {
+            return Err(DecodeError {
+                offset,
+                kind: DecodeErrorKind::NestingTooDeep,
+            });
+        }
+        self.depth += 1;
+        let res = f(self);
+        self.depth -= 1;
+        res
+    }
+    pub fn align_to(&mut self, align: usize) {
         let m = self.pos % align;
         if m > 0 {
             self.pos += align - m;
@@ -428,3 +740,283 @@ where
     a.as_mut().clone_from_slice(slice);
     a
 }
+
+#[cfg(test)]
+mod tests {
+    use velcro::hash_map;
+
+    use super::{
+        StandardCodecExtension, StandardCodecReader, StandardCodecWriter, StandardMethodCodec,
+    };
+    use crate::{
+        codec::{
+            value::{from_value_ref, CustomValue},
+            DecodeError, DecodeErrorKind, MessageCodec, MethodCall, MethodCodec, Value, ValueRef,
+        },
+        Error,
+    };
+
+    fn test_value() -> Value {
+        Value::Map(hash_map! {
+            "list".into(): Value::List(vec![1i64.into(), 2.5f64.into(), "x".into(), Value::Null]),
+            "bytes".into(): Value::U8List(vec![1, 2, 3]),
+            "ints".into(): Value::I32List(vec![1, -2]),
+            "longs".into(): Value::I64List(vec![1 << 40]),
+            "doubles".into(): Value::F64List(vec![0.5, -1.0]),
+            "floats".into(): Value::F32List(vec![0.25, 3.0, -8.5]),
+            Value::I64(10): Value::Map(hash_map! { "a".into(): true.into() }),
+        })
+    }
+
+    fn decode_error(buf: &[u8]) -> DecodeError {
+        match StandardMethodCodec.try_decode_message(buf) {
+            Err(Error::Decode(err)) => err,
+            res => panic!("Unexpected result {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let value = test_value();
+        let encoded = StandardMethodCodec.encode_message(&value);
+        assert_eq!(StandardMethodCodec.decode_message(&encoded), Some(value));
+
+        let encoded = StandardMethodCodec.encode_method_call(&MethodCall {
+            method: "foo".into(),
+            args: test_value(),
+        });
+        let call = StandardMethodCodec.decode_method_call(&encoded).unwrap();
+        assert_eq!(call.method, "foo");
+        assert_eq!(call.args, test_value());
+    }
+
+    #[test]
+    fn test_truncated() {
+        let encoded = StandardMethodCodec.encode_message(&test_value());
+        for len in 1..encoded.len() {
+            let err = decode_error(&encoded[..len]);
+            assert!(
+                matches!(err.kind, DecodeErrorKind::UnexpectedEnd { .. }),
+                "{}",
+                err
+            );
+            assert!(err.offset <= len);
+        }
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(
+            decode_error(&[12, 1, 42]),
+            DecodeError {
+                offset: 2,
+                kind: DecodeErrorKind::UnknownType(42)
+            }
+        );
+        assert_eq!(
+            decode_error(&[7, 2, 0xC3, 0x28]).kind,
+            DecodeErrorKind::InvalidUtf8
+        );
+        // list claiming more elements than there are bytes in message
+        assert!(matches!(
+            decode_error(&[12, 255, 255, 255, 255, 127]).kind,
+            DecodeErrorKind::UnexpectedEnd { .. }
+        ));
+        // deeply nested lists
+        let mut nested = Vec::new();
+        for _ in 0..10000 {
+            nested.extend_from_slice(&[12, 1]);
+        }
+        nested.push(0);
+        assert_eq!(decode_error(&nested).kind, DecodeErrorKind::NestingTooDeep);
+        assert!(StandardMethodCodec.decode_envelope(&[2]).is_none());
+        assert!(StandardMethodCodec
+            .decode_method_call(&[3, 1, 0, 0, 0, 0])
+            .is_none());
+    }
+
+    #[test]
+    fn test_decode_error_envelope() {
+        // method name is not a string
+        let envelope = StandardMethodCodec
+            .decode_method_call_or_error(&[3, 1, 0, 0, 0, 0])
+            .err()
+            .flatten()
+            .unwrap();
+        let error = match StandardMethodCodec.decode_envelope(&envelope) {
+            Some(Err(error)) => error,
+            other => panic!("unexpected envelope {:?}", other),
+        };
+        assert_eq!(error.code, "decode_error");
+        assert_eq!(
+            error.details,
+            Value::Map(hash_map! {
+                "offset".into(): Value::I64(0),
+                "kind".into(): "unexpectedValue".into(),
+            })
+        );
+
+        // truncated arguments
+        let mut call = StandardMethodCodec.encode_method_call(&MethodCall {
+            method: "method".into(),
+            args: Value::String("argument".into()),
+        });
+        call.truncate(call.len() - 2);
+        let envelope = StandardMethodCodec
+            .decode_method_call_or_error(&call)
+            .err()
+            .flatten()
+            .unwrap();
+        let error = match StandardMethodCodec.decode_envelope(&envelope) {
+            Some(Err(error)) => error,
+            other => panic!("unexpected envelope {:?}", other),
+        };
+        assert_eq!(
+            error.details,
+            Value::Map(hash_map! {
+                "offset".into(): Value::I64(10),
+                "kind".into(): "unexpectedEnd".into(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_large_int() {
+        let mut encoded = vec![5, 2];
+        encoded.extend_from_slice(b"7f");
+        assert_eq!(
+            StandardMethodCodec.decode_message(&encoded),
+            Some(Value::I64(127))
+        );
+
+        let large = "123456789abcdef0123456789";
+        let mut encoded = vec![5, large.len() as u8];
+        encoded.extend_from_slice(large.as_bytes());
+        assert_eq!(
+            StandardMethodCodec.decode_message(&encoded),
+            Some(Value::String(large.into()))
+        );
+    }
+
+    #[test]
+    fn test_random_input() {
+        // Simple LCG so that the test is deterministic
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next = move || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) as u8
+        };
+        for _ in 0..5000 {
+            let len = next() as usize % 64;
+            let buf: Vec<u8> = (0..len).map(|_| next() % 16).collect();
+            // owned and borrowed decoders must agree
+            assert_eq!(
+                StandardMethodCodec.try_decode_message(&buf).is_ok(),
+                StandardMethodCodec.try_decode_message_ref(&buf).is_ok()
+            );
+            let _ = StandardMethodCodec.try_decode_method_call(&buf);
+            let _ = StandardMethodCodec.try_decode_envelope(&buf);
+        }
+    }
+
+    // Encodes rectangle as four raw doubles
+    struct RectExtension;
+
+    impl StandardCodecExtension for RectExtension {
+        fn write_value(&self, writer: &mut StandardCodecWriter, value: &Value) {
+            if let Value::F64List(list) = value {
+                list.iter().for_each(|v| writer.write_f64(*v));
+            }
+        }
+
+        fn read_value(&self, reader: &mut StandardCodecReader) -> Result<Value, DecodeError> {
+            let mut list = Vec::new();
+            for _ in 0..4 {
+                list.push(reader.read_f64("rect")?);
+            }
+            Ok(Value::F64List(list))
+        }
+    }
+
+    #[test]
+    fn test_custom_type() {
+        let value = Value::List(vec![
+            Value::Custom(CustomValue {
+                type_tag: 200,
+                value: Box::new(Value::F64List(vec![1.0, 2.0, 3.0, 4.0])),
+            }),
+            Value::Null,
+        ]);
+        // without extension the value can not be encoded
+        assert!(StandardMethodCodec.try_encode_message(&value).is_err());
+        assert_eq!(
+            StandardMethodCodec.decode_message(&StandardMethodCodec.encode_message(&value)),
+            Some(Value::List(vec![Value::Null, Value::Null]))
+        );
+
+        StandardMethodCodec::register_extension(200, RectExtension);
+        let encoded = StandardMethodCodec.encode_message(&value);
+        // list tag, size, custom tag, 4 doubles, null
+        assert_eq!(encoded.len(), 3 + 32 + 1);
+        assert_eq!(
+            StandardMethodCodec.decode_message(&encoded),
+            Some(value.clone())
+        );
+        assert_eq!(
+            StandardMethodCodec.try_encode_message(&value).ok(),
+            Some(encoded.clone())
+        );
+        StandardMethodCodec::unregister_extension(200);
+
+        // tag of unregistered extension is rejected by decoder
+        assert!(matches!(
+            decode_error(&encoded).kind,
+            DecodeErrorKind::UnknownType(200)
+        ));
+    }
+
+    #[test]
+    fn test_value_ref() {
+        #[derive(serde::Deserialize)]
+        struct Args<'a> {
+            name: &'a str,
+            #[serde(with = "serde_bytes")]
+            data: &'a [u8],
+            doubles: Vec<f64>,
+        }
+
+        let value = Value::Map(hash_map! {
+            "name".into(): "image".into(),
+            "data".into(): Value::U8List(vec![1, 2, 3, 4]),
+            "doubles".into(): Value::F64List(vec![1.5, 2.5]),
+        });
+        let encoded = StandardMethodCodec.encode_message(&value);
+        let value_ref = StandardMethodCodec
+            .try_decode_message_ref(&encoded)
+            .unwrap();
+        assert_eq!(value_ref.to_value(), value);
+
+        let args: Args = from_value_ref(&value_ref).unwrap();
+        assert_eq!(args.name, "image");
+        assert_eq!(args.data, &[1, 2, 3, 4]);
+        assert_eq!(args.doubles, vec![1.5, 2.5]);
+
+        // borrowed slices point into the message buffer
+        let range = encoded.as_ptr_range();
+        assert!(range.contains(&args.name.as_ptr()));
+        assert!(range.contains(&args.data.as_ptr()));
+
+        let encoded = StandardMethodCodec.encode_message(&Value::F64List(vec![1.5, 2.5]));
+        match StandardMethodCodec.try_decode_message_ref(&encoded) {
+            Ok(ValueRef::F64List(list)) => {
+                assert_eq!(list.get(1), Some(2.5));
+                assert_eq!(list.get(2), None);
+                assert_eq!(list.get(usize::MAX / 8 + 1), None);
+                assert_eq!(list.get(usize::MAX), None);
+            }
+            res => panic!("unexpected result {:?}", res),
+        }
+    }
+}
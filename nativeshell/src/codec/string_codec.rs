@@ -0,0 +1,15 @@
+use super::MessageCodec;
+
+// UTF-8 encoded string messages, same as Flutter StringCodec. Invalid UTF-8
+// sequences are replaced with U+FFFD rather than failing the message.
+pub struct StringCodec;
+
+impl MessageCodec<String> for StringCodec {
+    fn encode_message(&self, v: &String) -> Vec<u8> {
+        v.as_bytes().into()
+    }
+
+    fn decode_message(&self, buf: &[u8]) -> Option<String> {
+        Some(String::from_utf8_lossy(buf).into_owned())
+    }
+}
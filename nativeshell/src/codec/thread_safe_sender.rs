@@ -0,0 +1,374 @@
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use futures::{channel::oneshot, Future};
+use log::error;
+
+use crate::{
+    shell::{AsyncMethodCallError, AsyncMethodCallResult, Context, EngineHandle, RunLoopSender},
+    Error, Result,
+};
+
+use super::{EventSender, MessageCodec, MessageSender, MethodCodec, MethodInvoker};
+
+// Counterparts of MethodInvoker, EventSender and MessageSender that can be used
+// from any thread. Calls are forwarded to run loop thread through RunLoopSender
+// and results are sent back through a channel.
+
+// Runs callback on run loop thread with the context that is current there.
+fn send_to_run_loop<F>(sender: &RunLoopSender, callback: F)
+where
+    F: FnOnce(Context) -> Result<()> + Send + 'static,
+{
+    sender.send(move || {
+        let res = match Context::current() {
+            Some(context) => callback(context.weak()),
+            None => Err(Error::InvalidContext),
+        };
+        if let Err(error) = res {
+            error!("Failed to forward call to run loop thread: {error}");
+        }
+    });
+}
+
+// Completes call forwarded to run loop thread; Only first result is delivered.
+struct ResultSender<T>(Rc<RefCell<Option<oneshot::Sender<Result<T>>>>>);
+
+impl<T> Clone for ResultSender<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> ResultSender<T> {
+    fn send(&self, result: Result<T>) {
+        if let Some(sender) = self.0.borrow_mut().take() {
+            let _ = sender.send(result);
+        }
+    }
+}
+
+// Runs callback on run loop thread with the context that is current there;
+// Callback completes the call through given ResultSender, error returned from
+// callback completes it as well. Returned future resolves to None when the call
+// was executed but never completed.
+fn call_on_run_loop<T, F>(
+    sender: &RunLoopSender,
+    callback: F,
+) -> impl Future<Output = Option<Result<T>>> + Send
+where
+    T: Send + 'static,
+    F: FnOnce(Context, ResultSender<T>) -> Result<()> + Send + 'static,
+{
+    let (result_sender, receiver) = oneshot::channel();
+    let executed = Arc::new(AtomicBool::new(false));
+    let executed_clone = executed.clone();
+    sender.send(move || {
+        executed_clone.store(true, Ordering::SeqCst);
+        let result_sender = ResultSender(Rc::new(RefCell::new(Some(result_sender))));
+        let res = match Context::current() {
+            Some(context) => callback(context.weak(), result_sender.clone()),
+            None => Err(Error::InvalidContext),
+        };
+        if let Err(error) = res {
+            result_sender.send(Err(error));
+        }
+    });
+    async move {
+        match receiver.await {
+            Ok(result) => Some(result),
+            // Run loop went away without executing the call
+            Err(_) if !executed.load(Ordering::SeqCst) => Some(Err(Error::InvalidContext)),
+            Err(_) => None,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ThreadSafeMethodInvoker<V>
+where
+    V: 'static,
+{
+    sender: RunLoopSender,
+    engine_handle: EngineHandle,
+    channel_name: String,
+    codec: &'static dyn MethodCodec<V>,
+}
+
+impl<V> ThreadSafeMethodInvoker<V> {
+    pub fn new(
+        sender: RunLoopSender,
+        engine_handle: EngineHandle,
+        channel_name: String,
+        codec: &'static dyn MethodCodec<V>,
+    ) -> Self {
+        Self {
+            sender,
+            engine_handle,
+            channel_name,
+            codec,
+        }
+    }
+}
+
+impl<V: Send> ThreadSafeMethodInvoker<V> {
+    // The returned future can be awaited on any thread. Dropping it doesn't cancel
+    // the call, the result is discarded.
+    pub fn call_method(
+        &self,
+        method: &str,
+        args: V,
+        timeout: Option<Duration>,
+    ) -> impl Future<Output = AsyncMethodCallResult<V>> + Send {
+        let engine_handle = self.engine_handle;
+        let channel_name = self.channel_name.clone();
+        let codec = self.codec;
+        let method = method.to_owned();
+        let result = call_on_run_loop(&self.sender, move |context, sender| {
+            let invoker = MethodInvoker::new(context, engine_handle, channel_name, codec);
+            invoker
+                .call_method_with_timeout(&method, args, timeout, move |reply| {
+                    sender.send(Ok(reply));
+                })
+                // Keep the call alive until it completes; The receiver going away
+                // doesn't cancel it
+                .map(|mut handle| handle.detach())
+        });
+        async move {
+            match result.await {
+                Some(Ok(result)) => result,
+                Some(Err(error)) => Err(AsyncMethodCallError::ShellError(error)),
+                // Pending call is only dropped without reply when the context,
+                // along with all engines, is destroyed
+                None => Err(AsyncMethodCallError::EngineDestroyed),
+            }
+        }
+    }
+
+    // Blocks current thread until result is available. Must not be called on run
+    // loop thread as that would deadlock.
+    pub fn call_method_blocking(
+        &self,
+        method: &str,
+        args: V,
+        timeout: Option<Duration>,
+    ) -> AsyncMethodCallResult<V> {
+        assert!(
+            Context::current().is_none(),
+            "call_method_blocking must not be called on run loop thread"
+        );
+        futures::executor::block_on(self.call_method(method, args, timeout))
+    }
+}
+
+#[derive(Clone)]
+pub struct ThreadSafeEventSender<V>
+where
+    V: 'static,
+{
+    sender: RunLoopSender,
+    engine_handle: EngineHandle,
+    channel_name: String,
+    codec: &'static dyn MethodCodec<V>,
+}
+
+impl<V> ThreadSafeEventSender<V> {
+    pub fn new(
+        sender: RunLoopSender,
+        engine_handle: EngineHandle,
+        channel_name: String,
+        codec: &'static dyn MethodCodec<V>,
+    ) -> Self {
+        Self {
+            sender,
+            engine_handle,
+            channel_name,
+            codec,
+        }
+    }
+}
+
+impl<V: Send> ThreadSafeEventSender<V> {
+    // Failure to deliver the event is only logged.
+    pub fn send_event(&self, event: V) {
+        let engine_handle = self.engine_handle;
+        let channel_name = self.channel_name.clone();
+        let codec = self.codec;
+        send_to_run_loop(&self.sender, move |context| {
+            EventSender::new(context, engine_handle, channel_name, codec).send_event(&event)
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct ThreadSafeMessageSender<V>
+where
+    V: 'static,
+{
+    sender: RunLoopSender,
+    engine_handle: EngineHandle,
+    channel_name: String,
+    codec: &'static dyn MessageCodec<V>,
+}
+
+impl<V> ThreadSafeMessageSender<V> {
+    pub fn new(
+        sender: RunLoopSender,
+        engine_handle: EngineHandle,
+        channel_name: String,
+        codec: &'static dyn MessageCodec<V>,
+    ) -> Self {
+        Self {
+            sender,
+            engine_handle,
+            channel_name,
+            codec,
+        }
+    }
+}
+
+impl<V: Send> ThreadSafeMessageSender<V> {
+    pub fn send_message(&self, message: V) -> impl Future<Output = Result<V>> + Send {
+        let engine_handle = self.engine_handle;
+        let channel_name = self.channel_name.clone();
+        let codec = self.codec;
+        let result = call_on_run_loop(&self.sender, move |context, sender| {
+            MessageSender::new(context, engine_handle, channel_name, codec).send_message(
+                &message,
+                move |reply| {
+                    sender.send(Ok(reply));
+                },
+            )
+        });
+        async move {
+            // Reply is dropped without being invoked when the engine goes away
+            // (or replies with malformed message)
+            result.await.unwrap_or(Err(Error::InvalidEngineHandle))
+        }
+    }
+
+    // Failure to deliver the message is only logged.
+    pub fn post_message(&self, message: V) {
+        let engine_handle = self.engine_handle;
+        let channel_name = self.channel_name.clone();
+        let codec = self.codec;
+        send_to_run_loop(&self.sender, move |context| {
+            MessageSender::new(context, engine_handle, channel_name, codec).post_message(&message)
+        });
+    }
+}
+
+#[cfg(all(test, feature = "null-backend"))]
+mod tests {
+    use std::thread;
+
+    use crate::{
+        codec::{
+            EventSender, MessageSender, MethodCodec, MethodInvoker, StandardMethodCodec, Value,
+        },
+        shell::{AsyncMethodCallError, Context, ContextOptions, EngineHandle, FakeDart},
+        Error,
+    };
+
+    #[test]
+    fn test_call_from_thread() {
+        let context = Context::new(ContextOptions::default()).unwrap();
+        let dart = FakeDart::new(&context).unwrap();
+        dart.set_method_handler("channel", |call| match call.args {
+            Value::I64(v) => Ok(Value::I64(v + 1)),
+            args => Ok(args),
+        });
+        let invoker = MethodInvoker::new(
+            context.weak(),
+            dart.engine(),
+            "channel".into(),
+            &StandardMethodCodec,
+        )
+        .to_thread_safe()
+        .unwrap();
+        let events = EventSender::new(
+            context.weak(),
+            dart.engine(),
+            "events".into(),
+            &StandardMethodCodec,
+        )
+        .to_thread_safe()
+        .unwrap();
+
+        let caller = thread::spawn(move || {
+            let call =
+                futures::executor::block_on(invoker.call_method("method", Value::I64(1), None));
+            let blocking = invoker.call_method_blocking("method", Value::I64(10), None);
+            for i in 0..10 {
+                events.send_event(Value::I64(i));
+            }
+            (call, blocking)
+        });
+        while !caller.is_finished() {
+            dart.pump();
+            thread::yield_now();
+        }
+        let (call, blocking) = caller.join().unwrap();
+        assert!(matches!(call, Ok(Value::I64(2))));
+        assert!(matches!(blocking, Ok(Value::I64(11))));
+        assert_eq!(dart.take_method_calls("channel").len(), 2);
+
+        dart.pump();
+        let events: Vec<_> = dart
+            .take_messages("events")
+            .iter()
+            .map(|m| StandardMethodCodec.decode_envelope(m).unwrap().unwrap())
+            .collect();
+        assert_eq!(events, (0..10).map(Value::I64).collect::<Vec<_>>());
+
+        dart.shut_down().unwrap();
+    }
+
+    #[test]
+    fn test_unknown_engine() {
+        let context = Context::new(ContextOptions::default()).unwrap();
+        let dart = FakeDart::new(&context).unwrap();
+        let engine = EngineHandle(dart.engine().0 + 1);
+        let invoker = MethodInvoker::new(
+            context.weak(),
+            engine,
+            "channel".into(),
+            &StandardMethodCodec,
+        )
+        .to_thread_safe()
+        .unwrap();
+        let sender = MessageSender::new(
+            context.weak(),
+            engine,
+            "channel".into(),
+            &StandardMethodCodec,
+        )
+        .to_thread_safe()
+        .unwrap();
+
+        let caller = thread::spawn(move || {
+            let call = invoker.call_method_blocking("method", Value::Null, None);
+            let message = futures::executor::block_on(sender.send_message(Value::Null));
+            (call, message)
+        });
+        while !caller.is_finished() {
+            dart.pump();
+            thread::yield_now();
+        }
+        let (call, message) = caller.join().unwrap();
+        assert!(matches!(
+            call,
+            Err(AsyncMethodCallError::ShellError(Error::InvalidEngineHandle))
+        ));
+        assert!(matches!(message, Err(Error::InvalidEngineHandle)));
+
+        dart.shut_down().unwrap();
+    }
+}
@@ -34,8 +34,13 @@ impl<'a, 'de> serde::de::Deserializer<'de> for &'a mut Deserializer<'de> {
             Value::I32List(_) => visitor.visit_seq(SeqAccess::new(self)),
             Value::I64List(_) => visitor.visit_seq(SeqAccess::new(self)),
             Value::F64List(_) => visitor.visit_seq(SeqAccess::new(self)),
+            Value::F32List(_) => visitor.visit_seq(SeqAccess::new(self)),
             Value::List(_) => visitor.visit_seq(SeqAccess::new(self)),
             Value::Map(_) => visitor.visit_map(MapAccess::new(self)),
+            Value::Custom(custom) => serde::de::Deserializer::deserialize_any(
+                &mut Deserializer::new(&custom.value),
+                visitor,
+            ),
         }
     }
 
@@ -131,6 +136,15 @@ impl<'a, 'de> serde::de::SeqAccess<'de> for SeqAccess<'a, 'de> {
                     seed.deserialize(vec[self.index - 1].into_deserializer())?,
                 ))
             }
+            Value::F32List(vec) => {
+                if vec.len() <= self.index {
+                    return Ok(None);
+                }
+                self.index += 1;
+                Ok(Some(
+                    seed.deserialize(vec[self.index - 1].into_deserializer())?,
+                ))
+            }
             Value::List(vec) => {
                 if vec.len() <= self.index {
                     return Ok(None);
@@ -1,5 +1,7 @@
 mod deserializer;
+mod ref_deserializer;
 mod serializer;
+mod value_ref;
 
 use std::{convert::TryFrom, f64::NAN, fmt};
 
@@ -7,7 +9,9 @@ use std::{collections::HashMap, hash::Hash};
 
 pub use self::{
     deserializer::{from_value, from_value_owned},
+    ref_deserializer::from_value_ref,
     serializer::to_value,
+    value_ref::{TypedListElement, TypedListRef, ValueRef},
 };
 
 #[derive(Clone, Debug, PartialEq)]
@@ -21,8 +25,18 @@ pub enum Value {
     I32List(Vec<i32>),
     I64List(Vec<i64>),
     F64List(Vec<f64>),
+    F32List(Vec<f32>),
     List(Vec<Value>),
     Map(HashMap<Value, Value>),
+    Custom(CustomValue),
+}
+
+// Value with custom type tag; Encoded by StandardCodecExtension registered
+// for the tag.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CustomValue {
+    pub type_tag: u8,
+    pub value: Box<Value>,
 }
 
 impl Default for Value {
@@ -52,8 +66,10 @@ impl_from!(Value::U8List, Vec<u8>);
 impl_from!(Value::I32List, Vec<i32>);
 impl_from!(Value::I64List, Vec<i64>);
 impl_from!(Value::F64List, Vec<f64>);
+impl_from!(Value::F32List, Vec<f32>);
 impl_from!(Value::List, Vec<Value>);
 impl_from!(Value::Map, HashMap<Value, Value>);
+impl_from!(Value::Custom, CustomValue);
 
 impl Eq for Value {}
 
@@ -85,8 +101,10 @@ impl std::hash::Hash for Value {
             Value::I32List(v) => v.hash(state),
             Value::I64List(v) => v.hash(state),
             Value::F64List(v) => v.iter().for_each(|x| hash_f64(*x, state)),
+            Value::F32List(v) => v.iter().for_each(|x| hash_f64((*x).into(), state)),
             Value::List(v) => v.hash(state),
             Value::Map(v) => hash_map(v, state),
+            Value::Custom(v) => v.hash(state),
         }
     }
 }
@@ -145,6 +163,7 @@ impl serde::Serialize for Value {
             Value::I32List(vec) => vec.serialize(serializer),
             Value::I64List(vec) => vec.serialize(serializer),
             Value::F64List(vec) => vec.serialize(serializer),
+            Value::F32List(vec) => vec.serialize(serializer),
             Value::List(vec) => vec.serialize(serializer),
             Value::Map(map) => {
                 use serde::ser::SerializeMap;
@@ -154,6 +173,7 @@ impl serde::Serialize for Value {
                 }
                 m.end()
             }
+            Value::Custom(custom) => custom.value.serialize(serializer),
         }
     }
 }
@@ -0,0 +1,212 @@
+use serde::de::{value::SeqDeserializer, IntoDeserializer};
+
+use super::{deserializer::Deserializer, ValueError, ValueRef};
+
+type Result<T> = std::result::Result<T, ValueError>;
+
+// Deserializer over borrowed value; Strings and byte lists are passed to visitor
+// as borrowed, so they can be deserialized into &str and &[u8] without copying.
+pub struct RefDeserializer<'de> {
+    value: &'de ValueRef<'de>,
+}
+
+impl<'de> RefDeserializer<'de> {
+    pub fn new(value: &'de ValueRef<'de>) -> Self {
+        Self { value }
+    }
+}
+
+impl<'de> serde::de::Deserializer<'de> for &mut RefDeserializer<'de> {
+    type Error = ValueError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        match self.value {
+            ValueRef::Null => visitor.visit_unit(),
+            ValueRef::Bool(b) => visitor.visit_bool(*b),
+            ValueRef::I64(i) => visitor.visit_i64(*i),
+            ValueRef::F64(f) => visitor.visit_f64(*f),
+            ValueRef::String(s) => visitor.visit_borrowed_str(s),
+            ValueRef::U8List(s) => visitor.visit_borrowed_bytes(s),
+            ValueRef::I32List(l) => visitor.visit_seq(SeqDeserializer::new(l.iter())),
+            ValueRef::I64List(l) => visitor.visit_seq(SeqDeserializer::new(l.iter())),
+            ValueRef::F64List(l) => visitor.visit_seq(SeqDeserializer::new(l.iter())),
+            ValueRef::F32List(l) => visitor.visit_seq(SeqDeserializer::new(l.iter())),
+            ValueRef::List(l) => visitor.visit_seq(SeqAccess { iter: l.iter() }),
+            ValueRef::Map(m) => visitor.visit_map(MapAccess {
+                iter: m.iter(),
+                next_value: None,
+            }),
+            ValueRef::Custom(custom) => serde::de::Deserializer::deserialize_any(
+                &mut Deserializer::new(&custom.value),
+                visitor,
+            ),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        match self.value {
+            ValueRef::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        match self.value {
+            ValueRef::String(s) => visitor.visit_enum((*s).into_deserializer()),
+            ValueRef::Map(m) if m.len() == 1 => match &m[0] {
+                (ValueRef::String(name), value) => visitor.visit_enum(EnumAccess {
+                    name,
+                    value_deserializer: RefDeserializer::new(value),
+                }),
+                _ => Err(ValueError::WrongType),
+            },
+            _ => Err(ValueError::WrongType),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool
+        i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes byte_buf unit unit_struct
+        seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+struct SeqAccess<'de> {
+    iter: std::slice::Iter<'de, ValueRef<'de>>,
+}
+
+impl<'de> serde::de::SeqAccess<'de> for SeqAccess<'de> {
+    type Error = ValueError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: serde::de::DeserializeSeed<'de>,
+    {
+        match self.iter.next() {
+            Some(value) => Ok(Some(seed.deserialize(&mut RefDeserializer::new(value))?)),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct MapAccess<'de> {
+    iter: std::slice::Iter<'de, (ValueRef<'de>, ValueRef<'de>)>,
+    next_value: Option<&'de ValueRef<'de>>,
+}
+
+impl<'de> serde::de::MapAccess<'de> for MapAccess<'de> {
+    type Error = ValueError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: serde::de::DeserializeSeed<'de>,
+    {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.next_value.replace(value);
+                Ok(Some(seed.deserialize(&mut RefDeserializer::new(key))?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: serde::de::DeserializeSeed<'de>,
+    {
+        match self.next_value.take() {
+            Some(value) => seed.deserialize(&mut RefDeserializer::new(value)),
+            None => Err(ValueError::NoMap),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct EnumAccess<'de> {
+    name: &'de str,
+    value_deserializer: RefDeserializer<'de>,
+}
+
+impl<'de> serde::de::EnumAccess<'de> for EnumAccess<'de> {
+    type Error = ValueError;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant)>
+    where
+        V: serde::de::DeserializeSeed<'de>,
+    {
+        let val = seed.deserialize(self.name.into_deserializer())?;
+        Ok((val, self))
+    }
+}
+
+impl<'de> serde::de::VariantAccess<'de> for EnumAccess<'de> {
+    type Error = ValueError;
+
+    fn unit_variant(mut self) -> Result<()> {
+        serde::de::Deserialize::deserialize(&mut self.value_deserializer)
+    }
+
+    fn newtype_variant_seed<V>(mut self, seed: V) -> Result<V::Value>
+    where
+        V: serde::de::DeserializeSeed<'de>,
+    {
+        seed.deserialize(&mut self.value_deserializer)
+    }
+
+    fn tuple_variant<V>(mut self, _len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        serde::de::Deserializer::deserialize_seq(&mut self.value_deserializer, visitor)
+    }
+
+    fn struct_variant<V>(mut self, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        serde::de::Deserializer::deserialize_struct(
+            &mut self.value_deserializer,
+            "",
+            fields,
+            visitor,
+        )
+    }
+}
+
+// Like from_value, but for borrowed values; Allows deserializing into types that
+// borrow from the message buffer (i.e. &str or &[u8] with serde_bytes).
+pub fn from_value_ref<'a, T>(value: &'a ValueRef<'a>) -> Result<T>
+where
+    T: serde::de::Deserialize<'a>,
+{
+    T::deserialize(&mut RefDeserializer::new(value))
+}
@@ -0,0 +1,156 @@
+use std::{convert::TryInto, fmt, marker::PhantomData};
+
+use super::{CustomValue, Value};
+
+// Value borrowed from message buffer. Strings, byte lists and typed lists point
+// directly into the buffer so decoding large payloads doesn't copy them.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValueRef<'a> {
+    Null,
+    Bool(bool),
+    I64(i64),
+    F64(f64),
+    String(&'a str),
+    U8List(&'a [u8]),
+    I32List(TypedListRef<'a, i32>),
+    I64List(TypedListRef<'a, i64>),
+    F64List(TypedListRef<'a, f64>),
+    F32List(TypedListRef<'a, f32>),
+    List(Vec<ValueRef<'a>>),
+    Map(Vec<(ValueRef<'a>, ValueRef<'a>)>),
+    // Custom values are decoded by extension and always owned
+    Custom(CustomValue),
+}
+
+impl<'a> ValueRef<'a> {
+    // Copies the value into owned Value.
+    pub fn to_value(&self) -> Value {
+        match self {
+            ValueRef::Null => Value::Null,
+            ValueRef::Bool(v) => Value::Bool(*v),
+            ValueRef::I64(v) => Value::I64(*v),
+            ValueRef::F64(v) => Value::F64(*v),
+            ValueRef::String(v) => Value::String((*v).into()),
+            ValueRef::U8List(v) => Value::U8List(v.to_vec()),
+            ValueRef::I32List(v) => Value::I32List(v.to_vec()),
+            ValueRef::I64List(v) => Value::I64List(v.to_vec()),
+            ValueRef::F64List(v) => Value::F64List(v.to_vec()),
+            ValueRef::F32List(v) => Value::F32List(v.to_vec()),
+            ValueRef::List(v) => Value::List(v.iter().map(|v| v.to_value()).collect()),
+            ValueRef::Map(v) => Value::Map(
+                v.iter()
+                    .map(|(k, v)| (k.to_value(), v.to_value()))
+                    .collect(),
+            ),
+            ValueRef::Custom(v) => Value::Custom(v.clone()),
+        }
+    }
+
+    // Returns value for given string key if this is a map.
+    pub fn get(&self, key: &str) -> Option<&ValueRef<'a>> {
+        match self {
+            ValueRef::Map(map) => map.iter().find_map(|(k, v)| match k {
+                ValueRef::String(k) if *k == key => Some(v),
+                _ => None,
+            }),
+            _ => None,
+        }
+    }
+}
+
+mod private {
+    pub trait Sealed {}
+}
+
+// Implemented only for plain numeric types (sealed), which TypedListRef::as_slice
+// relies on.
+pub trait TypedListElement: private::Sealed + Copy + fmt::Debug + 'static {
+    const SIZE: usize;
+    fn from_ne_bytes(bytes: &[u8]) -> Self;
+}
+
+macro_rules! impl_typed_list_element {
+    ($type:ty) => {
+        impl private::Sealed for $type {}
+
+        impl TypedListElement for $type {
+            const SIZE: usize = std::mem::size_of::<$type>();
+            fn from_ne_bytes(bytes: &[u8]) -> Self {
+                <$type>::from_ne_bytes(bytes.try_into().unwrap())
+            }
+        }
+    };
+}
+
+impl_typed_list_element!(i32);
+impl_typed_list_element!(i64);
+impl_typed_list_element!(f32);
+impl_typed_list_element!(f64);
+
+// Typed list in message buffer. The list is aligned relative to start of the
+// message, but the message buffer itself might not be, so elements are read
+// from bytes unless the slice happens to be aligned (see as_slice).
+#[derive(Clone, Copy)]
+pub struct TypedListRef<'a, T> {
+    data: &'a [u8],
+    _type: PhantomData<T>,
+}
+
+impl<'a, T: TypedListElement> TypedListRef<'a, T> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            _type: PhantomData {},
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len() / T::SIZE
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<T> {
+        let start = index.checked_mul(T::SIZE)?;
+        let end = start.checked_add(T::SIZE)?;
+        self.data.get(start..end).map(T::from_ne_bytes)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = T> + 'a {
+        self.data.chunks_exact(T::SIZE).map(T::from_ne_bytes)
+    }
+
+    // Raw list content in native byte order.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.data
+    }
+
+    // Returns list content as slice if the data is properly aligned in memory.
+    pub fn as_slice(&self) -> Option<&'a [T]> {
+        if self.data.as_ptr().align_offset(std::mem::align_of::<T>()) == 0 {
+            // Safe: all types implementing TypedListElement are plain numbers for
+            // which any bit pattern is valid and the pointer is aligned.
+            Some(unsafe { std::slice::from_raw_parts(self.data.as_ptr() as *const T, self.len()) })
+        } else {
+            None
+        }
+    }
+
+    pub fn to_vec(&self) -> Vec<T> {
+        self.iter().collect()
+    }
+}
+
+impl<'a, T: TypedListElement> fmt::Debug for TypedListRef<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, T: TypedListElement + PartialEq> PartialEq for TypedListRef<'a, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
@@ -1,6 +1,9 @@
 use std::fmt::Display;
 
-use crate::{codec::value::ValueError, shell::platform::error::PlatformError};
+use crate::{
+    codec::{value::ValueError, DecodeError},
+    shell::platform::error::PlatformError,
+};
 
 #[derive(Debug, Clone)]
 pub enum Error {
@@ -8,8 +11,10 @@ pub enum Error {
     InvalidEngineHandle,
     Platform(PlatformError),
     Value(ValueError),
+    Decode(DecodeError),
     InvalidMenuHandle,
     InvalidStatusItemHandle,
+    Io(String),
 }
 
 impl Display for Error {
@@ -23,6 +28,7 @@ impl Display for Error {
                 write!(f, "Provided handle does not match any engine")
             }
             Error::Value(error) => Display::fmt(error, f),
+            Error::Decode(error) => Display::fmt(error, f),
             Error::InvalidMenuHandle => {
                 write!(f, "Provided menu handle does not match any known menu")
             }
@@ -32,6 +38,7 @@ impl Display for Error {
                     "Provided status item handle does not match any known status item"
                 )
             }
+            Error::Io(error) => write!(f, "I/O error: {error}"),
         }
     }
 }
@@ -40,6 +47,12 @@ pub type Result<T> = std::result::Result<T, Error>;
 
 impl std::error::Error for Error {}
 
+impl From<std::io::Error> for Error {
+    fn from(src: std::io::Error) -> Error {
+        Error::Io(src.to_string())
+    }
+}
+
 impl From<PlatformError> for Error {
     fn from(src: PlatformError) -> Error {
         Error::Platform(src)
@@ -51,3 +64,9 @@ impl From<ValueError> for Error {
         Error::Value(src)
     }
 }
+
+impl From<DecodeError> for Error {
+    fn from(src: DecodeError) -> Error {
+        Error::Decode(src)
+    }
+}
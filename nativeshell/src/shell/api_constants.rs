@@ -24,15 +24,47 @@ pub(crate) mod channel {
 
     // Flutter channel for managing status items
     pub const STATUS_ITEM_MANAGER: &str = "nativeshell/status-item-manager";
+
+    // Flutter channel used by Dart to signal that it registered channel handler
+    pub const CHANNEL_READINESS: &str = "nativeshell/channel-readiness";
+
+    // Flutter channel for querying information about the shell itself
+    pub const SHELL: &str = "nativeshell/shell";
+
+    // Flutter channel for application wide events (i.e. termination, launch
+    // arguments)
+    pub const APPLICATION: &str = "nativeshell/application";
+
+    // Flutter channel for notifications from secondary application instances
+    pub const SINGLE_INSTANCE: &str = "nativeshell/single-instance";
 }
 
 pub const CURRENT_API_VERSION: i32 = 1;
 
+// Oldest API version that Dart side may negotiate
+pub const MIN_API_VERSION: i32 = 1;
+
+// Optional features that Dart side may request during API negotiation
+pub const API_FEATURES: &[&str] = &[api_feature::CHANNEL_READINESS, api_feature::CAPABILITIES];
+
+pub mod api_feature {
+    // Dart signals readiness of buffered channels; Without it buffered channels
+    // are flushed right after negotiation
+    pub const CHANNEL_READINESS: &str = "channelReadiness";
+
+    // Dart may query Shell.getCapabilities
+    pub const CAPABILITIES: &str = "capabilities";
+}
+
 pub(crate) mod method {
 
     pub mod window_manager {
         pub const GET_API_VERSION: &str = "WindowManager.getApiVersion";
 
+        // Dart sends supported version range and features; Returns negotiated
+        // version and enabled features
+        pub const NEGOTIATE_API: &str = "WindowManager.negotiateApi";
+
         // Request creation of new window
         pub const CREATE_WINDOW: &str = "WindowManager.createWindow";
 
@@ -131,6 +163,54 @@ pub(crate) mod method {
         pub const ON_PRESSED: &str = "HotKey.onPressed";
     }
 
+    pub mod channel_readiness {
+        // Channel handler is registered (argument is channel name); Messages
+        // buffered for the channel are flushed
+        pub const READY: &str = "ChannelReadiness.ready";
+    }
+
+    pub mod application {
+        // Invoked on all engines when termination is requested (i.e. on SIGTERM);
+        // Dart may delay the reply to finish cleanup or return false to cancel
+        // termination
+        pub const TERMINATE_REQUESTED: &str = "Application.terminateRequested";
+
+        // Returns arguments the application was launched with
+        pub const GET_LAUNCH_ARGUMENTS: &str = "Application.getLaunchArguments";
+
+        // Invoked on all engines when application is asked to open files after
+        // launch (i.e. by secondary instance)
+        pub const ON_OPEN_FILES: &str = "Application.onOpenFiles";
+
+        // Invoked on all engines when application is asked to open URL after
+        // launch (i.e. myapp:// deep link passed to secondary instance)
+        pub const ON_OPEN_URL: &str = "Application.onOpenUrl";
+
+        // Requests termination; Same as SIGTERM, termination hooks and
+        // Application.terminateRequested handlers may still cancel it
+        pub const QUIT: &str = "Application.quit";
+
+        // Returns whether any application window is focused
+        pub const IS_ACTIVE: &str = "Application.isActive";
+
+        // Invoked on all engines when first application window gets focused
+        pub const ON_ACTIVATED: &str = "Application.onActivated";
+
+        // Invoked on all engines when no application window is focused anymore
+        pub const ON_DEACTIVATED: &str = "Application.onDeactivated";
+    }
+
+    pub mod single_instance {
+        // Invoked on all engines when another instance of the application was
+        // launched and exited in favor of this one
+        pub const ON_SECONDARY_INSTANCE: &str = "SingleInstance.onSecondaryInstance";
+    }
+
+    pub mod shell {
+        // Returns features supported by current platform backend
+        pub const GET_CAPABILITIES: &str = "Shell.getCapabilities";
+    }
+
     pub mod screen_manager {
         pub const SCREENS_CHANGED: &str = "ScreenManager.screensChanged";
         pub const GET_SCREENS: &str = "ScreenManager.getScreens";
@@ -176,6 +256,8 @@ pub(crate) mod event {
     }
 }
 
+// Null backend has no native drag data to convert
+#[cfg_attr(feature = "null-backend", allow(dead_code))]
 pub(crate) mod drag_data {
     pub mod key {
         pub const FILES: &str = "drag-data:internal:files";
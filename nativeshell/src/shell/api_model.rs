@@ -11,6 +11,22 @@ pub enum GeometryPreference {
     PreferContent,
 }
 
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiNegotiationRequest {
+    pub min_version: i32,
+    pub max_version: i32,
+    #[serde(default)]
+    pub features: Vec<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiNegotiationResponse {
+    pub version: i32,
+    pub features: Vec<String>,
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct WindowGeometry {
@@ -476,3 +492,54 @@ pub struct StatusItemAction {
     pub action: StatusItemActionType,
     pub position: Point,
 }
+
+// Features supported by current platform backend. Calling corresponding
+// methods on backends that don't support them results in NotAvailable error.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Capabilities {
+    pub hot_keys: bool,
+    pub status_items: bool,
+    pub status_item_highlight: bool,
+    pub app_menu: bool,
+    pub window_menu: bool,
+    pub system_menu: bool,
+    pub collection_behavior: bool,
+    pub full_screen: bool,
+    // Whether window can be moved to arbitrary position (not possible under Wayland)
+    pub window_positioning: bool,
+    // RunLoop::watch_fd, fd_ready and spawn_process
+    pub fd_watch: bool,
+}
+
+// Sent from secondary application instance to the primary one.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SecondaryInstanceLaunch {
+    pub arguments: Vec<String>,
+    pub working_directory: String,
+}
+
+// Arguments of current process, or of secondary instance that forwarded its
+// launch. Arguments that are not options are sorted to files and URLs.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct LaunchArguments {
+    pub arguments: Vec<String>,
+    pub working_directory: String,
+    // Absolute paths (including decoded file:// URLs)
+    pub files: Vec<String>,
+    pub urls: Vec<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenFilesEvent {
+    pub files: Vec<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenUrlEvent {
+    pub url: String,
+}
@@ -0,0 +1,617 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashSet,
+    future::Future,
+    path::Path,
+    rc::{Rc, Weak},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use futures::future::{join_all, LocalBoxFuture};
+use log::warn;
+
+use crate::{
+    codec::{value::to_value, MethodCall, MethodCallError, MethodCallResult, Value},
+    util::{Late, OkLog},
+    Context,
+};
+
+#[cfg(fd_watch)]
+use super::signal_handler::SignalHandler;
+use super::{
+    api_constants::{channel, method},
+    api_model::{LaunchArguments, OpenFilesEvent, OpenUrlEvent, SecondaryInstanceLaunch},
+    AsyncMethodCallError, AsyncMethodCallHandler, AsyncMethodInvoker, ContextRef, Debouncer,
+    EngineHandle, Handle, RegisteredAsyncMethodCallHandler, WindowHandle,
+};
+
+type TerminateHook = dyn Fn() -> LocalBoxFuture<'static, bool>;
+
+// Focus moving between windows deactivates one window before activating the
+// other; Give it some time to not report that as application deactivation.
+const ACTIVATION_CHECK_DELAY: Duration = Duration::from_millis(50);
+
+// What happens after last window (engine) has been removed.
+pub enum LastWindowClosedPolicy {
+    // Request termination (termination hooks may still veto it)
+    Terminate,
+    // Keep running without windows (i.e. status item only application)
+    KeepRunning,
+    Custom(Box<dyn Fn(&ContextRef)>),
+}
+
+// Application wide state shared with Dart on nativeshell/application channel.
+//
+// Dart can query arguments the application was launched with; Files and URLs
+// that the application is asked to open later (i.e. forwarded by secondary
+// instance) are delivered as Application.onOpenFiles and onOpenUrl calls.
+//
+// Application is active while any of its windows is focused; Changes are
+// delivered as Application.onActivated and onDeactivated calls.
+//
+// Termination can be requested programmatically, from Dart (Application.quit),
+// after last window is closed (see LastWindowClosedPolicy) or (when enabled in
+// ContextOptions) by SIGTERM, SIGINT or SIGHUP. Termination hooks registered
+// from Rust and Dart (Application.terminateRequested) can delay termination by
+// not completing right away, or veto it by returning false. Once all of them
+// agree all engines are shut down and the run loop is stopped. Second signal
+// received while termination is in progress terminates right away.
+pub struct ApplicationManager {
+    context: Context,
+    weak_self: Late<Weak<RefCell<Self>>>,
+    invoker: Late<AsyncMethodInvoker>,
+    terminate_hooks: RefCell<Vec<(usize, Rc<TerminateHook>)>>,
+    next_hook_id: Cell<usize>,
+    terminating: Cell<bool>,
+    terminated: Cell<bool>,
+    launch_arguments: LaunchArguments,
+    active: Cell<bool>,
+    active_windows: RefCell<HashSet<WindowHandle>>,
+    activation_listeners: RefCell<Vec<(usize, Rc<dyn Fn(bool)>)>>,
+    activation_debouncer: RefCell<Debouncer>,
+    #[cfg(fd_watch)]
+    signal_handler: RefCell<Option<SignalHandler>>,
+}
+
+impl ApplicationManager {
+    pub(super) fn new(context: &ContextRef) -> RegisteredAsyncMethodCallHandler<Self> {
+        let res = Self {
+            context: context.weak(),
+            weak_self: Late::new(),
+            invoker: Late::new(),
+            terminate_hooks: RefCell::new(Vec::new()),
+            next_hook_id: Cell::new(1),
+            terminating: Cell::new(false),
+            terminated: Cell::new(false),
+            launch_arguments: LaunchArguments::from_launch(SecondaryInstanceLaunch::current()),
+            active: Cell::new(false),
+            active_windows: RefCell::new(HashSet::new()),
+            activation_listeners: RefCell::new(Vec::new()),
+            activation_debouncer: RefCell::new(Debouncer::new(
+                &context.run_loop.borrow(),
+                ACTIVATION_CHECK_DELAY,
+            )),
+            #[cfg(fd_watch)]
+            signal_handler: RefCell::new(None),
+        }
+        .register(context.weak(), channel::APPLICATION);
+
+        res
+    }
+
+    // Called during context initialization when handle_termination_signals
+    // is enabled.
+    #[cfg(fd_watch)]
+    pub(super) fn install_signal_handler(&self, context: &ContextRef) -> crate::Result<()> {
+        let weak_self = self.weak_self.clone();
+        let handler = SignalHandler::install(
+            &context.run_loop.borrow(),
+            &[libc::SIGTERM, libc::SIGINT, libc::SIGHUP],
+            move |_| {
+                if let Some(manager) = weak_self.upgrade() {
+                    let manager = manager.borrow();
+                    if manager.terminating.get() {
+                        manager.terminate_now();
+                    } else {
+                        manager.request_termination();
+                    }
+                }
+            },
+        );
+        self.signal_handler.replace(Some(handler?));
+        Ok(())
+    }
+
+    // Registers hook invoked when termination is requested; Termination waits
+    // until the returned future completes and is cancelled if it resolves to
+    // false. Dropping the returned handle unregisters the hook.
+    pub fn register_terminate_hook<F, R>(&self, hook: F) -> Handle
+    where
+        F: Fn() -> R + 'static,
+        R: Future<Output = bool> + 'static,
+    {
+        let id = self.next_hook_id.get();
+        self.next_hook_id.set(id + 1);
+        self.terminate_hooks.borrow_mut().push((
+            id,
+            Rc::new(move || Box::pin(hook()) as LocalBoxFuture<bool>),
+        ));
+        let weak_self = self.weak_self.clone();
+        Handle::new(move || {
+            if let Some(manager) = weak_self.upgrade() {
+                manager
+                    .borrow()
+                    .terminate_hooks
+                    .borrow_mut()
+                    .retain(|(hook_id, _)| *hook_id != id);
+            }
+        })
+    }
+
+    // Listener is called with true when application becomes active and false
+    // when it resigns. Dropping the returned handle unregisters the listener.
+    pub fn register_activation_listener<F>(&self, listener: F) -> Handle
+    where
+        F: Fn(bool) + 'static,
+    {
+        let id = self.next_hook_id.get();
+        self.next_hook_id.set(id + 1);
+        self.activation_listeners
+            .borrow_mut()
+            .push((id, Rc::new(listener)));
+        let weak_self = self.weak_self.clone();
+        Handle::new(move || {
+            if let Some(manager) = weak_self.upgrade() {
+                manager
+                    .borrow()
+                    .activation_listeners
+                    .borrow_mut()
+                    .retain(|(listener_id, _)| *listener_id != id);
+            }
+        })
+    }
+
+    // Whether any application window is focused.
+    pub fn is_active(&self) -> bool {
+        self.active.get()
+    }
+
+    pub(super) fn window_activation_changed(&self, window: WindowHandle, active: bool) {
+        {
+            let mut active_windows = self.active_windows.borrow_mut();
+            if active {
+                active_windows.insert(window);
+            } else {
+                active_windows.remove(&window);
+            }
+        }
+        let weak_self = self.weak_self.clone();
+        self.activation_debouncer.borrow_mut().call(move || {
+            if let Some(manager) = weak_self.upgrade() {
+                manager.borrow().update_active();
+            }
+        });
+    }
+
+    fn update_active(&self) {
+        let active = !self.active_windows.borrow().is_empty();
+        if self.active.replace(active) == active {
+            return;
+        }
+        let listeners: Vec<_> = self
+            .activation_listeners
+            .borrow()
+            .iter()
+            .map(|(_, listener)| listener.clone())
+            .collect();
+        for listener in listeners {
+            listener(active);
+        }
+        let method = match active {
+            true => method::application::ON_ACTIVATED,
+            false => method::application::ON_DEACTIVATED,
+        };
+        self.notify_engines(method, Value::Null);
+    }
+
+    pub(super) fn last_engine_removed(&self, context: &ContextRef) {
+        match &context.options.last_window_closed {
+            LastWindowClosedPolicy::Terminate => self.request_termination(),
+            LastWindowClosedPolicy::KeepRunning => {}
+            LastWindowClosedPolicy::Custom(callback) => callback(context),
+        }
+    }
+
+    pub fn launch_arguments(&self) -> &LaunchArguments {
+        &self.launch_arguments
+    }
+
+    // Delivers files to Dart (Application.onOpenFiles). Can be used to forward
+    // files from platform specific sources, such as ApplicationDelegate on macOS.
+    pub fn open_files(&self, files: Vec<String>) {
+        self.notify_engines(
+            method::application::ON_OPEN_FILES,
+            to_value(OpenFilesEvent { files }).unwrap(),
+        );
+    }
+
+    // Delivers URL to Dart (Application.onOpenUrl).
+    pub fn open_url(&self, url: String) {
+        self.notify_engines(
+            method::application::ON_OPEN_URL,
+            to_value(OpenUrlEvent { url }).unwrap(),
+        );
+    }
+
+    #[cfg_attr(not(fd_watch), allow(dead_code))]
+    pub(super) fn on_secondary_instance(&self, launch: SecondaryInstanceLaunch) {
+        let arguments = LaunchArguments::from_launch(launch);
+        if !arguments.files.is_empty() {
+            self.open_files(arguments.files);
+        }
+        for url in arguments.urls {
+            self.open_url(url);
+        }
+    }
+
+    fn notify_engines(&self, method: &'static str, args: Value) {
+        let context = match self.context.get() {
+            Some(context) => context,
+            None => return,
+        };
+        let engines = context.engine_manager.borrow().get_all_engines();
+        let invoker = self.invoker.clone();
+        context.run_loop.borrow().spawn(async move {
+            for engine in engines {
+                match invoker.call_method(engine, method, args.clone()).await {
+                    Ok(_)
+                    | Err(AsyncMethodCallError::NoReply)
+                    | Err(AsyncMethodCallError::EngineDestroyed) => {}
+                    Err(error) => warn!("{method} failed: {error}"),
+                }
+            }
+        });
+    }
+
+    pub fn is_terminating(&self) -> bool {
+        self.terminating.get()
+    }
+
+    // Asks termination hooks and Dart whether application can terminate and
+    // terminates if none of them vetoes. Does nothing if termination is
+    // already in progress.
+    pub fn request_termination(&self) {
+        if self.terminated.get() || self.terminating.replace(true) {
+            return;
+        }
+        let context = match self.context.get() {
+            Some(context) => context,
+            None => return,
+        };
+        let weak_self = self.weak_self.clone();
+        context.run_loop.borrow().spawn(async move {
+            let allowed = Self::can_terminate(weak_self.clone()).await;
+            if let Some(manager) = weak_self.upgrade() {
+                let manager = manager.borrow();
+                // termination could have been forced in the meanwhile
+                if !manager.terminating.get() {
+                    return;
+                }
+                if allowed {
+                    manager.terminate_now();
+                } else {
+                    manager.terminating.set(false);
+                }
+            }
+        });
+    }
+
+    // Shuts down all engines and stops the run loop without asking anyone.
+    pub fn terminate_now(&self) {
+        self.terminating.set(false);
+        // removing last engine must not request termination again
+        self.terminated.set(true);
+        if let Some(context) = self.context.get() {
+            // Engine shutdown notifies handlers (including this one); Do it
+            // outside of current call stack
+            let context_weak = self.context.clone();
+            context
+                .run_loop
+                .borrow()
+                .schedule_now(move || {
+                    if let Some(context) = context_weak.get() {
+                        context.engine_manager.borrow_mut().shut_down().ok_log();
+                        context.run_loop.borrow().stop();
+                    }
+                })
+                .detach();
+        }
+    }
+
+    async fn can_terminate(weak_self: Weak<RefCell<Self>>) -> bool {
+        let (hooks, invoker, engines) = match weak_self.upgrade() {
+            Some(manager) => {
+                let manager = manager.borrow();
+                let hooks: Vec<_> = manager
+                    .terminate_hooks
+                    .borrow()
+                    .iter()
+                    .map(|(_, hook)| hook.clone())
+                    .collect();
+                let engines = match manager.context.get() {
+                    Some(context) => context.engine_manager.borrow().get_all_engines(),
+                    None => return true,
+                };
+                (hooks, manager.invoker.clone(), engines)
+            }
+            None => return true,
+        };
+
+        for hook in hooks {
+            if !hook().await {
+                return false;
+            }
+        }
+
+        let replies = join_all(engines.into_iter().map(|engine| {
+            let invoker = invoker.clone();
+            async move {
+                invoker
+                    .call_method(
+                        engine,
+                        method::application::TERMINATE_REQUESTED,
+                        Value::Null,
+                    )
+                    .await
+            }
+        }))
+        .await;
+
+        replies.into_iter().all(|reply| match reply {
+            Ok(Value::Bool(allowed)) => allowed,
+            Ok(_) => true,
+            // engine not interested or gone
+            Err(AsyncMethodCallError::NoReply) | Err(AsyncMethodCallError::EngineDestroyed) => true,
+            Err(error) => {
+                warn!("Termination request failed: {error}");
+                true
+            }
+        })
+    }
+}
+
+impl LaunchArguments {
+    // Arguments starting with '-' (until '--') are options and are skipped;
+    // Arguments with URL scheme are URLs, everything else is a file path
+    // relative to working directory.
+    pub fn from_launch(launch: SecondaryInstanceLaunch) -> Self {
+        let mut files = Vec::new();
+        let mut urls = Vec::new();
+        let mut options_ended = false;
+        for argument in launch.arguments.iter().skip(1) {
+            if !options_ended && argument == "--" {
+                options_ended = true;
+            } else if !options_ended && argument.starts_with('-') {
+                continue;
+            } else if let Some(path) = argument.strip_prefix("file://") {
+                // skip host (usually empty or localhost)
+                let path = &path[path.find('/').unwrap_or(path.len())..];
+                files.push(percent_decode(path));
+            } else if has_url_scheme(argument) {
+                urls.push(argument.clone());
+            } else {
+                let path = Path::new(&launch.working_directory).join(argument);
+                files.push(path.to_string_lossy().into_owned());
+            }
+        }
+        Self {
+            arguments: launch.arguments,
+            working_directory: launch.working_directory,
+            files,
+            urls,
+        }
+    }
+}
+
+// Single letter schemes are not accepted to not confuse Windows drive letters
+// with URLs.
+fn has_url_scheme(argument: &str) -> bool {
+    match argument.find(':') {
+        Some(len) if len > 1 => argument[..len].chars().enumerate().all(|(i, c)| {
+            c.is_ascii_alphabetic() || (i > 0 && (c.is_ascii_digit() || "+-.".contains(c)))
+        }),
+        _ => false,
+    }
+}
+
+fn percent_decode(s: &str) -> String {
+    let mut res = Vec::with_capacity(s.len());
+    let bytes = s.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = match bytes[i] {
+            b'%' => s
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+            _ => None,
+        };
+        match escaped {
+            Some(byte) => {
+                res.push(byte);
+                i += 3;
+            }
+            None => {
+                res.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&res).into_owned()
+}
+
+#[async_trait(?Send)]
+impl AsyncMethodCallHandler for ApplicationManager {
+    async fn on_method_call(
+        &self,
+        call: MethodCall<Value>,
+        _engine: EngineHandle,
+    ) -> MethodCallResult<Value> {
+        match call.method.as_str() {
+            method::application::GET_LAUNCH_ARGUMENTS => {
+                Ok(to_value(&self.launch_arguments).unwrap())
+            }
+            method::application::IS_ACTIVE => Ok(Value::Bool(self.is_active())),
+            method::application::QUIT => {
+                self.request_termination();
+                Ok(Value::Null)
+            }
+            _ => Err(MethodCallError::from_code_message(
+                "not_implemented",
+                "Method not implemented",
+            )),
+        }
+    }
+
+    fn assign_weak_self(&mut self, weak_self: Weak<RefCell<Self>>) {
+        self.weak_self.set(weak_self);
+    }
+
+    fn assign_invoker(&mut self, invoker: AsyncMethodInvoker) {
+        self.invoker.set(invoker);
+    }
+}
+
+#[cfg(all(test, fd_watch, feature = "null-backend"))]
+mod tests {
+    use std::{
+        cell::{Cell, RefCell},
+        rc::Rc,
+        time::Duration,
+    };
+
+    use crate::{
+        codec::Value,
+        shell::{
+            api_model::{LaunchArguments, SecondaryInstanceLaunch},
+            Context, ContextOptions, FakeDart, WindowHandle,
+        },
+    };
+
+    #[test]
+    fn test_launch_arguments() {
+        let arguments = LaunchArguments::from_launch(SecondaryInstanceLaunch {
+            arguments: vec![
+                "app".into(),
+                "--verbose".into(),
+                "notes.txt".into(),
+                "myapp://open?id=1".into(),
+                "file:///tmp/My%20File.txt".into(),
+                "--".into(),
+                "-dash.txt".into(),
+            ],
+            working_directory: "/home/user".into(),
+        });
+        assert_eq!(
+            arguments.files,
+            vec![
+                "/home/user/notes.txt",
+                "/tmp/My File.txt",
+                "/home/user/-dash.txt"
+            ]
+        );
+        assert_eq!(arguments.urls, vec!["myapp://open?id=1"]);
+        assert_eq!(arguments.arguments.len(), 7);
+    }
+
+    #[test]
+    fn test_terminate_on_signal() {
+        let context = Context::new(ContextOptions {
+            handle_termination_signals: true,
+            ..Default::default()
+        })
+        .unwrap();
+        let dart = FakeDart::new(&context).unwrap();
+
+        let allow = Rc::new(Cell::new(false));
+        let allow_clone = allow.clone();
+        dart.set_method_handler("nativeshell/application", move |call| {
+            assert_eq!(call.method, "Application.terminateRequested");
+            Ok(Value::Bool(allow_clone.get()))
+        });
+        let hook_calls = Rc::new(Cell::new(0));
+        let hook_calls_clone = hook_calls.clone();
+        let _hook = context
+            .application_manager
+            .borrow()
+            .borrow()
+            .register_terminate_hook(move || {
+                hook_calls_clone.set(hook_calls_clone.get() + 1);
+                async { true }
+            });
+
+        unsafe { libc::raise(libc::SIGTERM) };
+        dart.pump_for(Duration::from_millis(50));
+        assert_eq!(hook_calls.get(), 1);
+        assert!(!context
+            .application_manager
+            .borrow()
+            .borrow()
+            .is_terminating());
+        assert_eq!(context.engine_manager.borrow().get_all_engines().len(), 1);
+
+        allow.set(true);
+        unsafe { libc::raise(libc::SIGTERM) };
+        dart.pump_for(Duration::from_millis(50));
+        assert_eq!(hook_calls.get(), 2);
+        assert!(context.engine_manager.borrow().get_all_engines().is_empty());
+    }
+
+    #[test]
+    fn test_activation_and_quit() {
+        let context = Context::new(ContextOptions::default()).unwrap();
+        let dart = FakeDart::new(&context).unwrap();
+        let activations = Rc::new(RefCell::new(Vec::new()));
+        let activations_clone = activations.clone();
+        let _listener = context
+            .application_manager
+            .borrow()
+            .borrow()
+            .register_activation_listener(move |active| {
+                activations_clone.borrow_mut().push(active);
+            });
+        let take_calls = || -> Vec<String> {
+            dart.take_method_calls("nativeshell/application")
+                .into_iter()
+                .map(|call| call.method)
+                .collect()
+        };
+
+        {
+            let manager = context.application_manager.borrow();
+            let manager = manager.borrow();
+            manager.window_activation_changed(WindowHandle(1), true);
+            // focus moving to another window is not a deactivation
+            manager.window_activation_changed(WindowHandle(1), false);
+            manager.window_activation_changed(WindowHandle(2), true);
+        }
+        dart.pump_for(Duration::from_millis(100));
+        assert_eq!(*activations.borrow(), vec![true]);
+        assert_eq!(take_calls(), vec!["Application.onActivated"]);
+
+        context
+            .application_manager
+            .borrow()
+            .borrow()
+            .window_activation_changed(WindowHandle(2), false);
+        dart.pump_for(Duration::from_millis(100));
+        assert_eq!(*activations.borrow(), vec![true, false]);
+        assert_eq!(take_calls(), vec!["Application.onDeactivated"]);
+
+        let reply = dart.invoke_method("nativeshell/application", "Application.quit", Value::Null);
+        assert_eq!(reply, Some(Ok(Value::Null)));
+        dart.pump_for(Duration::from_millis(50));
+        assert_eq!(take_calls(), vec!["Application.terminateRequested"]);
+        assert!(context.engine_manager.borrow().get_all_engines().is_empty());
+    }
+}
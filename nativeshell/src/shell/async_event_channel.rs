@@ -0,0 +1,250 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    rc::{Rc, Weak},
+};
+
+use async_trait::async_trait;
+
+use crate::codec::{MethodCallError, Value};
+
+use super::{Context, EngineHandle, EventSink, Handle};
+
+// Async counterpart of EventChannelHandler. Listening can await and fail, in
+// which case the error is reported to the Dart listener.
+//
+// Handler is only accessed through shared references, as other methods may be
+// called while on_listen is pending; Use interior mutability for state.
+#[async_trait(?Send)]
+pub trait AsyncEventChannelHandler: Sized + 'static {
+    // Called when Dart starts listening; Implementation can store the event sink
+    // and use it to send events. Returning error fails the listen call.
+    async fn on_listen(
+        self: Rc<Self>,
+        sink: EventSink,
+        listen_argument: Value,
+    ) -> std::result::Result<(), MethodCallError<Value>>;
+
+    // Called when Dart stops listening or engine is destroyed. May be called
+    // while on_listen for same sink is still pending.
+    fn on_cancel(&self, sink_id: i64);
+
+    // Implementation can store weak reference if it needs to pass it around.
+    // Guaranteed to call before any other methods.
+    fn assign_weak_self(&mut self, _weak_self: Weak<Self>) {}
+
+    // Registers itself for handling event sink registration methods.
+    fn register(self, context: Context, channel: &str) -> RegisteredAsyncEventChannel<Self> {
+        RegisteredAsyncEventChannel::new(context, channel, self)
+    }
+}
+
+struct Sinks {
+    next_sink_id: i64,
+    engine_to_sink: HashMap<EngineHandle, i64>,
+}
+
+pub struct RegisteredAsyncEventChannel<T: AsyncEventChannelHandler> {
+    context: Context,
+    channel: String,
+    _destroy_engine_handle: Handle,
+    handler: Rc<T>,
+}
+
+impl<T: AsyncEventChannelHandler> RegisteredAsyncEventChannel<T> {
+    pub fn new(context: Context, channel: &str, mut handler: T) -> Self {
+        let context_ref = context.get().unwrap();
+
+        let handler = Rc::new_cyclic(|weak_self| {
+            handler.assign_weak_self(weak_self.clone());
+            handler
+        });
+
+        let sinks = Rc::new(RefCell::new(Sinks {
+            next_sink_id: 1,
+            engine_to_sink: HashMap::new(),
+        }));
+
+        let handler_clone = handler.clone();
+        let sinks_clone = sinks.clone();
+        let destroy_engine_handle = context_ref
+            .engine_manager
+            .borrow_mut()
+            .register_destroy_engine_notification(move |engine| {
+                let sink_id = sinks_clone.borrow_mut().engine_to_sink.remove(&engine);
+                if let Some(sink_id) = sink_id {
+                    handler_clone.on_cancel(sink_id);
+                }
+            });
+
+        let handler_clone = handler.clone();
+        let context_clone = context.clone();
+        let channel_name = channel.to_owned();
+        context_ref
+            .message_manager
+            .borrow_mut()
+            .register_method_handler(channel, move |call, reply, engine| {
+                let context = match context_clone.get() {
+                    Some(context) => context,
+                    None => return,
+                };
+                if call.method != "listen" && call.method != "cancel" {
+                    return;
+                }
+                // Dart only has one subscription per engine; Listening again replaces
+                // the previous sink
+                let previous = sinks.borrow_mut().engine_to_sink.remove(&engine);
+                if let Some(sink_id) = previous {
+                    handler_clone.on_cancel(sink_id);
+                }
+                match call.method.as_str() {
+                    "listen" => {
+                        let sink_id = {
+                            let mut sinks = sinks.borrow_mut();
+                            let sink_id = sinks.next_sink_id;
+                            sinks.next_sink_id += 1;
+                            sinks.engine_to_sink.insert(engine, sink_id);
+                            sink_id
+                        };
+                        let sink =
+                            EventSink::new(context_clone.clone(), sink_id, &channel_name, engine);
+                        let handler_clone = handler_clone.clone();
+                        let sinks = sinks.clone();
+                        context.run_loop.borrow().spawn(async move {
+                            let result = handler_clone.on_listen(sink, call.args).await;
+                            if result.is_err() {
+                                let mut sinks = sinks.borrow_mut();
+                                if sinks.engine_to_sink.get(&engine) == Some(&sink_id) {
+                                    sinks.engine_to_sink.remove(&engine);
+                                }
+                            }
+                            reply.send(result.map(|_| Value::Null));
+                        });
+                    }
+                    "cancel" => {
+                        reply.send_ok(Value::Null);
+                    }
+                    _ => {}
+                }
+            });
+
+        Self {
+            context,
+            channel: channel.into(),
+            _destroy_engine_handle: destroy_engine_handle,
+            handler,
+        }
+    }
+
+    pub fn handler(&self) -> &Rc<T> {
+        &self.handler
+    }
+}
+
+impl<T: AsyncEventChannelHandler> Drop for RegisteredAsyncEventChannel<T> {
+    fn drop(&mut self) {
+        if let Some(context) = self.context.get() {
+            context
+                .message_manager
+                .borrow_mut()
+                .unregister_method_handler(&self.channel);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "null-backend"))]
+mod tests {
+    use std::{
+        cell::RefCell,
+        rc::{Rc, Weak},
+    };
+
+    use async_trait::async_trait;
+
+    use super::AsyncEventChannelHandler;
+    use crate::{
+        codec::{MethodCallError, Value},
+        shell::{Context, ContextOptions, EventSink, FakeDart},
+        util::FutureCompleter,
+    };
+
+    #[derive(Default)]
+    struct Handler {
+        weak_self: Weak<Handler>,
+        listening: RefCell<Vec<i64>>,
+        cancelled: RefCell<Vec<i64>>,
+        pending: RefCell<Option<FutureCompleter<()>>>,
+    }
+
+    #[async_trait(?Send)]
+    impl AsyncEventChannelHandler for Handler {
+        async fn on_listen(
+            self: Rc<Self>,
+            sink: EventSink,
+            listen_argument: Value,
+        ) -> std::result::Result<(), MethodCallError<Value>> {
+            match listen_argument {
+                Value::String(s) if s == "fail" => {
+                    return Err(MethodCallError::from_code_message(
+                        "failed",
+                        "listen failed",
+                    ))
+                }
+                Value::String(s) if s == "wait" => {
+                    let (future, completer) = FutureCompleter::new();
+                    self.pending.borrow_mut().replace(completer);
+                    future.await;
+                }
+                _ => {}
+            }
+            self.listening.borrow_mut().push(sink.id());
+            Ok(())
+        }
+
+        fn on_cancel(&self, sink_id: i64) {
+            self.cancelled.borrow_mut().push(sink_id);
+        }
+
+        fn assign_weak_self(&mut self, weak_self: Weak<Self>) {
+            self.weak_self = weak_self;
+        }
+    }
+
+    #[test]
+    fn test_listen() {
+        let context = Context::new(ContextOptions::default()).unwrap();
+        let dart = FakeDart::new(&context).unwrap();
+        let channel = Handler::default().register(context.weak(), "events");
+        let handler = channel.handler().clone();
+        assert!(Rc::ptr_eq(&handler.weak_self.upgrade().unwrap(), &handler));
+
+        // Failed listen is reported to Dart and the sink is not registered
+        let res = dart.invoke_method("events", "listen", "fail".into());
+        assert!(matches!(res, Some(Err(error)) if error.code == "failed"));
+        let res = dart.invoke_method("events", "cancel", Value::Null);
+        assert_eq!(res, Some(Ok(Value::Null)));
+        assert!(handler.cancelled.borrow().is_empty());
+
+        let res = dart.invoke_method("events", "listen", Value::Null);
+        assert_eq!(res, Some(Ok(Value::Null)));
+        let sink_id = handler.listening.borrow()[0];
+        dart.invoke_method("events", "cancel", Value::Null);
+        assert_eq!(*handler.cancelled.borrow(), vec![sink_id]);
+
+        // Cancel while listen is pending
+        let res = dart.invoke_method("events", "listen", "wait".into());
+        assert_eq!(res, None);
+        dart.invoke_method("events", "cancel", Value::Null);
+        assert_eq!(handler.cancelled.borrow().len(), 2);
+        let pending = handler.pending.borrow_mut().take().unwrap();
+        pending.complete(());
+        dart.pump();
+        assert_eq!(handler.listening.borrow().len(), 2);
+
+        // Destroying engine cancels active sink
+        dart.invoke_method("events", "listen", Value::Null);
+        let sink_id = handler.listening.borrow()[2];
+        dart.shut_down().unwrap();
+        assert_eq!(handler.cancelled.borrow().last(), Some(&sink_id));
+    }
+}
@@ -1,6 +1,7 @@
 use std::{
     cell::{Ref, RefCell, RefMut},
     rc::{Rc, Weak},
+    time::Duration,
 };
 
 use crate::{
@@ -27,6 +28,13 @@ pub enum AsyncMethodCallError<V> {
     ShellError(Error),
     // Error originating from Flutter code
     MethodCallError(MethodCallError<V>),
+    // No response received within specified timeout
+    Timeout,
+    // Engine was destroyed while waiting for response
+    EngineDestroyed,
+    // Empty or malformed response; Usually means there is no handler registered
+    // for the channel on Dart side or the isolate has been restarted
+    NoReply,
 }
 
 impl std::fmt::Display for AsyncMethodCallError<Value> {
@@ -34,6 +42,11 @@ impl std::fmt::Display for AsyncMethodCallError<Value> {
         match self {
             AsyncMethodCallError::ShellError(error) => write!(f, "NativeShell Error: {error}"),
             AsyncMethodCallError::MethodCallError(error) => write!(f, "MethodCallError: {error}"),
+            AsyncMethodCallError::Timeout => write!(f, "Method call timed out"),
+            AsyncMethodCallError::EngineDestroyed => {
+                write!(f, "Engine was destroyed before method call finished")
+            }
+            AsyncMethodCallError::NoReply => write!(f, "No reply received for method call"),
         }
     }
 }
@@ -43,11 +56,36 @@ impl std::error::Error for AsyncMethodCallError<Value> {}
 pub type AsyncMethodCallResult<V> = std::result::Result<V, AsyncMethodCallError<V>>;
 
 impl AsyncMethodInvoker {
+    // Dropping the returned future cancels the call; The method will still be
+    // invoked on Dart side, but the response is ignored.
     pub async fn call_method(
         &self,
         engine: EngineHandle,
         method: &str,
         args: Value,
+    ) -> AsyncMethodCallResult<Value> {
+        self.call_method_impl(engine, method, args, None).await
+    }
+
+    // Like call_method, but fails with AsyncMethodCallError::Timeout if there is
+    // no response within given duration.
+    pub async fn call_method_with_timeout(
+        &self,
+        engine: EngineHandle,
+        method: &str,
+        args: Value,
+        timeout: Duration,
+    ) -> AsyncMethodCallResult<Value> {
+        self.call_method_impl(engine, method, args, Some(timeout))
+            .await
+    }
+
+    async fn call_method_impl(
+        &self,
+        engine: EngineHandle,
+        method: &str,
+        args: Value,
+        timeout: Option<Duration>,
     ) -> AsyncMethodCallResult<Value> {
         let invoker = MethodInvoker::new(
             self.context.clone(),
@@ -60,23 +98,12 @@ impl AsyncMethodInvoker {
             completer,
         ) = FutureCompleter::<AsyncMethodCallResult<Value>>::new();
 
-        let completer = Rc::new(RefCell::new(Some(completer)));
-        let completer_clone = completer.clone();
-        let res = invoker.call_method(method, args, move |reply| {
-            let completer = completer_clone.borrow_mut().take().expect(
-                "Reply block was invoked more than once or after call_method failed with error.",
-            );
-            match reply {
-                Ok(value) => completer.complete(Ok(value)),
-                Err(error) => completer.complete(Err(AsyncMethodCallError::MethodCallError(error))),
-            }
-        });
-        if let Err(error) = res {
-            let completer = completer
-                .take()
-                .expect("call_method failed with error but reply block was already invoked.");
-            completer.complete(Err(AsyncMethodCallError::ShellError(error)));
-        };
+        // Keep the handle alive while waiting; Dropping it cancels the call
+        let _handle = invoker
+            .call_method_with_timeout(method, args, timeout, move |reply| {
+                completer.complete(reply)
+            })
+            .map_err(AsyncMethodCallError::ShellError)?;
         future.await
     }
 }
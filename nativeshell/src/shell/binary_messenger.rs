@@ -1,4 +1,9 @@
-use super::platform::binary_messenger::PlatformBinaryMessenger;
+use std::rc::Rc;
+
+use super::{
+    channel_buffering::ChannelBuffering, platform::binary_messenger::PlatformBinaryMessenger,
+    ChannelRecorder, EngineHandle, RecordedDirection,
+};
 use crate::Result;
 
 pub struct BinaryMessengerReply {
@@ -26,20 +31,52 @@ impl BinaryMessengerReply {
 
 pub struct BinaryMessenger {
     messenger: PlatformBinaryMessenger,
+    recorder: Option<(Rc<ChannelRecorder>, EngineHandle)>,
+    buffering: Option<(Rc<ChannelBuffering>, EngineHandle)>,
 }
 
 impl BinaryMessenger {
     pub fn new(messenger_impl: PlatformBinaryMessenger) -> Self {
         BinaryMessenger {
             messenger: messenger_impl,
+            recorder: None,
+            buffering: None,
         }
     }
 
+    pub(super) fn set_buffering(&mut self, buffering: Rc<ChannelBuffering>, engine: EngineHandle) {
+        self.buffering = Some((buffering, engine));
+    }
+
+    // Records all messages going through this messenger; Only affects handlers
+    // registered afterwards.
+    pub(super) fn set_recorder(&mut self, recorder: Rc<ChannelRecorder>, engine: EngineHandle) {
+        self.recorder = Some((recorder, engine));
+    }
+
     pub fn register_channel_handler<F>(&self, channel: &str, callback: F)
     where
         F: Fn(&[u8], BinaryMessengerReply) + 'static,
     {
-        self.messenger.register_channel_handler(channel, callback);
+        match &self.recorder {
+            Some((recorder, engine)) => {
+                let recorder = recorder.clone();
+                let engine = *engine;
+                let channel_name = channel.to_owned();
+                self.messenger
+                    .register_channel_handler(channel, move |data, reply| {
+                        let reply = ChannelRecorder::record_inbound(
+                            &recorder,
+                            engine,
+                            &channel_name,
+                            data,
+                            reply,
+                        );
+                        callback(data, reply);
+                    });
+            }
+            None => self.messenger.register_channel_handler(channel, callback),
+        }
     }
 
     pub fn unregister_channel_handler(&self, channel: &str) {
@@ -50,19 +87,66 @@ impl BinaryMessenger {
     where
         F: FnOnce(&[u8]) + 'static,
     {
-        self.messenger
-            .send_message(channel, message, reply_callback)
-            .map_err(|e| e.into())
+        let reply_callback: Box<dyn FnOnce(&[u8])> = match &self.buffering {
+            Some((buffering, engine)) => {
+                match buffering.buffer(*engine, channel, message, Some(Box::new(reply_callback))) {
+                    Some(reply_callback) => reply_callback.unwrap(),
+                    None => return Ok(()),
+                }
+            }
+            None => Box::new(reply_callback),
+        };
+        match &self.recorder {
+            Some((recorder, engine)) => {
+                let id =
+                    recorder.record(RecordedDirection::Outbound, None, *engine, channel, message);
+                let recorder = recorder.clone();
+                let engine = *engine;
+                let channel_name = channel.to_owned();
+                self.messenger
+                    .send_message(channel, message, move |reply| {
+                        recorder.record(
+                            RecordedDirection::OutboundReply,
+                            Some(id),
+                            engine,
+                            &channel_name,
+                            reply,
+                        );
+                        reply_callback(reply);
+                    })
+                    .map_err(|e| e.into())
+            }
+            None => self
+                .messenger
+                .send_message(channel, message, reply_callback)
+                .map_err(|e| e.into()),
+        }
     }
 
     // like "send_message" but wihtout reply
     pub fn post_message(&self, channel: &str, message: &[u8]) -> Result<()> {
+        if let Some((buffering, engine)) = &self.buffering {
+            if buffering.buffer(*engine, channel, message, None).is_none() {
+                return Ok(());
+            }
+        }
+        if let Some((recorder, engine)) = &self.recorder {
+            recorder.record(RecordedDirection::Outbound, None, *engine, channel, message);
+        }
         self.messenger
             .post_message(channel, message)
             .map_err(|e| e.into())
     }
 }
 
+impl Drop for BinaryMessenger {
+    fn drop(&mut self) {
+        if let Some((buffering, engine)) = &self.buffering {
+            buffering.engine_removed(*engine);
+        }
+    }
+}
+
 impl Drop for BinaryMessengerReply {
     fn drop(&mut self) {
         if !self.sent {
@@ -0,0 +1,250 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::{Rc, Weak},
+    time::Duration,
+};
+
+use log::error;
+
+use super::{Context, EngineHandle, Handle};
+
+type PendingReply = Box<dyn FnOnce(&[u8])>;
+
+struct PendingMessage {
+    message: Vec<u8>,
+    // None for posted messages
+    reply: Option<PendingReply>,
+}
+
+struct PendingChannel {
+    messages: Vec<PendingMessage>,
+    timeout: Option<Handle>,
+}
+
+// Queues messages sent from Rust on buffered channels until Dart signals that
+// it has registered the channel handler, or until timeout elapses since first
+// message was queued. After that the channel is not buffered for the engine
+// anymore.
+pub(super) struct ChannelBuffering {
+    context: Context,
+    weak_self: RefCell<Weak<ChannelBuffering>>,
+    timeouts: RefCell<HashMap<String, Duration>>,
+    ready: RefCell<HashSet<(EngineHandle, String)>>,
+    // Engines whose Dart side doesn't signal channel readiness
+    unbuffered: RefCell<HashSet<EngineHandle>>,
+    pending: RefCell<HashMap<(EngineHandle, String), PendingChannel>>,
+}
+
+impl ChannelBuffering {
+    pub fn new(context: Context) -> Rc<Self> {
+        let res = Rc::new(Self {
+            context,
+            weak_self: RefCell::new(Weak::new()),
+            timeouts: RefCell::new(HashMap::new()),
+            ready: RefCell::new(HashSet::new()),
+            unbuffered: RefCell::new(HashSet::new()),
+            pending: RefCell::new(HashMap::new()),
+        });
+        *res.weak_self.borrow_mut() = Rc::downgrade(&res);
+        res
+    }
+
+    pub fn enable(&self, channel: &str, timeout: Duration) {
+        self.timeouts.borrow_mut().insert(channel.into(), timeout);
+    }
+
+    // Stops buffering the channel and flushes messages queued so far.
+    pub fn disable(&self, channel: &str) {
+        self.timeouts.borrow_mut().remove(channel);
+        let engines: Vec<EngineHandle> = self
+            .pending
+            .borrow()
+            .keys()
+            .filter(|(_, c)| c == channel)
+            .map(|(engine, _)| *engine)
+            .collect();
+        for engine in engines {
+            self.flush(engine, channel, false);
+        }
+        self.ready.borrow_mut().retain(|(_, c)| c != channel);
+    }
+
+    pub fn set_ready(&self, engine: EngineHandle, channel: &str) {
+        if self.timeouts.borrow().contains_key(channel) {
+            self.ready.borrow_mut().insert((engine, channel.into()));
+        }
+        self.flush(engine, channel, false);
+    }
+
+    // Stops buffering any channel for the engine and flushes messages queued
+    // so far.
+    pub fn disable_for_engine(&self, engine: EngineHandle) {
+        self.unbuffered.borrow_mut().insert(engine);
+        let channels: Vec<String> = self
+            .pending
+            .borrow()
+            .keys()
+            .filter(|(e, _)| *e == engine)
+            .map(|(_, channel)| channel.clone())
+            .collect();
+        for channel in channels {
+            self.flush(engine, &channel, false);
+        }
+    }
+
+    // Queues the message if channel is buffered for the engine. Otherwise returns
+    // the reply back so that the message can be sent right away.
+    pub fn buffer(
+        &self,
+        engine: EngineHandle,
+        channel: &str,
+        message: &[u8],
+        reply: Option<PendingReply>,
+    ) -> Option<Option<PendingReply>> {
+        let timeout = match self.timeouts.borrow().get(channel) {
+            Some(timeout) => *timeout,
+            None => return Some(reply),
+        };
+        let key = (engine, channel.to_owned());
+        if self.ready.borrow().contains(&key) || self.unbuffered.borrow().contains(&engine) {
+            return Some(reply);
+        }
+        let mut pending = self.pending.borrow_mut();
+        let pending = pending.entry(key).or_insert_with(|| PendingChannel {
+            messages: Vec::new(),
+            timeout: self.schedule_timeout(engine, channel, timeout),
+        });
+        pending.messages.push(PendingMessage {
+            message: message.into(),
+            reply,
+        });
+        None
+    }
+
+    pub fn engine_removed(&self, engine: EngineHandle) {
+        self.ready.borrow_mut().retain(|(e, _)| *e != engine);
+        self.unbuffered.borrow_mut().remove(&engine);
+        self.pending.borrow_mut().retain(|(e, _), _| *e != engine);
+    }
+
+    fn schedule_timeout(
+        &self,
+        engine: EngineHandle,
+        channel: &str,
+        timeout: Duration,
+    ) -> Option<Handle> {
+        let context = self.context.get()?;
+        let weak_self = self.weak_self.borrow().clone();
+        let channel = channel.to_owned();
+        let handle = context.run_loop.borrow().schedule(timeout, move || {
+            if let Some(buffering) = weak_self.upgrade() {
+                buffering
+                    .ready
+                    .borrow_mut()
+                    .insert((engine, channel.clone()));
+                buffering.flush(engine, &channel, true);
+            }
+        });
+        Some(handle)
+    }
+
+    fn flush(&self, engine: EngineHandle, channel: &str, timed_out: bool) {
+        let pending = self
+            .pending
+            .borrow_mut()
+            .remove(&(engine, channel.to_owned()));
+        let pending = match pending {
+            Some(pending) => pending,
+            None => return,
+        };
+        if let Some(mut timeout) = pending.timeout {
+            if timed_out {
+                // Timer is being executed, nothing to cancel
+                timeout.detach();
+            }
+        }
+        let context = match self.context.get() {
+            Some(context) => context,
+            None => return,
+        };
+        let engine_manager = context.engine_manager.borrow();
+        let engine = match engine_manager.get_engine(engine) {
+            Some(engine) => engine,
+            None => return,
+        };
+        let messenger = engine.binary_messenger();
+        for message in pending.messages {
+            let res = match message.reply {
+                Some(reply) => messenger.send_message(channel, &message.message, reply),
+                None => messenger.post_message(channel, &message.message),
+            };
+            if let Err(err) = res {
+                error!("Failed to send buffered message on {channel}: {err}");
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "null-backend"))]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        codec::Value,
+        shell::{
+            api_constants::{channel, method},
+            Context, ContextOptions, FakeDart,
+        },
+    };
+
+    #[test]
+    fn test_buffering() {
+        let context = Context::new(ContextOptions::default()).unwrap();
+        let dart = FakeDart::new(&context).unwrap();
+        context
+            .message_manager
+            .borrow_mut()
+            .enable_buffering("buffered", Duration::from_millis(50));
+
+        let messenger = context
+            .message_manager
+            .borrow()
+            .get_binary_message_sender(dart.engine(), "buffered");
+        messenger.send_message(&[1], |_| {}).unwrap();
+        messenger.send_message(&[2], |_| {}).unwrap();
+        dart.pump();
+        assert!(dart.take_messages("buffered").is_empty());
+
+        let res = dart.invoke_method(
+            channel::CHANNEL_READINESS,
+            method::channel_readiness::READY,
+            Value::String("buffered".into()),
+        );
+        assert_eq!(res, Some(Ok(Value::Null)));
+        dart.pump();
+        assert_eq!(dart.take_messages("buffered"), vec![vec![1], vec![2]]);
+
+        // ready channel is not buffered anymore
+        messenger.send_message(&[3], |_| {}).unwrap();
+        dart.pump();
+        assert_eq!(dart.take_messages("buffered"), vec![vec![3]]);
+
+        // flushed after timeout when Dart never signals readiness
+        context
+            .message_manager
+            .borrow_mut()
+            .enable_buffering("late", Duration::from_millis(20));
+        let messenger = context
+            .message_manager
+            .borrow()
+            .get_binary_message_sender(dart.engine(), "late");
+        messenger.send_message(&[4], |_| {}).unwrap();
+        dart.pump();
+        assert!(dart.take_messages("late").is_empty());
+        dart.pump_for(Duration::from_millis(50));
+        assert_eq!(dart.take_messages("late"), vec![vec![4]]);
+
+        dart.shut_down().unwrap();
+    }
+}
@@ -0,0 +1,424 @@
+use std::time::{Duration, Instant};
+
+use crate::codec::{MessageReply, MethodCall, MethodCallReply, MethodCallResult, Value};
+
+use super::{Context, ContextRef, EngineHandle};
+
+// Describes intercepted inbound message or method call.
+#[derive(Clone, Debug)]
+pub struct ChannelCallInfo {
+    pub channel: String,
+    // Method name; None for plain messages
+    pub method: Option<String>,
+    pub engine: EngineHandle,
+    pub received: Instant,
+}
+
+// Observes and alters traffic going through MessageManager handlers. Interceptors
+// are configured in ContextOptions::channel_interceptors and called in order for
+// inbound calls and in reverse order for replies.
+//
+// Only handlers registered with register_message_handler, register_method_handler
+// and register_method_handler_ref are intercepted; Handlers with custom codecs
+// and binary or string handlers are not.
+pub trait ChannelInterceptor {
+    // Called for each inbound method call; Can rewrite the call or short-circuit
+    // it by returning result, in which case the handler is not called and
+    // result is sent back through interceptors that have seen the call.
+    fn intercept_method_call(
+        &self,
+        _info: &ChannelCallInfo,
+        _call: &mut MethodCall<Value>,
+    ) -> Option<MethodCallResult<Value>> {
+        None
+    }
+
+    // Called before the method call result is sent; Can rewrite the result.
+    // Not called when handler drops the reply without responding, see
+    // intercept_dropped_reply.
+    fn intercept_method_reply(
+        &self,
+        _info: &ChannelCallInfo,
+        _result: &mut MethodCallResult<Value>,
+        _elapsed: Duration,
+    ) {
+    }
+
+    // Called for each inbound message; Can rewrite the message or short-circuit
+    // it by returning reply.
+    fn intercept_message(&self, _info: &ChannelCallInfo, _message: &mut Value) -> Option<Value> {
+        None
+    }
+
+    // Called before reply to message is sent; Can rewrite the reply.
+    fn intercept_message_reply(
+        &self,
+        _info: &ChannelCallInfo,
+        _reply: &mut Value,
+        _elapsed: Duration,
+    ) {
+    }
+
+    // Called when handler drops the reply to method call or message without
+    // responding; Dart receives empty reply (method not implemented).
+    fn intercept_dropped_reply(&self, _info: &ChannelCallInfo, _elapsed: Duration) {}
+}
+
+// Reply that went through interceptors; Notifies them if it is dropped before
+// being sent.
+struct InterceptedReply {
+    context: Context,
+    info: ChannelCallInfo,
+    // Number of interceptors that have seen the call
+    seen: usize,
+    sent: bool,
+}
+
+impl InterceptedReply {
+    fn send<F>(mut self, mut f: F)
+    where
+        F: FnMut(&dyn ChannelInterceptor, &ChannelCallInfo, Duration),
+    {
+        self.sent = true;
+        let elapsed = self.info.received.elapsed();
+        with_interceptors(&self.context, self.seen, |interceptor| {
+            f(interceptor, &self.info, elapsed)
+        });
+    }
+}
+
+impl Drop for InterceptedReply {
+    fn drop(&mut self) {
+        if !self.sent {
+            let elapsed = self.info.received.elapsed();
+            with_interceptors(&self.context, self.seen, |interceptor| {
+                interceptor.intercept_dropped_reply(&self.info, elapsed);
+            });
+        }
+    }
+}
+
+// Runs method call through interceptors. Returns None if the call was
+// short-circuited and already replied to.
+pub(super) fn intercept_method_call(
+    context: &ContextRef,
+    channel: &str,
+    engine: EngineHandle,
+    mut call: MethodCall<Value>,
+    reply: MethodCallReply<Value>,
+) -> Option<(MethodCall<Value>, MethodCallReply<Value>)> {
+    let interceptors = &context.options.channel_interceptors;
+    if interceptors.is_empty() {
+        return Some((call, reply));
+    }
+    let info = ChannelCallInfo {
+        channel: channel.into(),
+        method: Some(call.method.clone()),
+        engine,
+        received: Instant::now(),
+    };
+    let mut short_circuit = None;
+    let mut seen = interceptors.len();
+    for (index, interceptor) in interceptors.iter().enumerate() {
+        if let Some(result) = interceptor.intercept_method_call(&info, &mut call) {
+            short_circuit = Some(result);
+            seen = index + 1;
+            break;
+        }
+    }
+    let intercepted = InterceptedReply {
+        context: context.weak(),
+        info,
+        seen,
+        sent: false,
+    };
+    let reply = reply.on_send(move |result| {
+        intercepted.send(|interceptor, info, elapsed| {
+            interceptor.intercept_method_reply(info, result, elapsed);
+        });
+    });
+    match short_circuit {
+        Some(result) => {
+            reply.send(result);
+            None
+        }
+        None => Some((call, reply)),
+    }
+}
+
+// Runs message through interceptors. Returns None if the message was
+// short-circuited and already replied to.
+pub(super) fn intercept_message(
+    context: &ContextRef,
+    channel: &str,
+    engine: EngineHandle,
+    mut message: Value,
+    reply: MessageReply<Value>,
+) -> Option<(Value, MessageReply<Value>)> {
+    let interceptors = &context.options.channel_interceptors;
+    if interceptors.is_empty() {
+        return Some((message, reply));
+    }
+    let info = ChannelCallInfo {
+        channel: channel.into(),
+        method: None,
+        engine,
+        received: Instant::now(),
+    };
+    let mut short_circuit = None;
+    let mut seen = interceptors.len();
+    for (index, interceptor) in interceptors.iter().enumerate() {
+        if let Some(value) = interceptor.intercept_message(&info, &mut message) {
+            short_circuit = Some(value);
+            seen = index + 1;
+            break;
+        }
+    }
+    let intercepted = InterceptedReply {
+        context: context.weak(),
+        info,
+        seen,
+        sent: false,
+    };
+    let reply = reply.on_send(move |value| {
+        intercepted.send(|interceptor, info, elapsed| {
+            interceptor.intercept_message_reply(info, value, elapsed);
+        });
+    });
+    match short_circuit {
+        Some(value) => {
+            reply.send(value);
+            None
+        }
+        None => Some((message, reply)),
+    }
+}
+
+// Calls first `count` interceptors in reverse order.
+fn with_interceptors<F>(context: &Context, count: usize, mut f: F)
+where
+    F: FnMut(&dyn ChannelInterceptor),
+{
+    if let Some(context) = context.get() {
+        let interceptors = &context.options.channel_interceptors;
+        for interceptor in interceptors.iter().take(count).rev() {
+            f(interceptor.as_ref());
+        }
+    }
+}
+
+#[cfg(all(test, feature = "null-backend"))]
+mod tests {
+    use std::{cell::RefCell, rc::Rc, time::Duration};
+
+    use crate::{
+        codec::{
+            MessageCodec, MethodCall, MethodCallError, MethodCallResult, StandardMethodCodec, Value,
+        },
+        shell::{Context, ContextOptions, ContextRef, FakeDart},
+    };
+
+    use super::{ChannelCallInfo, ChannelInterceptor};
+
+    type Log = Rc<RefCell<Vec<String>>>;
+
+    // Records what it sees; Rewrites "double" method arguments and answers
+    // "intercepted" calls and messages itself.
+    struct TestInterceptor {
+        name: &'static str,
+        log: Log,
+    }
+
+    impl TestInterceptor {
+        fn log(&self, entry: String) {
+            self.log
+                .borrow_mut()
+                .push(format!("{}: {}", self.name, entry));
+        }
+    }
+
+    impl ChannelInterceptor for TestInterceptor {
+        fn intercept_method_call(
+            &self,
+            info: &ChannelCallInfo,
+            call: &mut MethodCall<Value>,
+        ) -> Option<MethodCallResult<Value>> {
+            self.log(format!("call {} {}", info.channel, call.method));
+            match (call.method.as_str(), &call.args) {
+                ("double", Value::I64(v)) => {
+                    call.args = Value::I64(v * 2);
+                    None
+                }
+                ("intercepted", _) => Some(Ok(Value::String(self.name.into()))),
+                _ => None,
+            }
+        }
+
+        fn intercept_method_reply(
+            &self,
+            info: &ChannelCallInfo,
+            result: &mut MethodCallResult<Value>,
+            _elapsed: Duration,
+        ) {
+            self.log(format!("reply {} {:?}", info.channel, result));
+            if let Err(error) = result {
+                error.message = Some("rewritten".into());
+            }
+        }
+
+        fn intercept_message(&self, info: &ChannelCallInfo, message: &mut Value) -> Option<Value> {
+            self.log(format!("message {} {:?}", info.channel, message));
+            match message {
+                Value::String(s) if s == "intercepted" => Some(Value::String(self.name.into())),
+                _ => None,
+            }
+        }
+
+        fn intercept_message_reply(
+            &self,
+            info: &ChannelCallInfo,
+            reply: &mut Value,
+            _elapsed: Duration,
+        ) {
+            self.log(format!("message reply {} {:?}", info.channel, reply));
+        }
+
+        fn intercept_dropped_reply(&self, info: &ChannelCallInfo, _elapsed: Duration) {
+            self.log(format!("dropped {} {:?}", info.channel, info.method));
+        }
+    }
+
+    fn new_context(log: &Log) -> ContextRef {
+        let interceptor = |name| -> Box<dyn ChannelInterceptor> {
+            Box::new(TestInterceptor {
+                name,
+                log: log.clone(),
+            })
+        };
+        Context::new(ContextOptions {
+            channel_interceptors: vec![interceptor("first"), interceptor("second")],
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    fn take_log(log: &Log) -> Vec<String> {
+        log.borrow_mut().drain(..).collect()
+    }
+
+    #[test]
+    fn test_method_call() {
+        let log = Log::default();
+        let context = new_context(&log);
+        let dart = FakeDart::new(&context).unwrap();
+        context
+            .message_manager
+            .borrow_mut()
+            .register_method_handler("methods", |call, reply, _| match call.method.as_str() {
+                "double" => reply.send_ok(call.args),
+                "fail" => reply.send(Err(MethodCallError::from_code_message("code", "msg"))),
+                _ => {}
+            });
+        take_log(&log);
+
+        // arguments rewritten by both interceptors before handler, reply seen
+        // in reverse order
+        let res = dart.invoke_method("methods", "double", Value::I64(2));
+        assert_eq!(res, Some(Ok(Value::I64(8))));
+        assert_eq!(
+            take_log(&log),
+            vec![
+                "first: call methods double",
+                "second: call methods double",
+                "second: reply methods Ok(I64(8))",
+                "first: reply methods Ok(I64(8))",
+            ]
+        );
+
+        // interceptors can rewrite the result
+        let res = dart.invoke_method("methods", "fail", Value::Null);
+        assert!(matches!(res, Some(Err(error)) if error.message.as_deref() == Some("rewritten")));
+        take_log(&log);
+
+        // short-circuited call is only seen by first interceptor
+        let res = dart.invoke_method("methods", "intercepted", Value::Null);
+        assert_eq!(res, Some(Ok(Value::String("first".into()))));
+        assert_eq!(
+            take_log(&log),
+            vec![
+                "first: call methods intercepted",
+                "first: reply methods Ok(String(\"first\"))",
+            ]
+        );
+
+        // reply dropped by handler
+        let res = dart.invoke_method("methods", "unknown", Value::Null);
+        assert_eq!(res, None);
+        assert_eq!(
+            take_log(&log),
+            vec![
+                "first: call methods unknown",
+                "second: call methods unknown",
+                "second: dropped methods Some(\"unknown\")",
+                "first: dropped methods Some(\"unknown\")",
+            ]
+        );
+
+        dart.shut_down().unwrap();
+    }
+
+    #[test]
+    fn test_message() {
+        let log = Log::default();
+        let context = new_context(&log);
+        let dart = FakeDart::new(&context).unwrap();
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let received_clone = received.clone();
+        context
+            .message_manager
+            .borrow_mut()
+            .register_message_handler("messages", move |message, reply, _| {
+                received_clone.borrow_mut().push(message.clone());
+                if message != Value::Null {
+                    reply.send(message);
+                }
+            });
+        let send = |message: Value| {
+            let reply =
+                dart.send_message("messages", &StandardMethodCodec.encode_message(&message));
+            reply.map(|reply| StandardMethodCodec.decode_message(&reply).unwrap())
+        };
+        take_log(&log);
+
+        assert_eq!(send(Value::I64(1)), Some(Value::I64(1)));
+        assert_eq!(
+            take_log(&log),
+            vec![
+                "first: message messages I64(1)",
+                "second: message messages I64(1)",
+                "second: message reply messages I64(1)",
+                "first: message reply messages I64(1)",
+            ]
+        );
+
+        assert_eq!(
+            send(Value::String("intercepted".into())),
+            Some(Value::String("first".into()))
+        );
+        assert_eq!(received.borrow().len(), 1);
+        take_log(&log);
+
+        send(Value::Null);
+        assert_eq!(
+            take_log(&log),
+            vec![
+                "first: message messages Null",
+                "second: message messages Null",
+                "second: dropped messages None",
+                "first: dropped messages None",
+            ]
+        );
+
+        dart.shut_down().unwrap();
+    }
+}
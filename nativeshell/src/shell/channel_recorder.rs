@@ -0,0 +1,407 @@
+use std::{
+    cell::{Cell, RefCell},
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::Path,
+    rc::Rc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use crate::codec::{value_to_json, MethodCallReply, MethodCallResult, StandardMethodCodec, Value};
+
+use super::{BinaryMessengerReply, EngineHandle, MethodCallHandler};
+
+// When set, all platform channel traffic is recorded into file at given path.
+pub const RECORD_CHANNELS_ENV: &str = "NATIVESHELL_RECORD_CHANNELS";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RecordedDirection {
+    // Message from Dart to Rust handler
+    Inbound,
+    // Rust handler reply to inbound message
+    InboundReply,
+    // Message from Rust to Dart
+    Outbound,
+    // Dart reply to outbound message
+    OutboundReply,
+}
+
+// Single recorded platform channel message; Stored as one JSON object per line.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordedMessage {
+    pub id: u64,
+    // For replies, id of the message being replied to
+    pub reply_to: Option<u64>,
+    pub direction: RecordedDirection,
+    pub engine: i64,
+    pub channel: String,
+    // Microseconds since unix epoch
+    pub timestamp: u64,
+    // Raw message, hex encoded
+    #[serde(with = "hex")]
+    pub data: Vec<u8>,
+    // Message decoded with StandardMethodCodec, if possible; Informative only,
+    // replay uses raw data.
+    pub decoded: Option<serde_json::Value>,
+}
+
+pub struct ChannelRecorder {
+    writer: RefCell<Box<dyn Write>>,
+    next_id: Cell<u64>,
+}
+
+impl ChannelRecorder {
+    pub fn new(writer: Box<dyn Write>) -> Self {
+        Self {
+            writer: RefCell::new(writer),
+            next_id: Cell::new(1),
+        }
+    }
+
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = File::create(path)?;
+        Ok(Self::new(Box::new(BufWriter::new(file))))
+    }
+
+    // Creates recorder if enabled through NATIVESHELL_RECORD_CHANNELS.
+    pub fn from_env() -> Option<Self> {
+        let path = std::env::var_os(RECORD_CHANNELS_ENV)?;
+        match Self::create(&path) {
+            Ok(recorder) => Some(recorder),
+            Err(err) => {
+                error!("Failed to create channel recording {path:?}: {err}");
+                None
+            }
+        }
+    }
+
+    // Records the message and returns its id.
+    pub fn record(
+        &self,
+        direction: RecordedDirection,
+        reply_to: Option<u64>,
+        engine: EngineHandle,
+        channel: &str,
+        data: &[u8],
+    ) -> u64 {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        let message = RecordedMessage {
+            id,
+            reply_to,
+            direction,
+            engine: engine.0,
+            channel: channel.into(),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_micros() as u64)
+                .unwrap_or(0),
+            data: data.into(),
+            decoded: decode(direction, data),
+        };
+        let mut writer = self.writer.borrow_mut();
+        // Flush every message so that the recording survives a crash
+        let res = serde_json::to_writer(&mut *writer, &message)
+            .map_err(io::Error::from)
+            .and_then(|_| writer.write_all(b"\n"))
+            .and_then(|_| writer.flush());
+        if let Err(err) = res {
+            error!("Failed to record channel message: {err}");
+        }
+        id
+    }
+
+    // Wraps inbound message handler reply so that the reply gets recorded.
+    pub(super) fn record_inbound(
+        recorder: &Rc<Self>,
+        engine: EngineHandle,
+        channel: &str,
+        data: &[u8],
+        reply: BinaryMessengerReply,
+    ) -> BinaryMessengerReply {
+        let id = recorder.record(RecordedDirection::Inbound, None, engine, channel, data);
+        let recorder = recorder.clone();
+        let channel = channel.to_owned();
+        BinaryMessengerReply::new(move |data| {
+            recorder.record(
+                RecordedDirection::InboundReply,
+                Some(id),
+                engine,
+                &channel,
+                data,
+            );
+            reply.send(data);
+        })
+    }
+}
+
+// Best effort decoding for recording readability. Empty messages are left out.
+fn decode(direction: RecordedDirection, data: &[u8]) -> Option<serde_json::Value> {
+    if data.is_empty() {
+        return None;
+    }
+    let codec = StandardMethodCodec;
+    match direction {
+        RecordedDirection::Inbound | RecordedDirection::Outbound => {
+            if let Ok(call) = codec.try_decode_method_call(data) {
+                let mut res = serde_json::Map::new();
+                res.insert("method".into(), call.method.into());
+                res.insert("args".into(), value_to_json(&call.args));
+                Some(serde_json::Value::Object(res))
+            } else {
+                codec
+                    .try_decode_message(data)
+                    .ok()
+                    .map(|v| value_to_json(&v))
+            }
+        }
+        RecordedDirection::InboundReply | RecordedDirection::OutboundReply => {
+            match codec.try_decode_envelope(data) {
+                Ok(Ok(value)) => Some(value_to_json(&value)),
+                Ok(Err(err)) => Some(value_to_json(&Value::List(vec![
+                    err.code.into(),
+                    err.message.map(Value::String).unwrap_or(Value::Null),
+                    err.details,
+                ]))),
+                Err(_) => codec
+                    .try_decode_message(data)
+                    .ok()
+                    .map(|v| value_to_json(&v)),
+            }
+        }
+    }
+}
+
+// Result of replaying single recorded method call.
+pub struct ReplayedCall {
+    pub message: RecordedMessage,
+    // Reply recorded for this call, if any
+    pub recorded_reply: Option<Vec<u8>>,
+    // Reply sent by the handler during replay; None if handler didn't reply
+    // synchronously.
+    pub reply: Option<Vec<u8>>,
+}
+
+impl ReplayedCall {
+    pub fn decode_recorded_reply(&self) -> Option<MethodCallResult<Value>> {
+        decode_envelope(self.recorded_reply.as_deref())
+    }
+
+    pub fn decode_reply(&self) -> Option<MethodCallResult<Value>> {
+        decode_envelope(self.reply.as_deref())
+    }
+}
+
+fn decode_envelope(data: Option<&[u8]>) -> Option<MethodCallResult<Value>> {
+    match data {
+        Some(data) if !data.is_empty() => StandardMethodCodec.try_decode_envelope(data).ok(),
+        _ => None,
+    }
+}
+
+// Feeds recorded inbound traffic to Rust handlers without Flutter engine.
+pub struct ChannelReplay {
+    messages: Vec<RecordedMessage>,
+}
+
+impl ChannelReplay {
+    pub fn new(messages: Vec<RecordedMessage>) -> Self {
+        Self { messages }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    pub fn from_reader<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut messages = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            messages.push(serde_json::from_str(&line)?);
+        }
+        Ok(Self { messages })
+    }
+
+    pub fn messages(&self) -> &[RecordedMessage] {
+        &self.messages
+    }
+
+    // Replays inbound messages for given channel in recorded order.
+    pub fn replay_binary<F>(&self, channel: &str, mut callback: F) -> Vec<ReplayedCall>
+    where
+        F: FnMut(&[u8], BinaryMessengerReply, EngineHandle),
+    {
+        let inbound = self
+            .messages
+            .iter()
+            .filter(|m| m.direction == RecordedDirection::Inbound && m.channel == channel);
+        let mut res = Vec::new();
+        for message in inbound {
+            let reply_data = Rc::new(RefCell::new(None));
+            let reply_data_clone = reply_data.clone();
+            let reply = BinaryMessengerReply::new(move |data| {
+                reply_data_clone.borrow_mut().replace(data.to_vec());
+            });
+            callback(&message.data, reply, EngineHandle(message.engine));
+            let reply = reply_data.borrow_mut().take();
+            res.push(ReplayedCall {
+                message: message.clone(),
+                recorded_reply: self.recorded_reply(message.id),
+                reply,
+            });
+        }
+        res
+    }
+
+    // Replays method calls for given channel on the handler.
+    pub fn replay_method_calls<H: MethodCallHandler>(
+        &self,
+        channel: &str,
+        handler: &mut H,
+    ) -> Vec<ReplayedCall> {
+        self.replay_binary(channel, |data, reply, engine| {
+            match StandardMethodCodec.try_decode_method_call(data) {
+                Ok(call) => {
+                    let reply = MethodCallReply::new(reply, &StandardMethodCodec);
+                    handler.on_method_call(call, reply, engine);
+                }
+                Err(err) => error!("Invalid recorded method call: {err}"),
+            }
+        })
+    }
+
+    fn recorded_reply(&self, id: u64) -> Option<Vec<u8>> {
+        self.messages
+            .iter()
+            .find(|m| m.direction == RecordedDirection::InboundReply && m.reply_to == Some(id))
+            .map(|m| m.data.clone())
+    }
+}
+
+mod hex {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        let mut res = String::with_capacity(data.len() * 2);
+        for b in data {
+            res.push_str(&format!("{b:02x}"));
+        }
+        serializer.serialize_str(&res)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        if s.len() % 2 != 0 {
+            return Err(D::Error::custom("odd length hex string"));
+        }
+        s.as_bytes()
+            .chunks(2)
+            .map(|pair| {
+                let digit = |b: u8| (b as char).to_digit(16);
+                match (digit(pair[0]), digit(pair[1])) {
+                    (Some(high), Some(low)) => Ok((high * 16 + low) as u8),
+                    _ => Err(D::Error::custom("invalid hex string")),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, io::Write, rc::Rc};
+
+    use super::{ChannelRecorder, ChannelReplay, RecordedDirection};
+    use crate::{
+        codec::{MethodCall, MethodCallReply, MethodCodec, StandardMethodCodec, Value},
+        shell::{EngineHandle, MethodCallHandler},
+    };
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    struct Echo;
+
+    impl MethodCallHandler for Echo {
+        fn on_method_call(
+            &mut self,
+            call: MethodCall<Value>,
+            reply: MethodCallReply<Value>,
+            _engine: EngineHandle,
+        ) {
+            reply.send_ok(call.args);
+        }
+    }
+
+    #[test]
+    fn test_record_replay() {
+        let buffer = SharedBuffer::default();
+        let recorder = ChannelRecorder::new(Box::new(buffer.clone()));
+        let call = StandardMethodCodec.encode_method_call(&MethodCall {
+            method: "echo".into(),
+            args: Value::String("hello".into()),
+        });
+        let engine = EngineHandle(3);
+        let id = recorder.record(RecordedDirection::Inbound, None, engine, "ch", &call);
+        let reply = StandardMethodCodec.encode_success_envelope(&"hello".into());
+        recorder.record(
+            RecordedDirection::InboundReply,
+            Some(id),
+            engine,
+            "ch",
+            &reply,
+        );
+        recorder.record(RecordedDirection::Outbound, None, engine, "ch", &call);
+        recorder.record(RecordedDirection::Inbound, None, engine, "other", &call);
+
+        let data = buffer.0.borrow().clone();
+        let replay = ChannelReplay::from_reader(data.as_slice()).unwrap();
+        assert_eq!(replay.messages().len(), 4);
+        assert_eq!(replay.messages()[0].data, call);
+        assert_eq!(
+            replay.messages()[0].decoded,
+            Some(serde_json::json!({"method": "echo", "args": "hello"}))
+        );
+
+        let replayed = replay.replay_method_calls("ch", &mut Echo);
+        assert_eq!(replayed.len(), 1);
+        assert_eq!(replayed[0].message.engine, 3);
+        assert_eq!(replayed[0].reply, replayed[0].recorded_reply);
+        assert_eq!(
+            replayed[0].decode_reply().unwrap().unwrap(),
+            Value::String("hello".into())
+        );
+    }
+
+    #[test]
+    fn test_malformed_data() {
+        let line = |data: &str| {
+            format!(
+                r#"{{"id":1,"direction":"inbound","engine":1,"channel":"ch","timestamp":0,"data":"{data}"}}"#
+            )
+        };
+        assert!(ChannelReplay::from_reader(line("0a0b").as_bytes()).is_ok());
+        for data in &["aé0", "0a0", "zz", "+1"] {
+            assert!(ChannelReplay::from_reader(line(data).as_bytes()).is_err());
+        }
+    }
+}
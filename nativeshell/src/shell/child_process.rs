@@ -0,0 +1,363 @@
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    io::{ErrorKind, Read},
+    os::unix::io::{AsRawFd, RawFd},
+    pin::Pin,
+    process::{Child, Command, ExitStatus, Stdio},
+    rc::{Rc, Weak},
+    task::{Poll, Waker},
+    time::Duration,
+};
+
+use futures::Stream;
+use log::error;
+
+use crate::Result;
+
+use super::{
+    fd_watch::watch_fd_on, platform::run_loop::PlatformRunLoop, run_loop::schedule_on, FdCondition,
+    Handle,
+};
+
+// How often to check for exit status when child closed its output but did not
+// exit yet.
+const EXIT_CHECK_INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProcessEvent {
+    // Line of standard output (without line terminator)
+    Stdout(String),
+    // Line of standard error (without line terminator)
+    Stderr(String),
+    // Always the last event
+    Exit(ExitStatus),
+}
+
+#[derive(Clone, Copy)]
+enum Output {
+    Stdout,
+    Stderr,
+}
+
+struct OutputPipe {
+    // declared first so that the watch is removed before descriptor is closed
+    _watch: Handle,
+    reader: Box<dyn Read>,
+    buffer: Vec<u8>,
+}
+
+struct ProcessState {
+    child: Child,
+    run_loop: Rc<PlatformRunLoop>,
+    events: VecDeque<ProcessEvent>,
+    stdout: Option<OutputPipe>,
+    stderr: Option<OutputPipe>,
+    exit_check: Option<Handle>,
+    exited: bool,
+    waker: Option<Waker>,
+}
+
+// Child process with standard output and error read on run loop thread; Lines
+// and exit status are delivered through the Stream implementation. Created by
+// RunLoop::spawn_process.
+//
+// Dropping ChildProcess stops reading the output but does not kill the process.
+pub struct ChildProcess {
+    state: Rc<RefCell<ProcessState>>,
+    finished: bool,
+}
+
+impl ChildProcess {
+    pub(super) fn spawn(
+        platform_run_loop: &Rc<PlatformRunLoop>,
+        mut command: Command,
+    ) -> Result<Self> {
+        let mut child = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let stdout = child.stdout.take().unwrap();
+        let stderr = child.stderr.take().unwrap();
+        let stdout_fd = stdout.as_raw_fd();
+        let stderr_fd = stderr.as_raw_fd();
+        if let Err(err) = set_non_blocking(stdout_fd).and_then(|_| set_non_blocking(stderr_fd)) {
+            Self::reap(&mut child);
+            return Err(err);
+        }
+
+        let state = Rc::new(RefCell::new(ProcessState {
+            child,
+            run_loop: platform_run_loop.clone(),
+            events: VecDeque::new(),
+            stdout: None,
+            stderr: None,
+            exit_check: None,
+            exited: false,
+            waker: None,
+        }));
+
+        let watches = Self::watch_output(&state, stdout_fd, Output::Stdout).and_then(|stdout| {
+            Ok((
+                stdout,
+                Self::watch_output(&state, stderr_fd, Output::Stderr)?,
+            ))
+        });
+        let (stdout_watch, stderr_watch) = match watches {
+            Ok(watches) => watches,
+            Err(err) => {
+                Self::reap(&mut state.borrow_mut().child);
+                return Err(err);
+            }
+        };
+        {
+            let mut state = state.borrow_mut();
+            state.stdout.replace(OutputPipe {
+                reader: Box::new(stdout),
+                buffer: Vec::new(),
+                _watch: stdout_watch,
+            });
+            state.stderr.replace(OutputPipe {
+                reader: Box::new(stderr),
+                buffer: Vec::new(),
+                _watch: stderr_watch,
+            });
+        }
+
+        Ok(Self {
+            state,
+            finished: false,
+        })
+    }
+
+    // Child that can not be watched would never be waited for; Make sure it
+    // does not outlive the failed spawn as a zombie.
+    fn reap(child: &mut Child) {
+        child.kill().ok();
+        child.wait().ok();
+    }
+
+    pub fn id(&self) -> u32 {
+        self.state.borrow().child.id()
+    }
+
+    pub fn kill(&self) -> Result<()> {
+        self.state.borrow_mut().child.kill().map_err(Into::into)
+    }
+
+    fn watch_output(
+        state: &Rc<RefCell<ProcessState>>,
+        fd: RawFd,
+        output: Output,
+    ) -> Result<Handle> {
+        let weak_state = Rc::downgrade(state);
+        let run_loop = state.borrow().run_loop.clone();
+        watch_fd_on(&run_loop, fd, FdCondition::readable(), move |_| {
+            Self::on_output(&weak_state, output)
+        })
+    }
+
+    // Returns whether the output should still be watched.
+    fn on_output(state: &Weak<RefCell<ProcessState>>, output: Output) -> bool {
+        let state = match state.upgrade() {
+            Some(state) => state,
+            None => return false,
+        };
+        let closed = {
+            let mut state = state.borrow_mut();
+            let state = &mut *state;
+            let pipe = match output {
+                Output::Stdout => &mut state.stdout,
+                Output::Stderr => &mut state.stderr,
+            };
+            let closed = match pipe {
+                Some(pipe) => pipe.read(output, &mut state.events),
+                None => true,
+            };
+            if closed {
+                // this drops the watch handle, but the watch is being removed
+                // anyway as we return false
+                pipe.take();
+            }
+            closed
+        };
+        if closed {
+            Self::check_exit(&state);
+        }
+        Self::wake(&state);
+        !closed
+    }
+
+    fn check_exit(state: &Rc<RefCell<ProcessState>>) {
+        let mut state_ref = state.borrow_mut();
+        if state_ref.stdout.is_some() || state_ref.stderr.is_some() || state_ref.exited {
+            return;
+        }
+        if let Some(mut handle) = state_ref.exit_check.take() {
+            // Timer is being executed, nothing to cancel
+            handle.detach();
+        }
+        match state_ref.child.try_wait() {
+            Ok(Some(status)) => {
+                state_ref.exited = true;
+                state_ref.events.push_back(ProcessEvent::Exit(status));
+            }
+            Ok(None) => {
+                let weak_state = Rc::downgrade(state);
+                let handle = schedule_on(&state_ref.run_loop, EXIT_CHECK_INTERVAL, move || {
+                    if let Some(state) = weak_state.upgrade() {
+                        Self::check_exit(&state);
+                        Self::wake(&state);
+                    }
+                });
+                state_ref.exit_check.replace(handle);
+            }
+            Err(err) => {
+                error!("Failed to get child process exit status: {err}");
+            }
+        }
+    }
+
+    fn wake(state: &RefCell<ProcessState>) {
+        let waker = state.borrow_mut().waker.take();
+        // Waker might poll synchronously, must not be called while borrowed
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl OutputPipe {
+    // Reads available data and queues complete lines; Returns true if the pipe
+    // was closed, in which case remaining data is queued as last line.
+    fn read(&mut self, output: Output, events: &mut VecDeque<ProcessEvent>) -> bool {
+        let mut buf = [0u8; 4096];
+        let closed = loop {
+            match self.reader.read(&mut buf) {
+                Ok(0) => break true,
+                Ok(len) => self.buffer.extend_from_slice(&buf[..len]),
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break false,
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => {
+                    error!("Failed to read child process output: {err}");
+                    break true;
+                }
+            }
+        };
+        while let Some(pos) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+            events.push_back(Self::event(output, &line[..line.len() - 1]));
+        }
+        if closed && !self.buffer.is_empty() {
+            let line = std::mem::take(&mut self.buffer);
+            events.push_back(Self::event(output, &line));
+        }
+        closed
+    }
+
+    fn event(output: Output, line: &[u8]) -> ProcessEvent {
+        let line = String::from_utf8_lossy(line);
+        let line = line.strip_suffix('\r').unwrap_or(&line).to_owned();
+        match output {
+            Output::Stdout => ProcessEvent::Stdout(line),
+            Output::Stderr => ProcessEvent::Stderr(line),
+        }
+    }
+}
+
+impl Stream for ChildProcess {
+    type Item = ProcessEvent;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        if self.finished {
+            return Poll::Ready(None);
+        }
+        let event = {
+            let mut state = self.state.borrow_mut();
+            let event = state.events.pop_front();
+            if event.is_none() {
+                state.waker.replace(cx.waker().clone());
+            }
+            event
+        };
+        match event {
+            Some(event) => {
+                if let ProcessEvent::Exit(_) = event {
+                    self.finished = true;
+                }
+                Poll::Ready(Some(event))
+            }
+            None => Poll::Pending,
+        }
+    }
+}
+
+fn set_non_blocking(fd: RawFd) -> Result<()> {
+    unsafe {
+        let flags = libc::fcntl(fd, libc::F_GETFL);
+        if flags < 0 || libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(all(test, feature = "null-backend"))]
+mod tests {
+    use std::{cell::RefCell, process::Command, rc::Rc, time::Duration};
+
+    use futures::StreamExt;
+
+    use super::ProcessEvent;
+    use crate::shell::{Context, ContextOptions, FakeDart};
+
+    #[test]
+    fn test_child_process() {
+        let context = Context::new(ContextOptions::default()).unwrap();
+        let dart = FakeDart::new(&context).unwrap();
+        let events = Rc::new(RefCell::new(Vec::new()));
+
+        let mut command = Command::new("sh");
+        command
+            .arg("-c")
+            .arg("echo out1; echo err1 >&2; printf out2; exit 3");
+        let mut process = context.run_loop.borrow().spawn_process(command).unwrap();
+        let events_clone = events.clone();
+        context.run_loop.borrow().spawn(async move {
+            while let Some(event) = process.next().await {
+                events_clone.borrow_mut().push(event);
+            }
+        });
+
+        for _ in 0..100 {
+            if let Some(ProcessEvent::Exit(_)) = events.borrow().last() {
+                break;
+            }
+            dart.pump_for(Duration::from_millis(20));
+        }
+        let events = events.borrow();
+        let stdout: Vec<_> = events
+            .iter()
+            .filter(|e| matches!(e, ProcessEvent::Stdout(_)))
+            .cloned()
+            .collect();
+        assert_eq!(
+            stdout,
+            vec![
+                ProcessEvent::Stdout("out1".into()),
+                ProcessEvent::Stdout("out2".into())
+            ]
+        );
+        assert!(events.contains(&ProcessEvent::Stderr("err1".into())));
+        match events.last() {
+            Some(ProcessEvent::Exit(status)) => assert_eq!(status.code(), Some(3)),
+            _ => panic!("Expected exit event"),
+        }
+
+        dart.shut_down().unwrap();
+    }
+}
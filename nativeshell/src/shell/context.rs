@@ -13,16 +13,30 @@ use super::{
         engine::PlatformPlugin, init::init_platform,
     },
     screen_manager::ScreenManager,
+    shell_manager::ShellManager,
+    single_instance::SingleInstanceManager,
     status_item_manager::StatusItemManager,
-    EngineManager, HotKeyManager, JoinHandle, KeyboardMapManager, MenuManager, MessageManager,
-    RegisteredMethodCallHandler, RunLoop, WindowManager, WindowMethodChannel,
+    ApplicationManager, ChannelInterceptor, EngineManager, HotKeyManager, JoinHandle,
+    KeyboardMapManager, LastWindowClosedPolicy, MenuManager, MessageManager,
+    RegisteredAsyncMethodCallHandler, RegisteredMethodCallHandler, RunLoop, WindowManager,
+    WindowMethodChannel,
 };
 
 pub struct ContextOptions {
     pub app_namespace: String,
     pub flutter_plugins: Vec<PlatformPlugin>,
-    pub on_last_engine_removed: Box<dyn Fn(&ContextRef)>,
+    pub last_window_closed: LastWindowClosedPolicy,
     pub custom_drag_data_adapters: Vec<Box<dyn DragDataAdapter>>,
+    pub channel_interceptors: Vec<Box<dyn ChannelInterceptor>>,
+    // Forward SIGTERM, SIGINT and SIGHUP to ApplicationManager, which lets Rust
+    // and Dart code finish (or cancel) termination gracefully. Only supported
+    // on Linux; Context creation fails with NotAvailable elsewhere.
+    pub handle_termination_signals: bool,
+    // When another instance with same app_namespace is already running, forward
+    // arguments and working directory of this process to it and exit during
+    // context creation. Only supported on Linux; Context creation fails with
+    // NotAvailable elsewhere.
+    pub single_instance: bool,
 }
 
 impl Default for ContextOptions {
@@ -30,8 +44,11 @@ impl Default for ContextOptions {
         Self {
             app_namespace: Default::default(),
             flutter_plugins: Vec::new(),
-            on_last_engine_removed: Box::new(|context| context.run_loop.borrow().stop()),
+            last_window_closed: LastWindowClosedPolicy::Terminate,
             custom_drag_data_adapters: Vec::new(),
+            channel_interceptors: Vec::new(),
+            handle_termination_signals: false,
+            single_instance: false,
         }
     }
 }
@@ -44,11 +61,15 @@ pub struct ContextImpl {
     pub window_method_channel: LateRefCell<WindowMethodChannel>,
     pub window_manager: LateRefCell<WindowManager>,
     pub application_delegate_manager: LateRefCell<ApplicationDelegateManager>,
+    pub application_manager: LateRefCell<RegisteredAsyncMethodCallHandler<ApplicationManager>>,
     pub(crate) menu_manager: LateRefCell<RegisteredMethodCallHandler<MenuManager>>,
     pub(crate) keyboard_map_manager: LateRefCell<RegisteredMethodCallHandler<KeyboardMapManager>>,
     pub(crate) hot_key_manager: LateRefCell<RegisteredMethodCallHandler<HotKeyManager>>,
     pub(crate) screen_manager: LateRefCell<RegisteredMethodCallHandler<ScreenManager>>,
     pub(crate) status_item_manager: LateRefCell<RegisteredMethodCallHandler<StatusItemManager>>,
+    pub(crate) shell_manager: LateRefCell<RegisteredMethodCallHandler<ShellManager>>,
+    pub(crate) single_instance_manager:
+        LateRefCell<RegisteredMethodCallHandler<SingleInstanceManager>>,
 }
 
 impl ContextImpl {
@@ -61,11 +82,14 @@ impl ContextImpl {
             window_method_channel: LateRefCell::new(),
             window_manager: LateRefCell::new(),
             application_delegate_manager: LateRefCell::new(),
+            application_manager: LateRefCell::new(),
             menu_manager: LateRefCell::new(),
             keyboard_map_manager: LateRefCell::new(),
             hot_key_manager: LateRefCell::new(),
             screen_manager: LateRefCell::new(),
             status_item_manager: LateRefCell::new(),
+            shell_manager: LateRefCell::new(),
+            single_instance_manager: LateRefCell::new(),
         });
         let res = ContextRef { context: res };
         res.initialize(&res)?;
@@ -73,6 +97,15 @@ impl ContextImpl {
     }
 
     fn initialize(&self, context: &ContextRef) -> Result<()> {
+        #[cfg(not(fd_watch))]
+        if self.options.single_instance || self.options.handle_termination_signals {
+            return Err(super::platform::error::PlatformError::NotAvailable.into());
+        }
+
+        // Before initializing anything else as this might end the process
+        #[cfg(fd_watch)]
+        let instance_listener = super::single_instance::acquire_instance_or_exit(&self.options)?;
+
         init_platform().map_err(Error::from)?;
 
         self.run_loop.set(RunLoop::new(context));
@@ -90,6 +123,25 @@ impl ContextImpl {
         self.screen_manager.set(ScreenManager::new(context.weak()));
         self.status_item_manager
             .set(StatusItemManager::new(context.weak()));
+        self.shell_manager.set(ShellManager::new(context.weak()));
+        self.application_manager
+            .set(ApplicationManager::new(context));
+        #[cfg(fd_watch)]
+        if self.options.handle_termination_signals {
+            self.application_manager
+                .borrow()
+                .borrow()
+                .install_signal_handler(context)?;
+        }
+        self.single_instance_manager
+            .set(SingleInstanceManager::new(context.weak()));
+        #[cfg(fd_watch)]
+        if let Some(listener) = instance_listener {
+            self.single_instance_manager
+                .borrow()
+                .borrow_mut()
+                .listen(listener)?;
+        }
 
         #[cfg(debug_assertions)]
         {
@@ -1,6 +1,9 @@
+use std::rc::Rc;
+
 use super::{
+    channel_buffering::ChannelBuffering,
     platform::engine::{PlatformEngine, PlatformEngineType, PlatformPlugin},
-    BinaryMessenger, EngineHandle,
+    BinaryMessenger, ChannelRecorder, EngineHandle,
 };
 use crate::Result;
 
@@ -26,6 +29,18 @@ impl FlutterEngine {
         self.binary_messenger.as_ref().unwrap()
     }
 
+    pub(super) fn set_recorder(&mut self, recorder: Rc<ChannelRecorder>, engine: EngineHandle) {
+        if let Some(messenger) = self.binary_messenger.as_mut() {
+            messenger.set_recorder(recorder, engine);
+        }
+    }
+
+    pub(super) fn set_buffering(&mut self, buffering: Rc<ChannelBuffering>, engine: EngineHandle) {
+        if let Some(messenger) = self.binary_messenger.as_mut() {
+            messenger.set_buffering(buffering, engine);
+        }
+    }
+
     pub fn platform_engine(&self) -> PlatformEngineType {
         #[allow(clippy::clone_on_copy)]
         self.platform_engine.handle.clone()
@@ -1,9 +1,13 @@
 use std::{
     cell::{Ref, RefCell},
     collections::HashMap,
+    rc::Rc,
 };
 
-use super::{Context, ContextRef, FlutterEngine, Handle};
+use super::{
+    api_model::ApiNegotiationResponse, channel_buffering::ChannelBuffering, ChannelRecorder,
+    Context, ContextRef, FlutterEngine, Handle,
+};
 use crate::{Error, Result};
 
 #[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
@@ -16,6 +20,10 @@ pub struct EngineManager {
     next_notification: i64,
     create_notifications: HashMap<i64, Box<dyn Fn(EngineHandle, &FlutterEngine)>>,
     destroy_notifications: HashMap<i64, Box<dyn Fn(EngineHandle)>>,
+    recorder: Option<Rc<ChannelRecorder>>,
+    buffering: Rc<ChannelBuffering>,
+    // API version and features negotiated by Dart side of each engine
+    negotiated_api: HashMap<EngineHandle, ApiNegotiationResponse>,
 }
 
 impl EngineManager {
@@ -27,13 +35,20 @@ impl EngineManager {
             next_notification: 1,
             create_notifications: HashMap::new(),
             destroy_notifications: HashMap::new(),
+            recorder: ChannelRecorder::from_env().map(Rc::new),
+            buffering: ChannelBuffering::new(context.weak()),
+            negotiated_api: HashMap::new(),
         }
     }
 
     pub fn create_engine(&mut self, parent_engine: Option<EngineHandle>) -> Result<EngineHandle> {
         if let Some(context) = self.context.get() {
-            let engine = FlutterEngine::new(&context.options.flutter_plugins, parent_engine);
+            let mut engine = FlutterEngine::new(&context.options.flutter_plugins, parent_engine);
             let handle = self.next_handle;
+            if let Some(recorder) = &self.recorder {
+                engine.set_recorder(recorder.clone(), handle);
+            }
+            engine.set_buffering(self.buffering.clone(), handle);
 
             for n in self.create_notifications.values() {
                 n(handle, &engine);
@@ -123,6 +138,7 @@ impl EngineManager {
             n(handle);
         }
 
+        self.negotiated_api.remove(&handle);
         let entry = self.engines.remove(&handle);
         if let Some(entry) = entry {
             let mut engine = entry.borrow_mut();
@@ -130,12 +146,39 @@ impl EngineManager {
         }
         if self.engines.is_empty() {
             if let Some(context) = self.context.get() {
-                (context.options.on_last_engine_removed)(&context);
+                context
+                    .application_manager
+                    .borrow()
+                    .borrow()
+                    .last_engine_removed(&context);
             }
         }
         Ok(())
     }
 
+    // Returns API negotiated by Dart side of the engine; None if Dart side did
+    // not negotiate (yet).
+    pub fn get_negotiated_api(&self, handle: EngineHandle) -> Option<&ApiNegotiationResponse> {
+        self.negotiated_api.get(&handle)
+    }
+
+    pub fn is_api_feature_enabled(&self, handle: EngineHandle, feature: &str) -> bool {
+        self.negotiated_api
+            .get(&handle)
+            .map(|api| api.features.iter().any(|f| f == feature))
+            .unwrap_or(false)
+    }
+
+    pub(super) fn set_negotiated_api(&mut self, handle: EngineHandle, api: ApiNegotiationResponse) {
+        if self.engines.contains_key(&handle) {
+            self.negotiated_api.insert(handle, api);
+        }
+    }
+
+    pub(super) fn channel_buffering(&self) -> &Rc<ChannelBuffering> {
+        &self.buffering
+    }
+
     pub fn get_all_engines(&self) -> Vec<EngineHandle> {
         self.engines.keys().cloned().collect()
     }
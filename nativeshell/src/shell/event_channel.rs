@@ -4,7 +4,10 @@ use std::{
     rc::{Rc, Weak},
 };
 
-use crate::{codec::Value, Error, Result};
+use crate::{
+    codec::{EventSender, MethodCallError, Value},
+    Error, Result,
+};
 
 use super::{Context, EngineHandle, MethodCallHandler, RegisteredMethodCallHandler};
 
@@ -16,17 +19,50 @@ pub struct EventSink {
 }
 
 impl EventSink {
+    pub(super) fn new(
+        context: Context,
+        id: i64,
+        channel_name: &str,
+        engine_handle: EngineHandle,
+    ) -> Self {
+        Self {
+            context,
+            id,
+            channel_name: channel_name.into(),
+            engine_handle,
+        }
+    }
+
     pub fn id(&self) -> i64 {
         self.id
     }
 
     pub fn send_message(&self, message: &Value) -> Result<()> {
+        self.with_sender(|sender| sender.send_event(message))
+    }
+
+    // Reported as error on the Dart stream; The stream stays open.
+    pub fn send_error(&self, error: &MethodCallError<Value>) -> Result<()> {
+        self.with_sender(|sender| {
+            sender.send_error(&error.code, error.message.as_deref(), &error.details)
+        })
+    }
+
+    // Closes the Dart stream.
+    pub fn end_of_stream(&self) -> Result<()> {
+        self.with_sender(|sender| sender.end_of_stream())
+    }
+
+    fn with_sender<F>(&self, f: F) -> Result<()>
+    where
+        F: FnOnce(&EventSender<Value>) -> Result<()>,
+    {
         if let Some(context) = self.context.get() {
-            context
+            let sender = context
                 .message_manager
                 .borrow()
-                .get_event_sender(self.engine_handle, &self.channel_name)
-                .send_event(message)
+                .get_event_sender(self.engine_handle, &self.channel_name);
+            f(&sender)
         } else {
             Err(Error::InvalidContext)
         }
@@ -108,14 +144,16 @@ impl<T: EventChannelHandler> MethodCallHandler for EventChannelInternal<T> {
     ) {
         match call.method.as_str() {
             "listen" => {
+                // Dart only has one subscription per engine; Listening again (i.e.
+                // after hot restart) replaces previous sink
+                if let Some(sink_id) = self.engine_to_sink.remove(&engine) {
+                    self.handler.borrow_mut().unregister_event_sink(sink_id);
+                }
                 let sink_id = self.next_sink_id;
                 self.next_sink_id += 1;
-                let sink = EventSink {
-                    context: self.context.clone(),
-                    id: sink_id,
-                    channel_name: self.channel_name.clone(),
-                    engine_handle: engine,
-                };
+                let sink =
+                    EventSink::new(self.context.clone(), sink_id, &self.channel_name, engine);
+                self.engine_to_sink.insert(engine, sink_id);
                 self.handler
                     .borrow_mut()
                     .register_event_sink(sink, call.args);
@@ -0,0 +1,285 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    codec::{MethodCall, MethodCallResult, MethodCodec, StandardMethodCodec, Value},
+    Error, Result,
+};
+
+use super::{
+    platform::binary_messenger::MessengerState, BinaryMessengerReply, Context, ContextRef,
+    EngineHandle,
+};
+
+type DartHandler = Rc<dyn Fn(&[u8]) -> Vec<u8>>;
+
+// Stands in for Dart side of an engine when running with the null backend.
+// Allows calling Rust channel handlers and answering calls made from Rust
+// without Flutter engine, i.e. in unit tests.
+//
+// Messages sent from Rust are delivered only while pumping (pump, pump_for,
+// send_message and invoke_method), never from within the send call itself.
+pub struct FakeDart {
+    context: Context,
+    engine: EngineHandle,
+    handlers: RefCell<HashMap<String, DartHandler>>,
+    received: RefCell<Vec<(String, Vec<u8>)>>,
+}
+
+impl FakeDart {
+    // Creates and launches new engine.
+    pub fn new(context: &ContextRef) -> Result<Self> {
+        let engine = {
+            let mut engine_manager = context.engine_manager.borrow_mut();
+            let engine = engine_manager.create_engine(None)?;
+            engine_manager.launch_engine(engine)?;
+            engine
+        };
+        Ok(Self::for_engine(context, engine))
+    }
+
+    // Attaches to existing engine.
+    pub fn for_engine(context: &ContextRef, engine: EngineHandle) -> Self {
+        Self {
+            context: context.weak(),
+            engine,
+            handlers: RefCell::new(HashMap::new()),
+            received: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn engine(&self) -> EngineHandle {
+        self.engine
+    }
+
+    // Handles messages sent from Rust on given channel. Returning empty reply
+    // means that the channel is not implemented.
+    pub fn set_message_handler<F>(&self, channel: &str, handler: F)
+    where
+        F: Fn(&[u8]) -> Vec<u8> + 'static,
+    {
+        self.handlers
+            .borrow_mut()
+            .insert(channel.into(), Rc::new(handler));
+    }
+
+    // Handles method calls sent from Rust on given channel using StandardMethodCodec.
+    pub fn set_method_handler<F>(&self, channel: &str, handler: F)
+    where
+        F: Fn(MethodCall<Value>) -> MethodCallResult<Value> + 'static,
+    {
+        self.set_message_handler(channel, move |message| {
+            match StandardMethodCodec.try_decode_method_call(message) {
+                Ok(call) => StandardMethodCodec.encode_method_call_result(&handler(call)),
+                Err(_) => Vec::new(),
+            }
+        });
+    }
+
+    pub fn remove_handler(&self, channel: &str) {
+        self.handlers.borrow_mut().remove(channel);
+    }
+
+    // Returns all messages sent from Rust on given channel so far and clears them.
+    pub fn take_messages(&self, channel: &str) -> Vec<Vec<u8>> {
+        let mut received = self.received.borrow_mut();
+        let (res, rest) = received.drain(..).partition(|(c, _)| c == channel);
+        *received = rest;
+        res.into_iter().map(|(_, message)| message).collect()
+    }
+
+    // Like take_messages, but decodes the messages as method calls.
+    pub fn take_method_calls(&self, channel: &str) -> Vec<MethodCall<Value>> {
+        self.take_messages(channel)
+            .iter()
+            .filter_map(|m| StandardMethodCodec.try_decode_method_call(m).ok())
+            .collect()
+    }
+
+    // Sends message to Rust handler registered for the channel and pumps the run
+    // loop until idle. Returns the reply, or None if there is no handler or it
+    // didn't reply yet. Empty reply means the handler dropped the reply.
+    pub fn send_message(&self, channel: &str, message: &[u8]) -> Option<Vec<u8>> {
+        let handler = self.messenger_state()?.channel_handler(channel)?;
+        let reply = Rc::new(RefCell::new(None));
+        let reply_clone = reply.clone();
+        let context = self.context.get()?;
+        {
+            let _current = context.set_as_current();
+            handler(
+                message,
+                BinaryMessengerReply::new(move |data| {
+                    reply_clone.borrow_mut().replace(data.to_vec());
+                }),
+            );
+        }
+        self.pump();
+        let res = reply.borrow_mut().take();
+        res
+    }
+
+    // Invokes method on Rust handler using StandardMethodCodec. Returns None if
+    // there is no reply or the method is not implemented.
+    pub fn invoke_method(
+        &self,
+        channel: &str,
+        method: &str,
+        args: Value,
+    ) -> Option<MethodCallResult<Value>> {
+        let call = StandardMethodCodec.encode_method_call(&MethodCall {
+            method: method.into(),
+            args,
+        });
+        let reply = self.send_message(channel, &call)?;
+        if reply.is_empty() {
+            None
+        } else {
+            StandardMethodCodec.try_decode_envelope(&reply).ok()
+        }
+    }
+
+    // Runs pending run loop callbacks and delivers messages sent from Rust
+    // until there is nothing left to do. Timers that are not due yet are not
+    // waited for.
+    pub fn pump(&self) {
+        let context = match self.context.get() {
+            Some(context) => context,
+            None => return,
+        };
+        let _current = context.set_as_current();
+        let run_loop = context.run_loop.borrow().platform_run_loop.clone();
+        loop {
+            let executed = run_loop.poll();
+            if !self.deliver_messages() && !executed {
+                break;
+            }
+        }
+    }
+
+    // Pumps for given duration, including timers that become due meanwhile.
+    pub fn pump_for(&self, duration: Duration) {
+        let deadline = Instant::now() + duration;
+        loop {
+            self.pump();
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            let mut wait = (deadline - now).min(Duration::from_millis(5));
+            if let Some(next_timer) = self
+                .context
+                .get()
+                .and_then(|c| c.run_loop.borrow().platform_run_loop.next_timer())
+            {
+                wait = wait.min(next_timer.saturating_duration_since(now));
+            }
+            std::thread::sleep(wait);
+        }
+    }
+
+    fn messenger_state(&self) -> Option<Rc<MessengerState>> {
+        let context = self.context.get()?;
+        let engine_manager = context.engine_manager.borrow();
+        let engine = engine_manager.get_engine(self.engine)?;
+        let res = engine.platform_engine.messenger_state.clone();
+        Some(res)
+    }
+
+    fn deliver_messages(&self) -> bool {
+        let state = match self.messenger_state() {
+            Some(state) => state,
+            None => return false,
+        };
+        let mut delivered = false;
+        while let Some(message) = state.take_outgoing_message() {
+            delivered = true;
+            self.received
+                .borrow_mut()
+                .push((message.channel.clone(), message.message.clone()));
+            let handler = self.handlers.borrow().get(&message.channel).cloned();
+            let reply = handler.map(|h| h(&message.message)).unwrap_or_default();
+            if let Some(reply_callback) = message.reply {
+                reply_callback(&reply);
+            }
+        }
+        delivered
+    }
+
+    // Removes the engine.
+    pub fn shut_down(self) -> Result<()> {
+        let context = self.context.get().ok_or(Error::InvalidContext)?;
+        let res = context
+            .engine_manager
+            .borrow_mut()
+            .remove_engine(self.engine);
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::FakeDart;
+    use crate::{
+        codec::{MethodCallError, Value},
+        shell::{Context, ContextOptions},
+    };
+
+    #[test]
+    fn test_fake_dart() {
+        let context = Context::new(ContextOptions::default()).unwrap();
+        let dart = FakeDart::new(&context).unwrap();
+
+        context
+            .message_manager
+            .borrow_mut()
+            .register_method_handler("rust", |call, reply, _| {
+                if call.method == "add" {
+                    match call.args {
+                        Value::I64(v) => reply.send_ok(Value::I64(v + 1)),
+                        _ => reply.send_error("bad_args", None, Value::Null),
+                    }
+                }
+            });
+
+        assert_eq!(
+            dart.invoke_method("rust", "add", Value::I64(1)),
+            Some(Ok(Value::I64(2)))
+        );
+        assert_eq!(
+            dart.invoke_method("rust", "add", Value::Null)
+                .unwrap()
+                .unwrap_err()
+                .code,
+            "bad_args"
+        );
+        assert_eq!(dart.invoke_method("rust", "other", Value::Null), None);
+        assert_eq!(dart.invoke_method("missing", "add", Value::Null), None);
+
+        dart.set_method_handler("dart", |call| {
+            Err(MethodCallError::from_code_message("error", &call.method))
+        });
+        let result = Rc::new(RefCell::new(None));
+        let result_clone = result.clone();
+        context
+            .message_manager
+            .borrow()
+            .get_method_invoker(dart.engine(), "dart")
+            .call_method("hello", Value::Null, move |r| {
+                result_clone.borrow_mut().replace(r);
+            })
+            .unwrap();
+        assert!(result.borrow().is_none());
+        dart.pump();
+        let error = result.borrow_mut().take().unwrap().unwrap_err();
+        assert_eq!(error.message.as_deref(), Some("hello"));
+        assert_eq!(dart.take_method_calls("dart").len(), 1);
+
+        dart.shut_down().unwrap();
+    }
+}
@@ -0,0 +1,112 @@
+use std::{
+    cell::RefCell,
+    future::Future,
+    os::unix::io::RawFd,
+    pin::Pin,
+    rc::Rc,
+    task::{Poll, Waker},
+};
+
+use crate::{Error, Result};
+
+use super::{platform::run_loop::PlatformRunLoop, Handle};
+
+// Readiness of a file descriptor. When watching, only readable and writable
+// are taken into account; hang_up and error are always reported.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FdCondition {
+    pub readable: bool,
+    pub writable: bool,
+    pub hang_up: bool,
+    pub error: bool,
+}
+
+impl FdCondition {
+    pub fn readable() -> Self {
+        Self {
+            readable: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn writable() -> Self {
+        Self {
+            writable: true,
+            ..Default::default()
+        }
+    }
+}
+
+// Callback is invoked on run loop thread whenever the descriptor is ready;
+// Returning false stops watching. Watch is also removed when handle is dropped.
+pub(super) fn watch_fd_on<F>(
+    platform_run_loop: &Rc<PlatformRunLoop>,
+    fd: RawFd,
+    condition: FdCondition,
+    callback: F,
+) -> Result<Handle>
+where
+    F: FnMut(FdCondition) -> bool + 'static,
+{
+    let run_loop = platform_run_loop.clone();
+    let handle = run_loop
+        .watch_fd(fd, condition, callback)
+        .map_err(Error::from)?;
+    Ok(Handle::new(move || {
+        run_loop.unwatch_fd(handle);
+    }))
+}
+
+#[derive(Default)]
+struct FdReadyState {
+    result: Option<FdCondition>,
+    waker: Option<Waker>,
+}
+
+// Future that completes once file descriptor is ready; Created by
+// RunLoop::fd_ready.
+pub struct FdReady {
+    state: Rc<RefCell<FdReadyState>>,
+    _handle: Handle,
+}
+
+impl FdReady {
+    pub(super) fn new(
+        platform_run_loop: &Rc<PlatformRunLoop>,
+        fd: RawFd,
+        condition: FdCondition,
+    ) -> Result<Self> {
+        let state = Rc::new(RefCell::new(FdReadyState::default()));
+        let state_clone = state.clone();
+        let handle = watch_fd_on(platform_run_loop, fd, condition, move |condition| {
+            let waker = {
+                let mut state = state_clone.borrow_mut();
+                state.result.replace(condition);
+                state.waker.take()
+            };
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+            false
+        })?;
+        Ok(Self {
+            state,
+            _handle: handle,
+        })
+    }
+}
+
+impl Future for FdReady {
+    type Output = FdCondition;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.borrow_mut();
+        match state.result {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker.replace(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
@@ -4,10 +4,7 @@ use std::{
 };
 
 use crate::{
-    codec::{
-        value::{from_value, to_value},
-        MethodCall, MethodCallReply, MethodCallResult, Value,
-    },
+    codec::{value::to_value, MethodCall, MethodCallReply, Value},
     util::{Late, OkLog},
     Error, Result,
 };
@@ -17,6 +14,7 @@ use super::{
     api_model::{HotKeyCreateRequest, HotKeyDestroyRequest, HotKeyPressed},
     platform::hot_key::PlatformHotKeyManager,
     Context, EngineHandle, MethodCallHandler, MethodInvokerProvider, RegisteredMethodCallHandler,
+    TypedMethodChannel,
 };
 
 pub struct HotKeyManager {
@@ -24,6 +22,7 @@ pub struct HotKeyManager {
     platform_manager: Late<Rc<PlatformHotKeyManager>>,
     next_handle: HotKeyHandle,
     invoker_provider: Late<MethodInvokerProvider>,
+    methods: Rc<TypedMethodChannel<Self>>,
 }
 
 #[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -40,10 +39,17 @@ impl HotKeyManager {
             platform_manager: Late::new(),
             next_handle: HotKeyHandle(1),
             invoker_provider: Late::new(),
+            methods: Rc::new(Self::methods()),
         }
         .register(context, channel::HOT_KEY_MANAGER)
     }
 
+    fn methods() -> TypedMethodChannel<Self> {
+        TypedMethodChannel::<Self>::new()
+            .method(method::hot_key::CREATE, Self::on_create)
+            .method(method::hot_key::DESTROY, Self::on_destroy)
+    }
+
     fn on_create(
         &mut self,
         request: HotKeyCreateRequest,
@@ -59,11 +65,10 @@ impl HotKeyManager {
         Ok(handle)
     }
 
-    fn map_result<T>(result: Result<T>) -> MethodCallResult<Value>
-    where
-        T: serde::Serialize,
-    {
-        result.map(|v| to_value(v).unwrap()).map_err(|e| e.into())
+    fn on_destroy(&mut self, request: HotKeyDestroyRequest, _engine: EngineHandle) -> Result<()> {
+        self.platform_manager
+            .destroy_hot_key(request.handle)
+            .map_err(Error::from)
     }
 }
 
@@ -87,22 +92,7 @@ impl MethodCallHandler for HotKeyManager {
         reply: MethodCallReply<Value>,
         engine: EngineHandle,
     ) {
-        match call.method.as_str() {
-            method::hot_key::CREATE => {
-                let request: HotKeyCreateRequest = from_value(&call.args).unwrap();
-                let res = self.on_create(request, engine);
-                reply.send(Self::map_result(res));
-            }
-            method::hot_key::DESTROY => {
-                let request: HotKeyDestroyRequest = from_value(&call.args).unwrap();
-                let res = self
-                    .platform_manager
-                    .destroy_hot_key(request.handle)
-                    .map_err(Error::from);
-                reply.send(Self::map_result(res));
-            }
-            _ => {}
-        }
+        self.methods.clone().dispatch(self, call, reply, engine);
     }
 
     fn assign_weak_self(&mut self, weak_self: std::rc::Weak<std::cell::RefCell<Self>>) {
@@ -5,10 +5,7 @@ use std::{
 };
 
 use crate::{
-    codec::{
-        value::{from_value, to_value},
-        MethodCall, MethodCallReply, MethodInvoker, Value,
-    },
+    codec::{value::to_value, MethodCall, MethodCallReply, MethodInvoker, Value},
     util::{Late, OkLog},
     Error, Result,
 };
@@ -18,7 +15,7 @@ use super::{
     api_model::{MenuAction, MenuCreateRequest, MenuDestroyRequest, MenuOpen, SetMenuRequest},
     platform::menu::{PlatformMenu, PlatformMenuManager},
     Context, EngineHandle, MethodCallHandler, MethodInvokerProvider, RegisteredMethodCallHandler,
-    WindowMethodCallResult,
+    TypedMethodChannel,
 };
 
 struct MenuEntry {
@@ -33,6 +30,7 @@ pub struct MenuManager {
     next_handle: MenuHandle,
     weak_self: Late<Weak<RefCell<MenuManager>>>,
     invoker_provider: Late<MethodInvokerProvider>,
+    methods: Rc<TypedMethodChannel<Self>>,
 }
 
 #[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -57,10 +55,23 @@ impl MenuManager {
             next_handle: MenuHandle(1),
             weak_self: Late::new(),
             invoker_provider: Late::new(),
+            methods: Rc::new(Self::methods()),
         }
         .register(context, channel::MENU_MANAGER)
     }
 
+    fn methods() -> TypedMethodChannel<Self> {
+        TypedMethodChannel::<Self>::new()
+            .method(method::menu::CREATE_OR_UPDATE, Self::on_create_or_update)
+            .method(method::menu::DESTROY, |s, request, _| {
+                s.on_destroy(request);
+                Ok(())
+            })
+            .method(method::menu::SET_APP_MENU, |s, request, _| {
+                s.set_app_menu(request)
+            })
+    }
+
     pub fn get_platform_menu_manager(&self) -> &PlatformMenuManager {
         &self.platform_menu_manager
     }
@@ -96,19 +107,26 @@ impl MenuManager {
         Ok(handle)
     }
 
+    fn on_destroy(&mut self, request: MenuDestroyRequest) {
+        self.platform_menu_map.remove(&request.handle);
+    }
+
+    fn set_app_menu(&self, request: SetMenuRequest) -> Result<()> {
+        let menu = match request.handle {
+            Some(handle) => Some(self.get_platform_menu(handle)?),
+            None => None,
+        };
+        self.platform_menu_manager
+            .set_app_menu(menu)
+            .map_err(Error::from)
+    }
+
     fn invoker_for_menu(&self, menu_handle: MenuHandle) -> Option<MethodInvoker<Value>> {
         self.platform_menu_map.get(&menu_handle).map(|e| {
             self.invoker_provider
                 .get_method_invoker_for_engine(e.engine)
         })
     }
-
-    fn map_result<T>(result: Result<T>) -> WindowMethodCallResult
-    where
-        T: serde::Serialize,
-    {
-        result.map(|v| to_value(v).unwrap()).map_err(|e| e.into())
-    }
 }
 
 impl MethodCallHandler for MenuManager {
@@ -118,42 +136,7 @@ impl MethodCallHandler for MenuManager {
         reply: MethodCallReply<Value>,
         engine: EngineHandle,
     ) {
-        match call.method.as_str() {
-            method::menu::CREATE_OR_UPDATE => {
-                let request: MenuCreateRequest = from_value(&call.args).unwrap();
-                let res = self.on_create_or_update(request, engine);
-                reply.send(Self::map_result(res));
-            }
-            method::menu::DESTROY => {
-                let request: MenuDestroyRequest = from_value(&call.args).unwrap();
-                self.platform_menu_map.remove(&request.handle);
-                reply.send_ok(Value::Null);
-            }
-            method::menu::SET_APP_MENU => {
-                let request: SetMenuRequest = from_value(&call.args).unwrap();
-                match request.handle {
-                    Some(handle) => {
-                        let menu = self.platform_menu_map.get(&handle);
-                        match menu {
-                            Some(menu) => reply.send(Self::map_result(
-                                self.platform_menu_manager
-                                    .set_app_menu(Some(menu.platform_menu.clone()))
-                                    .map_err(|e| e.into()),
-                            )),
-                            None => {
-                                reply.send(Self::map_result::<()>(Err(Error::InvalidMenuHandle)));
-                            }
-                        }
-                    }
-                    None => reply.send(Self::map_result(
-                        self.platform_menu_manager
-                            .set_app_menu(None)
-                            .map_err(|e| e.into()),
-                    )),
-                }
-            }
-            _ => {}
-        };
+        self.methods.clone().dispatch(self, call, reply, engine);
     }
 
     fn assign_weak_self(&mut self, weak_self: Weak<RefCell<Self>>) {
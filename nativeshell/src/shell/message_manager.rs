@@ -1,59 +1,266 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{collections::HashMap, rc::Rc, time::Duration};
 
-use crate::codec::{
-    EngineMethodChannel, EventSender, MessageChannel, MessageReply, MessageSender, MethodCall,
-    MethodCallReply, MethodInvoker, StandardMethodCodec, Value,
+use log::error;
+
+use crate::{
+    codec::{
+        BinaryMessageChannel, BinaryMessageSender, EngineMethodChannel, EventSender,
+        MessageChannel, MessageCodec, MessageReply, MessageSender, MethodCall, MethodCallReply,
+        MethodCodec, MethodInvoker, StandardMethodCodec, StringCodec, Value, ValueRef,
+    },
+    Error, Result,
 };
 
-use super::{Context, ContextRef, EngineHandle, EngineManager};
+use super::{
+    api_constants::{channel, method},
+    intercept_message, intercept_method_call, BinaryMessengerReply, Context, ContextRef,
+    EngineHandle, EngineManager,
+};
 
-type MessageCallback = dyn Fn(Value, MessageReply<Value>, EngineHandle);
-type MethodCallback = dyn Fn(MethodCall<Value>, MethodCallReply<Value>, EngineHandle);
+// Registration of channel handler on single engine; Unregisters the handler when dropped.
+trait ChannelRegistration {}
 
-pub struct MessageManager {
-    context: Context,
+impl<V> ChannelRegistration for MessageChannel<V> {}
+
+impl ChannelRegistration for BinaryMessageChannel {}
+
+impl<V> ChannelRegistration for EngineMethodChannel<V> {}
+
+// Creates channel registration for given engine.
+type ChannelFactory =
+    dyn Fn(&Context, &EngineManager, EngineHandle, &str) -> Box<dyn ChannelRegistration>;
+
+#[derive(Default)]
+struct Channels {
+    registrations: HashMap<EngineHandle, HashMap<String, Box<dyn ChannelRegistration>>>,
+    factories: HashMap<String, Rc<ChannelFactory>>,
+}
+
+impl Channels {
+    fn register(
+        &mut self,
+        context: &Context,
+        engine_manager: &EngineManager,
+        channel: &str,
+        factory: Rc<ChannelFactory>,
+    ) {
+        // Remove existing registrations first; Dropping registration unregisters
+        // the channel handler from engine, which would otherwise happen after new
+        // handler has been registered.
+        self.unregister(channel);
+        self.factories.insert(channel.into(), factory);
+        for engine in engine_manager.get_all_engines() {
+            self.register_for_engine(context, engine_manager, engine, channel);
+        }
+    }
+
+    fn unregister(&mut self, channel: &str) {
+        self.factories.remove(channel);
+        for entry in self.registrations.values_mut() {
+            entry.remove(channel);
+        }
+    }
+
+    fn register_for_engine(
+        &mut self,
+        context: &Context,
+        engine_manager: &EngineManager,
+        engine: EngineHandle,
+        channel: &str,
+    ) {
+        if let Some(factory) = self.factories.get(channel) {
+            let registration = factory(context, engine_manager, engine, channel);
+            self.registrations
+                .entry(engine)
+                .or_default()
+                .insert(channel.into(), registration);
+        }
+    }
 
-    message_channels: HashMap<EngineHandle, HashMap<String, MessageChannel<Value>>>,
-    message_handlers: Rc<RefCell<HashMap<String, Box<MessageCallback>>>>,
+    fn engine_created(
+        &mut self,
+        context: &Context,
+        engine_manager: &EngineManager,
+        engine: EngineHandle,
+    ) {
+        let channels: Vec<String> = self.factories.keys().cloned().collect();
+        for channel in channels {
+            self.register_for_engine(context, engine_manager, engine, &channel);
+        }
+    }
+}
 
-    method_channels: HashMap<EngineHandle, HashMap<String, EngineMethodChannel<Value>>>,
-    method_handlers: Rc<RefCell<HashMap<String, Box<MethodCallback>>>>,
+pub struct MessageManager {
+    context: Context,
+    message_channels: Channels,
+    method_channels: Channels,
+    readiness_registered: bool,
 }
 
 impl MessageManager {
     pub(super) fn new(context: &ContextRef) -> Self {
         Self {
             context: context.weak(),
-            message_channels: HashMap::new(),
-            message_handlers: Rc::new(RefCell::new(HashMap::new())),
-            method_channels: HashMap::new(),
-            method_handlers: Rc::new(RefCell::new(HashMap::new())),
+            message_channels: Default::default(),
+            method_channels: Default::default(),
+            readiness_registered: false,
+        }
+    }
+
+    // Queues messages sent from Rust on given channel until Dart signals that it
+    // has registered the channel handler (ChannelReadiness.ready), or until
+    // timeout elapses since first message was queued. Queued messages are then
+    // sent in order. Applies to each engine separately.
+    pub fn enable_buffering(&mut self, channel: &str, timeout: Duration) {
+        if let Some(context) = self.context.get() {
+            self.register_readiness_handler();
+            context
+                .engine_manager
+                .borrow()
+                .channel_buffering()
+                .enable(channel, timeout);
         }
     }
 
+    // Stops buffering the channel; Messages queued so far are sent right away.
+    pub fn disable_buffering(&mut self, channel: &str) {
+        if let Some(context) = self.context.get() {
+            let buffering = context.engine_manager.borrow().channel_buffering().clone();
+            buffering.disable(channel);
+        }
+    }
+
+    // Marks channel as ready on given engine, same as when Dart calls
+    // ChannelReadiness.ready.
+    pub fn set_channel_ready(&self, engine: EngineHandle, channel: &str) {
+        if let Some(context) = self.context.get() {
+            let buffering = context.engine_manager.borrow().channel_buffering().clone();
+            buffering.set_ready(engine, channel);
+        }
+    }
+
+    fn register_readiness_handler(&mut self) {
+        if self.readiness_registered {
+            return;
+        }
+        self.readiness_registered = true;
+        let context = self.context.clone();
+        self.register_method_handler(channel::CHANNEL_READINESS, move |call, reply, engine| {
+            if call.method != method::channel_readiness::READY {
+                return;
+            }
+            match (call.args, context.get()) {
+                (Value::String(channel), Some(context)) => {
+                    context
+                        .message_manager
+                        .borrow()
+                        .set_channel_ready(engine, &channel);
+                    reply.send_ok(Value::Null);
+                }
+                _ => reply.send_error(
+                    "invalid_argument",
+                    Some("Expected channel name"),
+                    Value::Null,
+                ),
+            }
+        });
+    }
+
     pub fn register_message_handler<F>(&mut self, channel: &str, callback: F)
     where
         F: Fn(Value, MessageReply<Value>, EngineHandle) + 'static,
     {
-        if let Some(context) = self.context.get() {
-            if !self
-                .message_handlers
-                .as_ref()
-                .borrow()
-                .contains_key(channel)
-            {
-                // register handlers on engines
-                let manager = context.engine_manager.borrow();
-                let engines = manager.get_all_engines();
-                for engine in engines {
-                    self.register_message_channel_for_engine(&manager, engine, channel);
+        let context = self.context.clone();
+        let channel_name = channel.to_owned();
+        self.register_message_handler_with_codec(
+            channel,
+            &StandardMethodCodec,
+            move |message, reply, engine| {
+                if let Some(context) = context.get() {
+                    if let Some((message, reply)) =
+                        intercept_message(&context, &channel_name, engine, message, reply)
+                    {
+                        callback(message, reply, engine);
+                    }
                 }
-            }
+            },
+        );
+    }
 
-            self.message_handlers
-                .as_ref()
-                .borrow_mut()
-                .insert(channel.into(), Box::new(callback));
+    // Registers handler for raw binary messages; Message data is passed as is,
+    // without copying. Not seen by channel interceptors.
+    pub fn register_binary_message_handler<F>(&mut self, channel: &str, callback: F)
+    where
+        F: Fn(&[u8], BinaryMessengerReply, EngineHandle) + 'static,
+    {
+        if let Some(context) = self.context.get() {
+            let callback = Rc::new(callback);
+            let factory = move |context: &Context,
+                                engine_manager: &EngineManager,
+                                engine: EngineHandle,
+                                channel: &str|
+                  -> Box<dyn ChannelRegistration> {
+                let callback = callback.clone();
+                Box::new(BinaryMessageChannel::new_with_engine_manager(
+                    context.clone(),
+                    engine,
+                    channel,
+                    move |data, reply| callback(data, reply, engine),
+                    engine_manager,
+                ))
+            };
+            self.message_channels.register(
+                &self.context,
+                &context.engine_manager.borrow(),
+                channel,
+                Rc::new(factory),
+            );
+        }
+    }
+
+    // Registers handler for UTF-8 string messages; Not seen by channel
+    // interceptors.
+    pub fn register_string_message_handler<F>(&mut self, channel: &str, callback: F)
+    where
+        F: Fn(String, MessageReply<String>, EngineHandle) + 'static,
+    {
+        self.register_message_handler_with_codec(channel, &StringCodec, callback);
+    }
+
+    // Registers message handler that uses custom codec (i.e. JsonMessageCodec);
+    // Such handlers are not seen by channel interceptors.
+    pub fn register_message_handler_with_codec<V, F>(
+        &mut self,
+        channel: &str,
+        codec: &'static dyn MessageCodec<V>,
+        callback: F,
+    ) where
+        V: 'static,
+        F: Fn(V, MessageReply<V>, EngineHandle) + 'static,
+    {
+        if let Some(context) = self.context.get() {
+            let callback = Rc::new(callback);
+            let factory = move |context: &Context,
+                                engine_manager: &EngineManager,
+                                engine: EngineHandle,
+                                channel: &str|
+                  -> Box<dyn ChannelRegistration> {
+                let callback = callback.clone();
+                Box::new(MessageChannel::new_with_engine_manager(
+                    context.clone(),
+                    engine,
+                    channel,
+                    codec,
+                    move |value, reply| callback(value, reply, engine),
+                    engine_manager,
+                ))
+            };
+            self.message_channels.register(
+                &self.context,
+                &context.engine_manager.borrow(),
+                channel,
+                Rc::new(factory),
+            );
         }
     }
 
@@ -61,160 +268,316 @@ impl MessageManager {
     where
         F: Fn(MethodCall<Value>, MethodCallReply<Value>, EngineHandle) + 'static,
     {
-        if let Some(context) = self.context.get() {
-            if !self.method_handlers.as_ref().borrow().contains_key(channel) {
-                // register handlers on engines
-                let manager = context.engine_manager.borrow();
-                let engines = manager.get_all_engines();
-                for engine in engines {
-                    self.register_method_channel_for_engine(&manager, engine, channel);
+        let context = self.context.clone();
+        let channel_name = channel.to_owned();
+        self.register_method_handler_with_codec(
+            channel,
+            &StandardMethodCodec,
+            move |call, reply, engine| {
+                if let Some(context) = context.get() {
+                    if let Some((call, reply)) =
+                        intercept_method_call(&context, &channel_name, engine, call, reply)
+                    {
+                        callback(call, reply, engine);
+                    }
                 }
-            }
+            },
+        );
+    }
 
-            self.method_handlers
-                .as_ref()
-                .borrow_mut()
-                .insert(channel.into(), Box::new(callback));
+    // Registers method handler that uses custom codec (i.e. JsonMethodCodec);
+    // Such handlers are not seen by channel interceptors.
+    pub fn register_method_handler_with_codec<V, F>(
+        &mut self,
+        channel: &str,
+        codec: &'static dyn MethodCodec<V>,
+        callback: F,
+    ) where
+        V: 'static,
+        F: Fn(MethodCall<V>, MethodCallReply<V>, EngineHandle) + 'static,
+    {
+        if let Some(context) = self.context.get() {
+            let callback = Rc::new(callback);
+            let factory = move |context: &Context,
+                                engine_manager: &EngineManager,
+                                engine: EngineHandle,
+                                channel: &str|
+                  -> Box<dyn ChannelRegistration> {
+                let callback = callback.clone();
+                Box::new(EngineMethodChannel::new_with_engine_manager(
+                    context.clone(),
+                    engine,
+                    channel,
+                    codec,
+                    move |call, reply| callback(call, reply, engine),
+                    engine_manager,
+                ))
+            };
+            self.method_channels.register(
+                &self.context,
+                &context.engine_manager.borrow(),
+                channel,
+                Rc::new(factory),
+            );
         }
     }
 
-    pub fn unregister_message_handler(&mut self, channel: &str) {
-        self.message_handlers.as_ref().borrow_mut().remove(channel);
-
-        for entry in self.message_channels.values_mut() {
-            entry.remove(channel);
+    // Registers method handler that receives arguments borrowing from incoming
+    // message buffer; Avoids copying large strings and typed lists (unless there
+    // are channel interceptors configured).
+    pub fn register_method_handler_ref<F>(&mut self, channel: &str, callback: F)
+    where
+        F: Fn(MethodCall<ValueRef>, MethodCallReply<Value>, EngineHandle) + 'static,
+    {
+        if let Some(context) = self.context.get() {
+            let weak_context = self.context.clone();
+            let channel_name = channel.to_owned();
+            let callback = Rc::new(
+                move |call: MethodCall<ValueRef<'_>>,
+                      reply: MethodCallReply<Value>,
+                      engine: EngineHandle| {
+                    let context = match weak_context.get() {
+                        Some(context) => context,
+                        None => return,
+                    };
+                    if context.options.channel_interceptors.is_empty() {
+                        callback(call, reply, engine);
+                        return;
+                    }
+                    let args = call.args.to_value();
+                    let owned = MethodCall {
+                        method: call.method.clone(),
+                        args: args.clone(),
+                    };
+                    if let Some((intercepted, reply)) =
+                        intercept_method_call(&context, &channel_name, engine, owned, reply)
+                    {
+                        if intercepted.method == call.method && intercepted.args == args {
+                            callback(call, reply, engine);
+                        } else {
+                            // Call was rewritten; Encode it again to borrow from
+                            let encoded = StandardMethodCodec.encode_method_call(&intercepted);
+                            match StandardMethodCodec.try_decode_method_call_ref(&encoded) {
+                                Ok(call) => callback(call, reply, engine),
+                                Err(err) => error!("Invalid intercepted method call: {err}"),
+                            }
+                        }
+                    }
+                },
+            );
+            let factory = move |context: &Context,
+                                engine_manager: &EngineManager,
+                                engine: EngineHandle,
+                                channel: &str|
+                  -> Box<dyn ChannelRegistration> {
+                let callback = callback.clone();
+                Box::new(EngineMethodChannel::new_with_engine_manager_ref(
+                    context.clone(),
+                    engine,
+                    channel,
+                    move |call, reply| callback(call, reply, engine),
+                    engine_manager,
+                ))
+            };
+            self.method_channels.register(
+                &self.context,
+                &context.engine_manager.borrow(),
+                channel,
+                Rc::new(factory),
+            );
         }
     }
 
-    pub fn unregister_method_handler(&mut self, channel: &str) {
-        self.method_handlers.as_ref().borrow_mut().remove(channel);
+    pub fn unregister_message_handler(&mut self, channel: &str) {
+        self.message_channels.unregister(channel);
+    }
 
-        for entry in self.method_channels.values_mut() {
-            entry.remove(channel);
-        }
+    pub fn unregister_method_handler(&mut self, channel: &str) {
+        self.method_channels.unregister(channel);
     }
 
     pub fn get_message_sender(&self, engine: EngineHandle, channel: &str) -> MessageSender<Value> {
-        MessageSender::new(
-            self.context.clone(),
-            engine,
-            channel.into(),
-            &StandardMethodCodec,
-        )
+        self.get_message_sender_with_codec(engine, channel, &StandardMethodCodec)
+    }
+
+    pub fn get_binary_message_sender(
+        &self,
+        engine: EngineHandle,
+        channel: &str,
+    ) -> BinaryMessageSender {
+        BinaryMessageSender::new(self.context.clone(), engine, channel.into())
+    }
+
+    pub fn get_string_message_sender(
+        &self,
+        engine: EngineHandle,
+        channel: &str,
+    ) -> MessageSender<String> {
+        self.get_message_sender_with_codec(engine, channel, &StringCodec)
+    }
+
+    pub fn get_message_sender_with_codec<V>(
+        &self,
+        engine: EngineHandle,
+        channel: &str,
+        codec: &'static dyn MessageCodec<V>,
+    ) -> MessageSender<V> {
+        MessageSender::new(self.context.clone(), engine, channel.into(), codec)
     }
 
     pub fn get_event_sender(&self, engine: EngineHandle, channel: &str) -> EventSender<Value> {
-        EventSender::new(
-            self.context.clone(),
-            engine,
-            channel.into(),
-            &StandardMethodCodec,
-        )
+        self.get_event_sender_with_codec(engine, channel, &StandardMethodCodec)
+    }
+
+    pub fn get_event_sender_with_codec<V>(
+        &self,
+        engine: EngineHandle,
+        channel: &str,
+        codec: &'static dyn MethodCodec<V>,
+    ) -> EventSender<V> {
+        EventSender::new(self.context.clone(), engine, channel.into(), codec)
     }
 
     pub fn get_method_invoker(&self, engine: EngineHandle, channel: &str) -> MethodInvoker<Value> {
-        MethodInvoker::new(
-            self.context.clone(),
-            engine,
-            channel.into(),
-            &StandardMethodCodec,
-        )
+        self.get_method_invoker_with_codec(engine, channel, &StandardMethodCodec)
     }
 
-    pub(super) fn engine_created(&mut self, engine_manager: &EngineManager, engine: EngineHandle) {
-        let message_keys: Vec<String> = self
-            .message_handlers
-            .as_ref()
-            .borrow()
-            .keys()
-            .map(|s| s.into())
-            .collect();
-        for channel in message_keys {
-            self.register_message_channel_for_engine(engine_manager, engine, &channel);
-        }
+    pub fn get_method_invoker_with_codec<V>(
+        &self,
+        engine: EngineHandle,
+        channel: &str,
+        codec: &'static dyn MethodCodec<V>,
+    ) -> MethodInvoker<V> {
+        MethodInvoker::new(self.context.clone(), engine, channel.into(), codec)
+    }
 
-        let method_keys: Vec<String> = self
-            .method_handlers
-            .as_ref()
-            .borrow()
-            .keys()
-            .map(|s| s.into())
-            .collect();
+    // Posts message on all engines. Message is only encoded once.
+    pub fn broadcast_message(&self, channel: &str, message: &Value) -> Result<()> {
+        self.broadcast_message_with_codec(channel, message, &StandardMethodCodec)
+    }
 
-        for channel in method_keys {
-            self.register_method_channel_for_engine(engine_manager, engine, &channel);
+    pub fn broadcast_binary_message(&self, channel: &str, message: &[u8]) -> Result<()> {
+        match self.context.get() {
+            Some(context) => context
+                .engine_manager
+                .borrow()
+                .broadcast_message(channel, message),
+            None => Err(Error::InvalidContext),
         }
     }
 
-    fn on_message(
-        handlers: Rc<RefCell<HashMap<String, Box<MessageCallback>>>>,
-        value: Value,
-        channel: &str,
-        reply: MessageReply<Value>,
-        engine: EngineHandle,
-    ) {
-        let handlers = handlers.as_ref().borrow();
-        if let Some(handler) = handlers.get(channel) {
-            handler(value, reply, engine);
-        }
+    pub fn broadcast_string_message(&self, channel: &str, message: &str) -> Result<()> {
+        self.broadcast_binary_message(channel, message.as_bytes())
     }
 
-    fn on_method(
-        handlers: Rc<RefCell<HashMap<String, Box<MethodCallback>>>>,
-        call: MethodCall<Value>,
+    pub fn broadcast_message_with_codec<V>(
+        &self,
         channel: &str,
-        reply: MethodCallReply<Value>,
-        engine: EngineHandle,
-    ) {
-        let handlers = handlers.as_ref().borrow();
-        if let Some(handler) = handlers.get(channel) {
-            handler(call, reply, engine);
-        }
+        message: &V,
+        codec: &'static dyn MessageCodec<V>,
+    ) -> Result<()> {
+        self.broadcast_binary_message(channel, &codec.encode_message(message))
     }
 
-    fn register_message_channel_for_engine(
-        &mut self,
-        engine_manager: &EngineManager,
-        engine: EngineHandle,
-        channel: &str,
-    ) {
-        let channel_str = String::from(channel);
-        let handlers = self.message_handlers.clone();
-        let message_channel = MessageChannel::new_with_engine_manager(
-            self.context.clone(),
-            engine,
-            channel,
-            &StandardMethodCodec,
-            move |value, reply| {
-                Self::on_message(handlers.clone(), value, &channel_str, reply, engine);
-            },
-            engine_manager,
-        );
-        let map = self.message_channels.entry(engine);
-        let entry = map.or_default();
-        entry.insert(channel.into(), message_channel);
+    pub(super) fn engine_created(&mut self, engine_manager: &EngineManager, engine: EngineHandle) {
+        self.message_channels
+            .engine_created(&self.context, engine_manager, engine);
+        self.method_channels
+            .engine_created(&self.context, engine_manager, engine);
     }
+}
 
-    fn register_method_channel_for_engine(
-        &mut self,
-        engine_manager: &EngineManager,
-        engine: EngineHandle,
-        channel: &str,
-    ) {
-        let channel_str = String::from(channel);
-        let handlers = self.method_handlers.clone();
-        let method_channel = EngineMethodChannel::new_with_engine_manager(
-            self.context.clone(),
-            engine,
-            channel,
-            &StandardMethodCodec,
-            move |call, reply| {
-                Self::on_method(handlers.clone(), call, &channel_str, reply, engine);
-            },
-            engine_manager,
+#[cfg(all(test, feature = "null-backend"))]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::shell::{Context, ContextOptions, FakeDart};
+
+    #[test]
+    fn test_binary_and_string_channels() {
+        let context = Context::new(ContextOptions::default()).unwrap();
+        let dart = FakeDart::new(&context).unwrap();
+
+        context
+            .message_manager
+            .borrow_mut()
+            .register_binary_message_handler("binary", |data, reply, _| {
+                let mut data = data.to_vec();
+                data.reverse();
+                reply.send(&data);
+            });
+        context
+            .message_manager
+            .borrow_mut()
+            .register_string_message_handler("string", |message, reply, _| {
+                reply.send(message.to_uppercase());
+            });
+
+        assert_eq!(dart.send_message("binary", &[1, 2, 3]), Some(vec![3, 2, 1]));
+        assert_eq!(
+            dart.send_message("string", "hello".as_bytes()),
+            Some("HELLO".as_bytes().to_vec())
         );
-        let map = self.method_channels.entry(engine);
-        let entry = map.or_default();
-        entry.insert(channel.into(), method_channel);
+
+        // Handlers are registered on engines created later as well
+        let other = FakeDart::new(&context).unwrap();
+        assert_eq!(other.send_message("binary", &[4, 5]), Some(vec![5, 4]));
+
+        context
+            .message_manager
+            .borrow_mut()
+            .unregister_message_handler("binary");
+        assert_eq!(dart.send_message("binary", &[1]), None);
+
+        // Sending to Dart
+        dart.set_message_handler("dart", |data| [data, &[0]].concat());
+        let reply = Rc::new(RefCell::new(None));
+        let reply_clone = reply.clone();
+        let sender = context
+            .message_manager
+            .borrow()
+            .get_binary_message_sender(dart.engine(), "dart");
+        sender
+            .send_message(&[7], move |data| {
+                reply_clone.borrow_mut().replace(data.to_vec());
+            })
+            .unwrap();
+        sender.post_message(&[8]).unwrap();
+        dart.pump();
+        assert_eq!(reply.borrow_mut().take(), Some(vec![7, 0]));
+        assert_eq!(dart.take_messages("dart"), vec![vec![7], vec![8]]);
+
+        let reply_clone = reply.clone();
+        context
+            .message_manager
+            .borrow()
+            .get_string_message_sender(dart.engine(), "dart")
+            .send_message(&"a".into(), move |message| {
+                reply_clone
+                    .borrow_mut()
+                    .replace(message.as_bytes().to_vec());
+            })
+            .unwrap();
+        dart.pump();
+        assert_eq!(reply.borrow_mut().take(), Some(vec![b'a', 0]));
+        dart.take_messages("dart");
+
+        // Broadcast goes to all engines
+        let message_manager = context.message_manager.borrow();
+        message_manager
+            .broadcast_binary_message("dart", &[9])
+            .unwrap();
+        message_manager
+            .broadcast_string_message("dart", "text")
+            .unwrap();
+        drop(message_manager);
+        let expected = vec![vec![9], "text".as_bytes().to_vec()];
+        dart.pump();
+        assert_eq!(dart.take_messages("dart"), expected);
+        other.pump();
+        assert_eq!(other.take_messages("dart"), expected);
+
+        other.shut_down().unwrap();
+        dart.shut_down().unwrap();
     }
 }
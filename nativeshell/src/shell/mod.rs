@@ -1,11 +1,22 @@
 mod api_constants;
+mod application_manager;
+mod async_event_channel;
 mod async_method_call_handler;
 mod binary_messenger;
 mod bundle;
+mod channel_buffering;
+mod channel_interceptor;
+mod channel_recorder;
+#[cfg(fd_watch)]
+mod child_process;
 mod context;
 mod engine;
 mod engine_manager;
 mod event_channel;
+#[cfg(feature = "null-backend")]
+mod fake_dart;
+#[cfg(fd_watch)]
+mod fd_watch;
 mod geometry;
 mod handle;
 mod hot_key_manager;
@@ -17,18 +28,35 @@ mod method_call_handler;
 mod observatory;
 mod run_loop;
 mod screen_manager;
+mod shell_manager;
+#[cfg(fd_watch)]
+mod signal_handler;
+mod single_instance;
 mod status_item_manager;
+mod stream_event_channel;
+mod timer;
+mod typed_method_channel;
 mod window;
 mod window_manager;
 mod window_method_channel;
 
+pub use application_manager::*;
+pub use async_event_channel::*;
 pub use async_method_call_handler::*;
 pub use binary_messenger::*;
 pub use bundle::*;
+pub use channel_interceptor::*;
+pub use channel_recorder::*;
+#[cfg(fd_watch)]
+pub use child_process::*;
 pub use context::*;
 pub use engine::*;
 pub use engine_manager::*;
 pub use event_channel::*;
+#[cfg(feature = "null-backend")]
+pub use fake_dart::*;
+#[cfg(fd_watch)]
+pub use fd_watch::*;
 pub use geometry::*;
 pub use handle::*;
 pub use hot_key_manager::*;
@@ -38,6 +66,11 @@ pub use message_manager::*;
 pub use method_call_handler::*;
 pub use observatory::*;
 pub use run_loop::*;
+pub use shell_manager::*;
+pub use single_instance::*;
+pub use stream_event_channel::*;
+pub use timer::*;
+pub use typed_method_channel::*;
 pub use window::*;
 pub use window_manager::*;
 pub use window_method_channel::*;
@@ -0,0 +1,18 @@
+use crate::shell::api_model::Capabilities;
+
+use super::utils::{get_session_type, SessionType};
+
+pub fn get_capabilities() -> Capabilities {
+    Capabilities {
+        hot_keys: false,
+        status_items: false,
+        status_item_highlight: false,
+        app_menu: false,
+        window_menu: false,
+        system_menu: false,
+        collection_behavior: false,
+        full_screen: true,
+        window_positioning: get_session_type() == SessionType::X11,
+        fd_watch: true,
+    }
+}
@@ -216,7 +216,7 @@ impl DragDataAdapter for FallThroughDragDataAdapter {
     fn retrieve_drag_data(&self, data: &SelectionData, data_out: &mut HashMap<String, Value>) {
         let codec: &'static dyn MessageCodec<Value> = &StandardMethodCodec;
         let data = data.data();
-        let value = codec.decode_message(&data).unwrap();
+        let value = codec.decode_message(&data).unwrap_or_default();
         if let Value::Map(value) = value {
             for entry in value {
                 if let Value::String(key) = entry.0 {
@@ -1,5 +1,6 @@
 pub mod app_delegate;
 pub mod binary_messenger;
+pub mod capabilities;
 pub mod drag_context;
 pub mod drag_data;
 pub mod engine;
@@ -1,11 +1,19 @@
 use std::{
     cell::{Cell, RefCell},
     collections::HashMap,
+    os::unix::io::RawFd,
     rc::Rc,
     time::Duration,
 };
 
-use glib::{source_remove, timeout_add_local, Continue, MainContext, SourceId};
+use glib::{
+    source_remove, timeout_add_local, unix_fd_add_local, Continue, IOCondition, MainContext,
+    SourceId,
+};
+
+use crate::shell::FdCondition;
+
+use super::error::PlatformResult;
 
 pub type HandleType = usize;
 pub const INVALID_HANDLE: HandleType = 0;
@@ -59,6 +67,48 @@ impl PlatformRunLoop {
         handle
     }
 
+    pub fn watch_fd<F>(
+        &self,
+        fd: RawFd,
+        condition: FdCondition,
+        mut callback: F,
+    ) -> PlatformResult<HandleType>
+    where
+        F: FnMut(FdCondition) -> bool + 'static,
+    {
+        let handle = self.next_handle();
+
+        let mut io_condition = IOCondition::HUP | IOCondition::ERR | IOCondition::NVAL;
+        if condition.readable {
+            io_condition |= IOCondition::IN;
+        }
+        if condition.writable {
+            io_condition |= IOCondition::OUT;
+        }
+
+        let timers = self.timers.clone();
+        let source_id = unix_fd_add_local(fd, io_condition, move |_, io_condition| {
+            let condition = FdCondition {
+                readable: io_condition.contains(IOCondition::IN),
+                writable: io_condition.contains(IOCondition::OUT),
+                hang_up: io_condition.contains(IOCondition::HUP),
+                error: io_condition.intersects(IOCondition::ERR | IOCondition::NVAL),
+            };
+            if callback(condition) {
+                Continue(true)
+            } else {
+                timers.borrow_mut().remove(&handle);
+                Continue(false)
+            }
+        });
+        self.timers.borrow_mut().insert(handle, source_id);
+        Ok(handle)
+    }
+
+    pub fn unwatch_fd(&self, handle: HandleType) {
+        self.unschedule(handle);
+    }
+
     pub fn run(&self) {
         gtk::main();
     }
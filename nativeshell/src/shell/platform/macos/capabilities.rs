@@ -0,0 +1,16 @@
+use crate::shell::api_model::Capabilities;
+
+pub fn get_capabilities() -> Capabilities {
+    Capabilities {
+        hot_keys: true,
+        status_items: true,
+        status_item_highlight: true,
+        app_menu: true,
+        window_menu: true,
+        system_menu: false,
+        collection_behavior: true,
+        full_screen: true,
+        window_positioning: true,
+        fd_watch: false,
+    }
+}
@@ -1,6 +1,7 @@
 pub mod app_delegate;
 pub mod binary_messenger;
 pub mod bundle;
+pub mod capabilities;
 mod drag_context;
 pub mod drag_data;
 pub mod engine;
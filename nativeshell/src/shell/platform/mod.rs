@@ -1,21 +1,24 @@
 pub use self::platform_impl::*;
 
-// #[path = "null/mod.rs"]
-// mod platform_impl;
+// In-memory implementation without Flutter engine (i.e. for testing)
+#[cfg(feature = "null-backend")]
+#[path = "null/mod.rs"]
+mod platform_impl;
 
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "null-backend")))]
 #[path = "macos/mod.rs"]
 mod platform_impl;
 
-#[cfg(target_os = "windows")]
+#[cfg(all(target_os = "windows", not(feature = "null-backend")))]
 #[path = "win32/mod.rs"]
 mod platform_impl;
 
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", not(feature = "null-backend")))]
 #[path = "linux/mod.rs"]
 mod platform_impl;
 
 // Null implementation - include just to make sure that it compiles
+#[cfg(not(feature = "null-backend"))]
 #[allow(unused_imports, unused_variables, dead_code)]
 #[path = "null/mod.rs"]
 mod null;
@@ -1,27 +1,79 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+    rc::Rc,
+};
+
 use crate::shell::BinaryMessengerReply;
 
-use super::error::{PlatformError, PlatformResult};
+use super::error::PlatformResult;
+
+type ChannelHandler = Rc<dyn Fn(&[u8], BinaryMessengerReply)>;
+
+// Message sent from Rust to the (fake) Dart side.
+pub struct OutgoingMessage {
+    pub channel: String,
+    pub message: Vec<u8>,
+    // None for posted messages
+    pub reply: Option<Box<dyn FnOnce(&[u8])>>,
+}
 
-pub struct PlatformBinaryMessenger {}
+// State shared between engine and its binary messengers. Outgoing messages are
+// queued until picked up, so that replies are never delivered synchronously
+// from within send_message.
+#[derive(Default)]
+pub struct MessengerState {
+    handlers: RefCell<HashMap<String, ChannelHandler>>,
+    outgoing: RefCell<VecDeque<OutgoingMessage>>,
+}
+
+impl MessengerState {
+    pub fn channel_handler(&self, channel: &str) -> Option<ChannelHandler> {
+        self.handlers.borrow().get(channel).cloned()
+    }
+
+    pub fn take_outgoing_message(&self) -> Option<OutgoingMessage> {
+        self.outgoing.borrow_mut().pop_front()
+    }
+}
+
+pub struct PlatformBinaryMessenger {
+    pub(super) state: Rc<MessengerState>,
+}
 
-#[allow(unused_variables)]
 impl PlatformBinaryMessenger {
     pub fn register_channel_handler<F>(&self, channel: &str, callback: F)
     where
         F: Fn(&[u8], BinaryMessengerReply) + 'static,
     {
+        self.state
+            .handlers
+            .borrow_mut()
+            .insert(channel.into(), Rc::new(callback));
     }
 
-    pub fn unregister_channel_handler(&self, channel: &str) {}
+    pub fn unregister_channel_handler(&self, channel: &str) {
+        self.state.handlers.borrow_mut().remove(channel);
+    }
 
     pub fn send_message<F>(&self, channel: &str, message: &[u8], reply: F) -> PlatformResult<()>
     where
         F: FnOnce(&[u8]) + 'static,
     {
-        Err(PlatformError::NotImplemented)
+        self.state.outgoing.borrow_mut().push_back(OutgoingMessage {
+            channel: channel.into(),
+            message: message.into(),
+            reply: Some(Box::new(reply)),
+        });
+        Ok(())
     }
 
     pub fn post_message(&self, channel: &str, message: &[u8]) -> PlatformResult<()> {
-        Err(PlatformError::NotImplemented)
+        self.state.outgoing.borrow_mut().push_back(OutgoingMessage {
+            channel: channel.into(),
+            message: message.into(),
+            reply: None,
+        });
+        Ok(())
     }
 }
@@ -0,0 +1,17 @@
+use crate::shell::api_model::Capabilities;
+
+// Everything is kept in memory so all features are "supported".
+pub fn get_capabilities() -> Capabilities {
+    Capabilities {
+        hot_keys: true,
+        status_items: true,
+        status_item_highlight: true,
+        app_menu: true,
+        window_menu: true,
+        system_menu: true,
+        collection_behavior: true,
+        full_screen: true,
+        window_positioning: true,
+        fd_watch: true,
+    }
+}
@@ -1,27 +1,51 @@
-use super::{binary_messenger::PlatformBinaryMessenger, error::PlatformResult};
+use std::{cell::Cell, rc::Rc};
+
+use super::{
+    binary_messenger::{MessengerState, PlatformBinaryMessenger},
+    error::PlatformResult,
+};
 
 pub type PlatformEngineType = isize;
 
 pub struct PlatformEngine {
     pub(crate) handle: PlatformEngineType,
+    pub(crate) messenger_state: Rc<MessengerState>,
+    launched: bool,
 }
 
 pub type PlatformPlugin = isize;
 
+thread_local! {
+    static NEXT_HANDLE: Cell<PlatformEngineType> = const { Cell::new(1) };
+}
+
 impl PlatformEngine {
     pub fn new(_plugins: &[PlatformPlugin]) -> Self {
-        PlatformEngine { handle: 0 }
+        let handle = NEXT_HANDLE.with(|h| h.replace(h.get() + 1));
+        PlatformEngine {
+            handle,
+            messenger_state: Rc::new(MessengerState::default()),
+            launched: false,
+        }
     }
 
     pub fn new_binary_messenger(&self) -> PlatformBinaryMessenger {
-        PlatformBinaryMessenger {}
+        PlatformBinaryMessenger {
+            state: self.messenger_state.clone(),
+        }
     }
 
     pub fn launch(&mut self) -> PlatformResult<()> {
-        Err(super::error::PlatformError::NotImplemented)
+        self.launched = true;
+        Ok(())
+    }
+
+    pub fn is_launched(&self) -> bool {
+        self.launched
     }
 
     pub fn shut_down(&mut self) -> PlatformResult<()> {
-        Err(super::error::PlatformError::NotImplemented)
+        self.launched = false;
+        Ok(())
     }
 }
@@ -3,6 +3,7 @@ use std::fmt::Display;
 #[derive(Debug, Clone)]
 pub enum PlatformError {
     NotImplemented,
+    NotAvailable,
     UnknownError,
 }
 
@@ -1,4 +1,4 @@
-use std::{cell::RefCell, rc::Weak};
+use std::{cell::RefCell, collections::HashMap, rc::Weak};
 
 use crate::shell::{
     api_model::Accelerator, Context, EngineHandle, HotKeyHandle, HotKeyManagerDelegate,
@@ -6,30 +6,38 @@ use crate::shell::{
 
 use super::error::PlatformResult;
 
-pub(crate) struct PlatformHotKeyManager {}
+// Keeps track of registered hot keys; There is no keyboard to press them.
+pub(crate) struct PlatformHotKeyManager {
+    hot_keys: RefCell<HashMap<HotKeyHandle, EngineHandle>>,
+}
 
 impl PlatformHotKeyManager {
-    pub fn new(context: Context, delegate: Weak<RefCell<dyn HotKeyManagerDelegate>>) -> Self {
-        Self {}
+    pub fn new(_context: Context, _delegate: Weak<RefCell<dyn HotKeyManagerDelegate>>) -> Self {
+        Self {
+            hot_keys: RefCell::new(HashMap::new()),
+        }
     }
 
-    pub fn assign_weak_self(&self, weak: Weak<PlatformHotKeyManager>) {}
+    pub fn assign_weak_self(&self, _weak: Weak<PlatformHotKeyManager>) {}
 
     pub fn create_hot_key(
         &self,
-        accelerator: Accelerator,
-        virtual_key: i64,
+        _accelerator: Accelerator,
+        _virtual_key: i64,
         handle: HotKeyHandle,
         engine: EngineHandle,
     ) -> PlatformResult<()> {
+        self.hot_keys.borrow_mut().insert(handle, engine);
         Ok(())
     }
 
     pub fn destroy_hot_key(&self, handle: HotKeyHandle) -> PlatformResult<()> {
+        self.hot_keys.borrow_mut().remove(&handle);
         Ok(())
     }
 
     pub fn engine_destroyed(&self, engine: EngineHandle) -> PlatformResult<()> {
+        self.hot_keys.borrow_mut().retain(|_, e| *e != engine);
         Ok(())
     }
 }
@@ -5,7 +5,7 @@ use crate::shell::{api_model::KeyboardMap, Context, KeyboardMapDelegate};
 pub struct PlatformKeyboardMap {}
 
 impl PlatformKeyboardMap {
-    pub fn new(context: Context, delegate: Weak<RefCell<dyn KeyboardMapDelegate>>) -> Self {
+    pub fn new(_context: Context, _delegate: Weak<RefCell<dyn KeyboardMapDelegate>>) -> Self {
         Self {}
     }
 
@@ -13,5 +13,5 @@ impl PlatformKeyboardMap {
         KeyboardMap { keys: vec![] }
     }
 
-    pub fn assign_weak_self(&self, weak: Weak<PlatformKeyboardMap>) {}
+    pub fn assign_weak_self(&self, _weak: Weak<PlatformKeyboardMap>) {}
 }
@@ -1,40 +1,117 @@
 use std::{
-    cell::RefCell,
+    cell::{Ref, RefCell},
     rc::{Rc, Weak},
 };
 
 use super::error::{PlatformError, PlatformResult};
 use crate::shell::{api_model::Menu, Context, MenuDelegate, MenuHandle, MenuManager};
 
-pub struct PlatformMenu {}
+pub struct PlatformMenu {
+    handle: MenuHandle,
+    delegate: Weak<RefCell<dyn MenuDelegate>>,
+    menu: RefCell<Menu>,
+    submenus: RefCell<Vec<Rc<PlatformMenu>>>,
+    on_action: RefCell<Option<Box<dyn FnOnce()>>>,
+}
 
-#[allow(unused_variables)]
 impl PlatformMenu {
     pub fn new(
-        context: Context,
+        _context: Context,
         handle: MenuHandle,
         delegate: Weak<RefCell<dyn MenuDelegate>>,
     ) -> Self {
-        Self {}
+        Self {
+            handle,
+            delegate,
+            menu: RefCell::new(Default::default()),
+            submenus: RefCell::new(Vec::new()),
+            on_action: RefCell::new(None),
+        }
     }
 
-    pub fn assign_weak_self(&self, weak: Weak<PlatformMenu>) {}
+    pub fn assign_weak_self(&self, _weak: Weak<PlatformMenu>) {}
 
     pub fn update_from_menu(&self, menu: Menu, manager: &MenuManager) -> PlatformResult<()> {
-        Err(PlatformError::NotImplemented)
+        // When compiled next to real backend (see platform/mod.rs) the manager
+        // hands out the other PlatformMenu type
+        #[cfg(feature = "null-backend")]
+        {
+            *self.submenus.borrow_mut() = menu
+                .items
+                .iter()
+                .filter_map(|item| item.submenu)
+                .filter_map(|submenu| manager.get_platform_menu(submenu).ok())
+                .collect();
+        }
+        *self.menu.borrow_mut() = menu;
+        Ok(())
+    }
+
+    pub fn handle(&self) -> MenuHandle {
+        self.handle
+    }
+
+    pub fn menu(&self) -> Ref<'_, Menu> {
+        self.menu.borrow()
+    }
+
+    // Simulates user selecting menu item with given id. The item may be in
+    // this menu or in any of its submenus.
+    pub fn perform_action(&self, id: i64) -> PlatformResult<()> {
+        let (handle, delegate) = self.find_item(id).ok_or(PlatformError::UnknownError)?;
+        if let Some(delegate) = delegate.upgrade() {
+            delegate.borrow().on_menu_open(handle);
+            delegate.borrow().on_menu_action(handle, id);
+        }
+        let on_action = self.on_action.borrow_mut().take();
+        if let Some(on_action) = on_action {
+            on_action();
+        }
+        Ok(())
+    }
+
+    // Returns handle and delegate of the menu containing enabled item with given id.
+    fn find_item(&self, id: i64) -> Option<(MenuHandle, Weak<RefCell<dyn MenuDelegate>>)> {
+        let enabled = self
+            .menu
+            .borrow()
+            .items
+            .iter()
+            .any(|item| item.id == id && item.enabled && !item.separator);
+        if enabled {
+            return Some((self.handle, self.delegate.clone()));
+        }
+        self.submenus
+            .borrow()
+            .iter()
+            .find_map(|submenu| submenu.find_item(id))
+    }
+
+    // Callback invoked once when next action is performed (used for popup menus).
+    pub(super) fn on_action<F: FnOnce() + 'static>(&self, callback: F) {
+        self.on_action.borrow_mut().replace(Box::new(callback));
     }
 }
 
-pub struct PlatformMenuManager {}
+pub struct PlatformMenuManager {
+    app_menu: RefCell<Option<Rc<PlatformMenu>>>,
+}
 
 impl PlatformMenuManager {
-    pub fn new(context: Context) -> Self {
-        Self {}
+    pub fn new(_context: Context) -> Self {
+        Self {
+            app_menu: RefCell::new(None),
+        }
     }
 
     pub(crate) fn assign_weak_self(&self, _weak_self: Weak<PlatformMenuManager>) {}
 
     pub fn set_app_menu(&self, menu: Option<Rc<PlatformMenu>>) -> PlatformResult<()> {
-        Err(PlatformError::NotImplemented)
+        *self.app_menu.borrow_mut() = menu;
+        Ok(())
+    }
+
+    pub fn app_menu(&self) -> Option<Rc<PlatformMenu>> {
+        self.app_menu.borrow().clone()
     }
 }
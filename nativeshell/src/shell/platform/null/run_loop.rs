@@ -1,43 +1,280 @@
-use std::time::Duration;
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Condvar, Mutex},
+    time::{Duration, Instant},
+};
+
+#[cfg(fd_watch)]
+use std::os::unix::io::RawFd;
+
+#[cfg(fd_watch)]
+use crate::shell::FdCondition;
+
+#[cfg(fd_watch)]
+use super::error::PlatformResult;
 
 pub type HandleType = usize;
 pub const INVALID_HANDLE: HandleType = 0;
 
-pub struct PlatformRunLoop {}
+type SenderCallback = Box<dyn FnOnce() + Send>;
+
+#[cfg(fd_watch)]
+struct FdWatch {
+    fd: RawFd,
+    condition: FdCondition,
+    // None while being executed
+    callback: Option<Box<dyn FnMut(FdCondition) -> bool>>,
+}
+
+// Watched descriptors are checked (without blocking) when polling; While there
+// are any, run() wakes up periodically to check them.
+#[cfg(fd_watch)]
+const FD_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Default)]
+struct SenderState {
+    callbacks: Mutex<VecDeque<SenderCallback>>,
+    condition: Condvar,
+}
+
+// In-memory run loop; Timers are kept in process and executed either by run()
+// or by polling the run loop manually (see poll()).
+pub struct PlatformRunLoop {
+    next_handle: Cell<HandleType>,
+    timers: RefCell<HashMap<HandleType, (Instant, Box<dyn FnOnce()>)>>,
+    stopped: Cell<bool>,
+    sender: Arc<SenderState>,
+    #[cfg(fd_watch)]
+    fd_watches: RefCell<HashMap<HandleType, FdWatch>>,
+}
 
-#[allow(unused_variables)]
 impl PlatformRunLoop {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            next_handle: Cell::new(INVALID_HANDLE + 1),
+            timers: RefCell::new(HashMap::new()),
+            stopped: Cell::new(false),
+            sender: Arc::new(SenderState::default()),
+            #[cfg(fd_watch)]
+            fd_watches: RefCell::new(HashMap::new()),
+        }
     }
 
-    pub fn unschedule(&self, handle: HandleType) {}
+    pub fn unschedule(&self, handle: HandleType) {
+        self.timers.borrow_mut().remove(&handle);
+    }
 
     #[must_use]
     pub fn schedule<F>(&self, in_time: Duration, callback: F) -> HandleType
     where
         F: FnOnce() + 'static,
     {
-        INVALID_HANDLE
+        let handle = self.next_handle.get();
+        self.next_handle.set(handle + 1);
+        self.timers
+            .borrow_mut()
+            .insert(handle, (Instant::now() + in_time, Box::new(callback)));
+        handle
+    }
+
+    #[cfg(fd_watch)]
+    pub fn watch_fd<F>(
+        &self,
+        fd: RawFd,
+        condition: FdCondition,
+        callback: F,
+    ) -> PlatformResult<HandleType>
+    where
+        F: FnMut(FdCondition) -> bool + 'static,
+    {
+        let handle = self.next_handle.get();
+        self.next_handle.set(handle + 1);
+        self.fd_watches.borrow_mut().insert(
+            handle,
+            FdWatch {
+                fd,
+                condition,
+                callback: Some(Box::new(callback)),
+            },
+        );
+        Ok(handle)
     }
 
-    pub fn run(&self) {}
+    #[cfg(fd_watch)]
+    pub fn unwatch_fd(&self, handle: HandleType) {
+        self.fd_watches.borrow_mut().remove(&handle);
+    }
 
-    pub fn stop(&self) {}
+    pub fn run(&self) {
+        self.stopped.set(false);
+        while !self.stopped.get() {
+            if self.poll() {
+                continue;
+            }
+            let timeout = self
+                .next_timer()
+                .map(|t| t.saturating_duration_since(Instant::now()));
+            #[cfg(fd_watch)]
+            let timeout = if self.fd_watches.borrow().is_empty() {
+                timeout
+            } else {
+                Some(timeout.map_or(FD_POLL_INTERVAL, |t| t.min(FD_POLL_INTERVAL)))
+            };
+            self.wait(timeout);
+        }
+    }
+
+    pub fn stop(&self) {
+        self.stopped.set(true);
+        self.sender.condition.notify_one();
+    }
 
     pub fn new_sender(&self) -> PlatformRunLoopSender {
-        PlatformRunLoopSender {}
+        PlatformRunLoopSender {
+            state: self.sender.clone(),
+        }
+    }
+
+    // Executes callbacks sent from other threads and timers that are due.
+    // Returns whether anything was executed.
+    pub fn poll(&self) -> bool {
+        let mut executed = false;
+        loop {
+            let callback = self.sender.callbacks.lock().unwrap().pop_front();
+            match callback {
+                Some(callback) => {
+                    callback();
+                    executed = true;
+                }
+                None => break,
+            }
+        }
+        let now = Instant::now();
+        let mut due: Vec<_> = self
+            .timers
+            .borrow()
+            .iter()
+            .filter(|(_, (time, _))| *time <= now)
+            .map(|(handle, (time, _))| (*time, *handle))
+            .collect();
+        due.sort_unstable();
+        for (_, handle) in due {
+            // Timer might have been unscheduled by previous callback
+            let timer = self.timers.borrow_mut().remove(&handle);
+            if let Some((_, callback)) = timer {
+                callback();
+                executed = true;
+            }
+        }
+        #[cfg(fd_watch)]
+        {
+            executed |= self.poll_fds();
+        }
+        executed
+    }
+
+    #[cfg(fd_watch)]
+    fn poll_fds(&self) -> bool {
+        let (handles, mut poll_fds): (Vec<HandleType>, Vec<libc::pollfd>) = self
+            .fd_watches
+            .borrow()
+            .iter()
+            .filter(|(_, watch)| watch.callback.is_some())
+            .map(|(handle, watch)| {
+                let mut events = 0;
+                if watch.condition.readable {
+                    events |= libc::POLLIN;
+                }
+                if watch.condition.writable {
+                    events |= libc::POLLOUT;
+                }
+                let poll_fd = libc::pollfd {
+                    fd: watch.fd,
+                    events,
+                    revents: 0,
+                };
+                (*handle, poll_fd)
+            })
+            .unzip();
+        if poll_fds.is_empty() {
+            return false;
+        }
+        let res = unsafe { libc::poll(poll_fds.as_mut_ptr(), poll_fds.len() as libc::nfds_t, 0) };
+        if res <= 0 {
+            return false;
+        }
+        let mut executed = false;
+        for (handle, poll_fd) in handles.into_iter().zip(poll_fds) {
+            if poll_fd.revents == 0 {
+                continue;
+            }
+            let condition = FdCondition {
+                readable: poll_fd.revents & libc::POLLIN != 0,
+                writable: poll_fd.revents & libc::POLLOUT != 0,
+                hang_up: poll_fd.revents & libc::POLLHUP != 0,
+                error: poll_fd.revents & (libc::POLLERR | libc::POLLNVAL) != 0,
+            };
+            // Watch might have been removed by previous callback
+            let callback = self
+                .fd_watches
+                .borrow_mut()
+                .get_mut(&handle)
+                .and_then(|watch| watch.callback.take());
+            if let Some(mut callback) = callback {
+                executed = true;
+                let keep = callback(condition);
+                let mut fd_watches = self.fd_watches.borrow_mut();
+                if keep {
+                    if let Some(watch) = fd_watches.get_mut(&handle) {
+                        watch.callback.replace(callback);
+                    }
+                } else {
+                    fd_watches.remove(&handle);
+                }
+            }
+        }
+        executed
+    }
+
+    // Returns time of the earliest scheduled timer.
+    pub fn next_timer(&self) -> Option<Instant> {
+        self.timers.borrow().values().map(|(time, _)| *time).min()
+    }
+
+    fn wait(&self, timeout: Option<Duration>) {
+        let callbacks = self.sender.callbacks.lock().unwrap();
+        if !callbacks.is_empty() || self.stopped.get() {
+            return;
+        }
+        let _lock = match timeout {
+            Some(timeout) => {
+                self.sender
+                    .condition
+                    .wait_timeout(callbacks, timeout)
+                    .unwrap()
+                    .0
+            }
+            None => self.sender.condition.wait(callbacks).unwrap(),
+        };
     }
 }
 
 #[derive(Clone)]
-pub struct PlatformRunLoopSender {}
+pub struct PlatformRunLoopSender {
+    state: Arc<SenderState>,
+}
 
-#[allow(unused_variables)]
 impl PlatformRunLoopSender {
     pub fn send<F>(&self, callback: F)
     where
         F: FnOnce() + 'static + Send,
     {
+        self.state
+            .callbacks
+            .lock()
+            .unwrap()
+            .push_back(Box::new(callback));
+        self.state.condition.notify_one();
     }
 }
@@ -1,29 +1,37 @@
 use std::{cell::RefCell, rc::Weak};
 
-use crate::shell::{api_model::Screen, screen_manager::ScreenManagerDelegate, Point};
+use crate::shell::{api_model::Screen, screen_manager::ScreenManagerDelegate, Point, Rect};
 
-use super::error::{PlatformError, PlatformResult};
+use super::error::PlatformResult;
 
+pub const MAIN_SCREEN_ID: i64 = 1;
+
+// Single virtual screen; Logical and system coordinates are the same.
 pub struct PlatformScreenManager {}
 
 impl PlatformScreenManager {
-    pub fn new(delegate: Weak<RefCell<dyn ScreenManagerDelegate>>) -> Self {
+    pub fn new(_delegate: Weak<RefCell<dyn ScreenManagerDelegate>>) -> Self {
         Self {}
     }
 
     pub fn get_screens(&self) -> PlatformResult<Vec<Screen>> {
-        Err(PlatformError::NotImplemented)
+        Ok(vec![Screen {
+            id: MAIN_SCREEN_ID,
+            frame: Rect::xywh(0.0, 0.0, 1920.0, 1080.0),
+            work_area: Rect::xywh(0.0, 0.0, 1920.0, 1080.0),
+            scaling_factor: 1.0,
+        }])
     }
 
     pub fn get_main_screen(&self) -> PlatformResult<i64> {
-        Err(PlatformError::NotImplemented)
+        Ok(MAIN_SCREEN_ID)
     }
 
     pub fn logical_to_system(&self, offset: Point) -> PlatformResult<Point> {
-        Err(PlatformError::NotImplemented)
+        Ok(offset)
     }
 
     pub fn system_to_logical(&self, offset: Point) -> PlatformResult<Point> {
-        Err(PlatformError::NotImplemented)
+        Ok(offset)
     }
 }
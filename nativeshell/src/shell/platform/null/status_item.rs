@@ -1,65 +1,89 @@
 use std::{
-    cell::RefCell,
+    cell::{Cell, RefCell},
     rc::{Rc, Weak},
 };
 
 use crate::{
     shell::{
-        api_model::ImageData,
+        api_model::{ImageData, StatusItemActionType},
         status_item_manager::{StatusItemDelegate, StatusItemHandle},
         EngineHandle, Point, Rect,
     },
     Context,
 };
 
-use super::{
-    error::{PlatformError, PlatformResult},
-    menu::PlatformMenu,
-};
+use super::{error::PlatformResult, menu::PlatformMenu, screen_manager::MAIN_SCREEN_ID};
 
 pub struct PlatformStatusItem {
     pub(crate) engine: EngineHandle,
+    handle: StatusItemHandle,
+    delegate: Weak<RefCell<dyn StatusItemDelegate>>,
+    image: RefCell<Vec<ImageData>>,
+    hint: RefCell<String>,
+    highlighted: Cell<bool>,
 }
 
 impl PlatformStatusItem {
-    pub fn assign_weak_self(&self, weak: Weak<PlatformStatusItem>) {}
+    pub fn assign_weak_self(&self, _weak: Weak<PlatformStatusItem>) {}
 
     pub fn set_image(&self, image: Vec<ImageData>) -> PlatformResult<()> {
-        Err(PlatformError::NotImplemented)
+        *self.image.borrow_mut() = image;
+        Ok(())
+    }
+
+    pub fn image(&self) -> Vec<ImageData> {
+        self.image.borrow().clone()
     }
 
     pub fn set_hint(&self, hint: String) -> PlatformResult<()> {
-        Err(PlatformError::NotImplemented)
+        *self.hint.borrow_mut() = hint;
+        Ok(())
+    }
+
+    pub fn hint(&self) -> String {
+        self.hint.borrow().clone()
     }
 
-    pub fn show_menu<F>(&self, menu: Rc<PlatformMenu>, offset: Point, on_done: F)
+    pub fn show_menu<F>(&self, _menu: Rc<PlatformMenu>, _offset: Point, on_done: F)
     where
         F: FnOnce(PlatformResult<()>) + 'static,
     {
-        on_done(Err(PlatformError::NotImplemented))
+        on_done(Ok(()))
     }
 
     pub fn set_highlighted(&self, highlighted: bool) -> PlatformResult<()> {
-        Err(PlatformError::NotImplemented)
+        self.highlighted.set(highlighted);
+        Ok(())
+    }
+
+    pub fn is_highlighted(&self) -> bool {
+        self.highlighted.get()
     }
 
     pub fn get_geometry(&self) -> PlatformResult<Rect> {
-        Err(PlatformError::NotImplemented)
+        Ok(Rect::xywh(0.0, 0.0, 24.0, 24.0))
     }
 
     pub fn get_screen_id(&self) -> PlatformResult<i64> {
-        Err(PlatformError::NotImplemented)
+        Ok(MAIN_SCREEN_ID)
+    }
+
+    // Simulates user clicking the status item.
+    pub fn perform_action(&self, action: StatusItemActionType, position: Point) {
+        if let Some(delegate) = self.delegate.upgrade() {
+            delegate.borrow().on_action(self.handle, action, position);
+        }
     }
 }
 
 pub struct PlatformStatusItemManager {}
 
 impl PlatformStatusItemManager {
-    pub fn new(context: Context) -> Self {
+    pub fn new(_context: Context) -> Self {
         Self {}
     }
 
-    pub fn assign_weak_self(&self, weak: Weak<PlatformStatusItemManager>) {}
+    pub fn assign_weak_self(&self, _weak: Weak<PlatformStatusItemManager>) {}
 
     pub fn create_status_item(
         &self,
@@ -67,8 +91,15 @@ impl PlatformStatusItemManager {
         delegate: Weak<RefCell<dyn StatusItemDelegate>>,
         engine: EngineHandle,
     ) -> PlatformResult<Rc<PlatformStatusItem>> {
-        Err(PlatformError::NotImplemented)
+        Ok(Rc::new(PlatformStatusItem {
+            engine,
+            handle,
+            delegate,
+            image: RefCell::new(Vec::new()),
+            hint: RefCell::new(String::new()),
+            highlighted: Cell::new(false),
+        }))
     }
 
-    pub fn unregister_status_item(&self, item: &Rc<PlatformStatusItem>) {}
+    pub fn unregister_status_item(&self, _item: &Rc<PlatformStatusItem>) {}
 }
@@ -1,162 +1,620 @@
-use std::rc::{Rc, Weak};
+use std::{
+    cell::{Cell, RefCell},
+    rc::{Rc, Weak},
+};
 
 use crate::{
     codec::Value,
     shell::{
         api_model::{
-            DragEffect, DragRequest, PopupMenuRequest, PopupMenuResponse, WindowCollectionBehavior,
-            WindowGeometry, WindowGeometryFlags, WindowGeometryRequest, WindowStateFlags,
-            WindowStyle,
+            BoolTransition, DragEffect, DragRequest, PopupMenuRequest, PopupMenuResponse,
+            WindowCollectionBehavior, WindowGeometry, WindowGeometryFlags, WindowGeometryRequest,
+            WindowStateFlags, WindowStyle,
         },
-        Context, PlatformWindowDelegate,
+        Context, PlatformWindowDelegate, Point, Size,
     },
 };
 
-use super::{
-    engine::PlatformEngine,
-    error::{PlatformError, PlatformResult},
-    menu::PlatformMenu,
-};
+use super::{engine::PlatformEngine, error::PlatformResult, menu::PlatformMenu};
+
+// There is no native window, platform window is the in-memory window itself.
+pub type PlatformWindowType = Rc<PlatformWindow>;
 
-pub type PlatformWindowType = isize;
+#[derive(Clone, Debug, Default, PartialEq)]
+struct WindowState {
+    visible: bool,
+    minimized: bool,
+    maximized: bool,
+    full_screen: bool,
+    active: bool,
+}
 
-pub struct PlatformWindow {}
+// In-memory window. Frame and content are the same as there is no decoration.
+// Delegate is notified on next run loop turn, same as with real windows where
+// notifications come from the windowing system.
+pub struct PlatformWindow {
+    context: Context,
+    weak_self: RefCell<Weak<PlatformWindow>>,
+    delegate: Weak<dyn PlatformWindowDelegate>,
+    parent: Option<Rc<PlatformWindow>>,
+    ready_to_show: Cell<bool>,
+    show_when_ready: Cell<bool>,
+    closing: Cell<bool>,
+    origin: RefCell<Point>,
+    size: RefCell<Size>,
+    min_size: RefCell<Option<Size>>,
+    max_size: RefCell<Option<Size>>,
+    title: RefCell<String>,
+    style: RefCell<WindowStyle>,
+    collection_behavior: RefCell<WindowCollectionBehavior>,
+    state: RefCell<WindowState>,
+    window_menu: RefCell<Option<Rc<PlatformMenu>>>,
+    popup_menu: RefCell<
+        Option<(
+            Rc<PlatformMenu>,
+            Box<dyn FnOnce(PlatformResult<PopupMenuResponse>)>,
+        )>,
+    >,
+    modal_close_callback: RefCell<Option<Box<dyn FnOnce(PlatformResult<Value>)>>>,
+}
 
-#[allow(unused_variables)]
 impl PlatformWindow {
     pub fn new(
         context: Context,
         delegate: Weak<dyn PlatformWindowDelegate>,
         parent: Option<Rc<PlatformWindow>>,
     ) -> Self {
-        Self {}
+        Self {
+            context,
+            weak_self: RefCell::new(Weak::new()),
+            delegate,
+            parent,
+            ready_to_show: Cell::new(false),
+            show_when_ready: Cell::new(false),
+            closing: Cell::new(false),
+            origin: RefCell::new(Point::xy(0.0, 0.0)),
+            size: RefCell::new(Size::wh(800.0, 600.0)),
+            min_size: RefCell::new(None),
+            max_size: RefCell::new(None),
+            title: RefCell::new(String::new()),
+            style: RefCell::new(Default::default()),
+            collection_behavior: RefCell::new(Default::default()),
+            state: RefCell::new(Default::default()),
+            window_menu: RefCell::new(None),
+            popup_menu: RefCell::new(None),
+            modal_close_callback: RefCell::new(None),
+        }
     }
 
-    pub fn assign_weak_self(&self, weak: Weak<PlatformWindow>, engine: &PlatformEngine) {}
+    pub fn assign_weak_self(&self, weak: Weak<PlatformWindow>, _engine: &PlatformEngine) {
+        *self.weak_self.borrow_mut() = weak;
+    }
 
     pub fn get_platform_window(&self) -> PlatformWindowType {
-        Default::default()
+        self.weak_self.borrow().upgrade().unwrap()
+    }
+
+    fn notify<F>(&self, callback: F)
+    where
+        F: FnOnce(&dyn PlatformWindowDelegate) + 'static,
+    {
+        let delegate = self.delegate.clone();
+        if let Some(context) = self.context.get() {
+            context
+                .run_loop
+                .borrow()
+                .schedule_now(move || {
+                    if let Some(delegate) = delegate.upgrade() {
+                        callback(delegate.as_ref());
+                    }
+                })
+                .detach();
+        }
+    }
+
+    fn update_state<F: FnOnce(&mut WindowState)>(&self, f: F) {
+        let (previous, current) = {
+            let mut state = self.state.borrow_mut();
+            let previous = state.clone();
+            f(&mut state);
+            (previous, state.clone())
+        };
+        if previous.visible != current.visible {
+            let visible = current.visible;
+            self.notify(move |d| d.visibility_changed(visible));
+        }
+        if previous.minimized != current.minimized
+            || previous.maximized != current.maximized
+            || previous.full_screen != current.full_screen
+            || previous.active != current.active
+        {
+            self.notify(|d| d.state_flags_changed());
+        }
     }
 
     pub fn show(&self) -> PlatformResult<()> {
-        Err(PlatformError::NotImplemented)
+        if self.ready_to_show.get() {
+            self.update_state(|s| s.visible = true);
+        } else {
+            self.show_when_ready.set(true);
+        }
+        Ok(())
     }
 
     pub fn ready_to_show(&self) -> PlatformResult<()> {
-        Err(PlatformError::NotImplemented)
+        self.ready_to_show.set(true);
+        if self.show_when_ready.get() {
+            self.show()
+        } else {
+            Ok(())
+        }
     }
 
     pub fn close(&self) -> PlatformResult<()> {
-        Err(PlatformError::NotImplemented)
+        if self.closing.replace(true) {
+            return Ok(());
+        }
+        self.update_state(|s| {
+            s.visible = false;
+            s.active = false;
+        });
+        let callback = self.modal_close_callback.borrow_mut().take();
+        self.notify(move |d| {
+            if let Some(callback) = callback {
+                callback(Ok(Value::Null));
+            }
+            d.will_close();
+        });
+        Ok(())
     }
 
     pub fn close_with_result(&self, result: Value) -> PlatformResult<()> {
-        Err(PlatformError::NotImplemented)
+        let callback = self.modal_close_callback.borrow_mut().take();
+        if let Some(callback) = callback {
+            callback(Ok(result));
+        }
+        self.close()
+    }
+
+    // Simulates user pressing the close button.
+    pub fn request_close(&self) {
+        self.notify(|d| d.did_request_close());
     }
 
     pub fn hide(&self) -> PlatformResult<()> {
-        Err(PlatformError::NotImplemented)
+        if self.ready_to_show.get() {
+            self.update_state(|s| s.visible = false);
+        } else {
+            self.show_when_ready.set(false);
+        }
+        Ok(())
     }
 
-    pub fn activate(&self, activate_application: bool) -> PlatformResult<bool> {
-        Err(PlatformError::NotImplemented)
+    pub fn activate(&self, _activate_application: bool) -> PlatformResult<bool> {
+        self.update_state(|s| s.active = true);
+        Ok(true)
     }
 
-    pub fn deactivate(&self, deactivate_application: bool) -> PlatformResult<bool> {
-        Err(PlatformError::NotImplemented)
+    pub fn deactivate(&self, _deactivate_application: bool) -> PlatformResult<bool> {
+        self.update_state(|s| s.active = false);
+        Ok(true)
     }
 
     pub fn show_modal<F>(&self, done_callback: F)
     where
         F: FnOnce(PlatformResult<Value>) + 'static,
     {
-        done_callback(Err(PlatformError::NotImplemented))
+        self.modal_close_callback
+            .borrow_mut()
+            .replace(Box::new(done_callback));
+        self.show().ok();
     }
 
     pub fn set_geometry(
         &self,
         geometry: WindowGeometryRequest,
     ) -> PlatformResult<WindowGeometryFlags> {
-        Err(PlatformError::NotImplemented)
+        let geometry = geometry.filtered_by_preference();
+        let previous = self.get_geometry()?;
+
+        let res = WindowGeometryFlags {
+            frame_origin: geometry.frame_origin.is_some(),
+            frame_size: geometry.frame_size.is_some(),
+            content_origin: geometry.content_origin.is_some(),
+            content_size: geometry.content_size.is_some(),
+            min_frame_size: geometry.min_frame_size.is_some(),
+            max_frame_size: geometry.max_frame_size.is_some(),
+            min_content_size: geometry.min_content_size.is_some(),
+            max_content_size: geometry.max_content_size.is_some(),
+        };
+
+        let origin = geometry.frame_origin.or(geometry.content_origin);
+        let size = geometry.frame_size.or(geometry.content_size);
+        let min_size = geometry.min_frame_size.or(geometry.min_content_size);
+        let max_size = geometry.max_frame_size.or(geometry.max_content_size);
+
+        if let Some(origin) = origin {
+            *self.origin.borrow_mut() = origin;
+        }
+        if min_size.is_some() {
+            *self.min_size.borrow_mut() = min_size;
+        }
+        if max_size.is_some() {
+            *self.max_size.borrow_mut() = max_size;
+        }
+        let mut size = size.unwrap_or_else(|| self.size.borrow().clone());
+        if let Some(min_size) = self.min_size.borrow().as_ref() {
+            size.width = size.width.max(min_size.width);
+            size.height = size.height.max(min_size.height);
+        }
+        if let Some(max_size) = self.max_size.borrow().as_ref() {
+            size.width = size.width.min(max_size.width);
+            size.height = size.height.min(max_size.height);
+        }
+        *self.size.borrow_mut() = size;
+
+        let current = self.get_geometry()?;
+        if previous.frame_origin != current.frame_origin
+            || previous.frame_size != current.frame_size
+        {
+            self.notify(|d| d.geometry_changed());
+        }
+        Ok(res)
     }
 
     pub fn get_geometry(&self) -> PlatformResult<WindowGeometry> {
-        Err(PlatformError::NotImplemented)
+        let origin = self.origin.borrow().clone();
+        let size = self.size.borrow().clone();
+        let min_size = self.min_size.borrow().clone();
+        let max_size = self.max_size.borrow().clone();
+        Ok(WindowGeometry {
+            frame_origin: Some(origin.clone()),
+            frame_size: Some(size.clone()),
+            content_origin: Some(origin),
+            content_size: Some(size),
+            min_frame_size: min_size.clone(),
+            max_frame_size: max_size.clone(),
+            min_content_size: min_size,
+            max_content_size: max_size,
+        })
     }
 
     pub fn supported_geometry(&self) -> PlatformResult<WindowGeometryFlags> {
-        Err(PlatformError::NotImplemented)
+        Ok(WindowGeometryFlags {
+            frame_origin: true,
+            frame_size: true,
+            content_origin: true,
+            content_size: true,
+            min_frame_size: true,
+            max_frame_size: true,
+            min_content_size: true,
+            max_content_size: true,
+        })
     }
 
     pub fn get_screen_id(&self) -> PlatformResult<i64> {
-        Err(PlatformError::NotImplemented)
+        Ok(super::screen_manager::MAIN_SCREEN_ID)
     }
 
     pub fn set_title(&self, title: String) -> PlatformResult<()> {
-        Err(PlatformError::NotImplemented)
+        *self.title.borrow_mut() = title;
+        Ok(())
+    }
+
+    pub fn title(&self) -> String {
+        self.title.borrow().clone()
     }
 
     pub fn set_collection_behavior(
         &self,
         behavior: WindowCollectionBehavior,
     ) -> PlatformResult<()> {
-        Err(PlatformError::NotImplemented)
+        *self.collection_behavior.borrow_mut() = behavior;
+        Ok(())
     }
 
     pub fn set_minimized(&self, minimized: bool) -> PlatformResult<()> {
-        Err(PlatformError::NotImplemented)
+        self.update_state(|s| s.minimized = minimized);
+        Ok(())
     }
 
     pub fn set_maximized(&self, maximized: bool) -> PlatformResult<()> {
-        Err(PlatformError::NotImplemented)
+        self.update_state(|s| s.maximized = maximized);
+        Ok(())
     }
 
     pub fn set_full_screen(&self, full_screen: bool) -> PlatformResult<()> {
-        Err(PlatformError::NotImplemented)
+        self.update_state(|s| s.full_screen = full_screen);
+        Ok(())
     }
 
     pub fn get_window_state_flags(&self) -> PlatformResult<WindowStateFlags> {
-        Err(PlatformError::NotImplemented)
+        let state = self.state.borrow();
+        let transition = |v: bool| {
+            if v {
+                BoolTransition::Yes
+            } else {
+                BoolTransition::No
+            }
+        };
+        Ok(WindowStateFlags {
+            maximized: transition(state.maximized),
+            minimized: transition(state.minimized),
+            full_screen: transition(state.full_screen),
+            active: state.active,
+        })
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.state.borrow().visible
     }
 
     pub fn save_position_to_string(&self) -> PlatformResult<String> {
-        Ok(String::new())
+        let origin = self.origin.borrow();
+        let size = self.size.borrow();
+        Ok(format!(
+            "{},{},{},{}",
+            origin.x, origin.y, size.width, size.height
+        ))
     }
 
     pub fn restore_position_from_string(&self, position: String) -> PlatformResult<()> {
+        let values: Vec<f64> = position.split(',').filter_map(|v| v.parse().ok()).collect();
+        if let [x, y, width, height] = values[..] {
+            *self.origin.borrow_mut() = Point::xy(x, y);
+            *self.size.borrow_mut() = Size::wh(width, height);
+            self.notify(|d| d.geometry_changed());
+        }
         Ok(())
     }
 
     pub fn set_style(&self, style: WindowStyle) -> PlatformResult<()> {
-        Err(PlatformError::NotImplemented)
+        *self.style.borrow_mut() = style;
+        Ok(())
+    }
+
+    pub fn style(&self) -> WindowStyle {
+        self.style.borrow().clone()
     }
 
     pub fn perform_window_drag(&self) -> PlatformResult<()> {
-        Err(PlatformError::NotImplemented)
+        Ok(())
     }
 
-    pub fn begin_drag_session(&self, request: DragRequest) -> PlatformResult<()> {
-        Err(PlatformError::NotImplemented)
+    // There is nothing to drag onto, so the session ends right away.
+    pub fn begin_drag_session(&self, _request: DragRequest) -> PlatformResult<()> {
+        self.notify(|d| d.drag_ended(DragEffect::None));
+        Ok(())
     }
 
-    pub fn set_pending_effect(&self, effect: DragEffect) {}
+    pub fn set_pending_effect(&self, _effect: DragEffect) {}
 
-    pub fn show_popup_menu<F>(&self, menu: Rc<PlatformMenu>, request: PopupMenuRequest, on_done: F)
+    // Menu stays open until hidden or until item is selected with
+    // PlatformMenu::perform_action.
+    pub fn show_popup_menu<F>(&self, menu: Rc<PlatformMenu>, _request: PopupMenuRequest, on_done: F)
     where
         F: FnOnce(PlatformResult<PopupMenuResponse>) + 'static,
     {
-        on_done(Err(PlatformError::NotImplemented))
+        let previous = self
+            .popup_menu
+            .borrow_mut()
+            .replace((menu.clone(), Box::new(on_done)));
+        if let Some((_, on_done)) = previous {
+            on_done(Ok(PopupMenuResponse {
+                item_selected: false,
+            }));
+        }
+        let weak_self = self.weak_self.borrow().clone();
+        menu.on_action(move || {
+            if let Some(window) = weak_self.upgrade() {
+                window.finish_popup_menu(true);
+            }
+        });
+    }
+
+    fn finish_popup_menu(&self, item_selected: bool) {
+        let popup_menu = self.popup_menu.borrow_mut().take();
+        if let Some((_, on_done)) = popup_menu {
+            on_done(Ok(PopupMenuResponse { item_selected }));
+        }
     }
 
     pub fn hide_popup_menu(&self, menu: Rc<PlatformMenu>) -> PlatformResult<()> {
-        Err(PlatformError::NotImplemented)
+        let current = self.popup_menu.borrow().as_ref().map(|m| m.0.clone());
+        if let Some(current) = current {
+            if Rc::ptr_eq(&current, &menu) {
+                self.finish_popup_menu(false);
+            }
+        }
+        Ok(())
     }
 
     pub fn show_system_menu(&self) -> PlatformResult<()> {
-        Err(PlatformError::NotImplemented)
+        Ok(())
     }
 
     pub fn set_window_menu(&self, menu: Option<Rc<PlatformMenu>>) -> PlatformResult<()> {
-        Err(PlatformError::NotImplemented)
+        *self.window_menu.borrow_mut() = menu;
+        Ok(())
+    }
+
+    pub fn window_menu(&self) -> Option<Rc<PlatformMenu>> {
+        self.window_menu.borrow().clone()
+    }
+
+    pub fn parent(&self) -> Option<Rc<PlatformWindow>> {
+        self.parent.clone()
+    }
+}
+
+#[cfg(all(test, feature = "null-backend"))]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use velcro::hash_map;
+
+    use crate::{
+        codec::Value,
+        shell::{
+            api_constants::{channel, method},
+            api_model::{
+                GeometryPreference, PopupMenuRequest, WindowGeometry, WindowGeometryRequest,
+            },
+            Context, ContextOptions, FakeDart, MenuDelegate, MenuHandle, Point, Size,
+        },
+    };
+
+    #[test]
+    fn test_window() {
+        let context = Context::new(ContextOptions::default()).unwrap();
+        let handle = context
+            .window_manager
+            .borrow_mut()
+            .create_window(Value::Null, None)
+            .unwrap();
+        let engine = context
+            .window_manager
+            .borrow()
+            .get_engine_for_window(handle)
+            .unwrap();
+        let dart = FakeDart::for_engine(&context, engine);
+        let window = context
+            .window_manager
+            .borrow()
+            .get_platform_window(handle)
+            .unwrap();
+
+        window.show().unwrap();
+        assert!(!window.is_visible());
+        window.ready_to_show().unwrap();
+        assert!(window.is_visible());
+
+        window.set_title("Title".into()).unwrap();
+        assert_eq!(window.title(), "Title");
+
+        window
+            .set_geometry(WindowGeometryRequest {
+                geometry: WindowGeometry {
+                    content_size: Some(Size::wh(100.0, 50.0)),
+                    min_content_size: Some(Size::wh(200.0, 20.0)),
+                    ..Default::default()
+                },
+                preference: GeometryPreference::PreferContent,
+            })
+            .unwrap();
+        let geometry = window.get_geometry().unwrap();
+        assert_eq!(geometry.frame_size, Some(Size::wh(200.0, 50.0)));
+
+        window.set_maximized(true).unwrap();
+        assert!(window.get_window_state_flags().unwrap().is_maximized());
+
+        window.close().unwrap();
+        drop(window);
+        dart.pump();
+        assert!(context
+            .window_manager
+            .borrow()
+            .get_platform_window(handle)
+            .is_none());
+    }
+
+    #[test]
+    fn test_popup_menu() {
+        let context = Context::new(ContextOptions::default()).unwrap();
+        let handle = context
+            .window_manager
+            .borrow_mut()
+            .create_window(Value::Null, None)
+            .unwrap();
+        let engine = context
+            .window_manager
+            .borrow()
+            .get_engine_for_window(handle)
+            .unwrap();
+        let dart = FakeDart::for_engine(&context, engine);
+        let window = context
+            .window_manager
+            .borrow()
+            .get_platform_window(handle)
+            .unwrap();
+
+        let item = |id: i64, submenu: Option<i64>| {
+            Value::Map(hash_map! {
+                "id".into(): Value::I64(id),
+                "title".into(): Value::String(format!("Item {}", id)),
+                "enabled".into(): Value::Bool(true),
+                "separator".into(): Value::Bool(false),
+                "checkStatus".into(): Value::String("none".into()),
+                "submenu".into(): submenu.map(Value::I64).unwrap_or(Value::Null),
+            })
+        };
+        let create_menu = |items: Vec<Value>| {
+            let args = Value::Map(hash_map! {
+                "menu".into(): Value::Map(hash_map! {
+                    "items".into(): Value::List(items),
+                }),
+            });
+            match dart.invoke_method(channel::MENU_MANAGER, method::menu::CREATE_OR_UPDATE, args) {
+                Some(Ok(Value::I64(handle))) => handle,
+                res => panic!("unexpected result {:?}", res),
+            }
+        };
+        let submenu = create_menu(vec![item(2, None)]);
+        let menu = create_menu(vec![item(1, None), item(3, Some(submenu))]);
+        let menu = context
+            .menu_manager
+            .borrow()
+            .borrow()
+            .get_platform_menu(MenuHandle(menu))
+            .unwrap();
+
+        let show_popup_menu = || {
+            let response = Rc::new(RefCell::new(None));
+            let response_clone = response.clone();
+            window.show_popup_menu(
+                menu.clone(),
+                PopupMenuRequest {
+                    handle: menu.handle(),
+                    position: Point::xy(0.0, 0.0),
+                    tracking_rect: None,
+                    item_rect: None,
+                    preselect_first: false,
+                },
+                move |res| {
+                    response_clone.replace(Some(res.unwrap().item_selected));
+                },
+            );
+            response
+        };
+
+        // Item in submenu is reported with submenu handle and closes the popup
+        let response = show_popup_menu();
+        assert_eq!(*response.borrow(), None);
+        menu.perform_action(2).unwrap();
+        assert_eq!(*response.borrow(), Some(true));
+        dart.pump();
+        let calls = dart.take_method_calls(channel::MENU_MANAGER);
+        let action = calls
+            .iter()
+            .find(|call| call.method == method::menu::ON_ACTION)
+            .unwrap();
+        assert_eq!(
+            action.args,
+            Value::Map(hash_map! {
+                "handle".into(): Value::I64(submenu),
+                "id".into(): Value::I64(2),
+            })
+        );
+
+        // Unknown item leaves the popup open until it is hidden
+        let response = show_popup_menu();
+        assert!(menu.perform_action(4).is_err());
+        assert_eq!(*response.borrow(), None);
+        window.hide_popup_menu(menu.clone()).unwrap();
+        assert_eq!(*response.borrow(), Some(false));
+
+        window.close().unwrap();
+        drop(window);
+        dart.pump();
     }
 }
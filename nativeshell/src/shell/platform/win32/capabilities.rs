@@ -0,0 +1,16 @@
+use crate::shell::api_model::Capabilities;
+
+pub fn get_capabilities() -> Capabilities {
+    Capabilities {
+        hot_keys: true,
+        status_items: true,
+        status_item_highlight: false,
+        app_menu: false,
+        window_menu: false,
+        system_menu: true,
+        collection_behavior: false,
+        full_screen: false,
+        window_positioning: true,
+        fd_watch: false,
+    }
+}
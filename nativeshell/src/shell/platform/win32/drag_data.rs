@@ -143,7 +143,7 @@ impl DragDataAdapter for FallThroughDragDataAdapter {
 
         let data = DataUtil::get_data(data, self.format);
         if let Ok(data) = data {
-            let value = codec.decode_message(&data).unwrap();
+            let value = codec.decode_message(&data).unwrap_or_default();
             if let Value::Map(value) = value {
                 for entry in value {
                     if let Value::String(key) = entry.0 {
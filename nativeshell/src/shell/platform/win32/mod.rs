@@ -1,5 +1,6 @@
 pub mod app_delegate;
 pub mod binary_messenger;
+pub mod capabilities;
 pub mod display;
 pub mod dpi;
 pub mod drag_com;
@@ -1,27 +1,36 @@
 use std::{
-    cell::{RefCell, UnsafeCell},
+    any::Any,
+    cell::{Cell, RefCell, UnsafeCell},
     future::Future,
     marker::PhantomData,
+    panic::{catch_unwind, AssertUnwindSafe},
     rc::Rc,
     sync::Arc,
     task::Poll,
     time::Duration,
 };
 
+#[cfg(fd_watch)]
+use std::{os::unix::io::RawFd, process::Command};
+
 use futures::{
     future::LocalBoxFuture,
     task::{waker_ref, ArcWake},
     FutureExt,
 };
 
+#[cfg(fd_watch)]
+use super::{fd_watch::watch_fd_on, ChildProcess, FdCondition, FdReady};
 use super::{
     platform::run_loop::{PlatformRunLoop, PlatformRunLoopSender},
-    Context, ContextRef, Handle,
+    Context, ContextRef, Handle, Interval, Sleep,
 };
 
 pub struct RunLoop {
     pub(super) platform_run_loop: Rc<PlatformRunLoop>,
     context: Context,
+    #[cfg(feature = "tokio")]
+    tokio_runtime: tokio::runtime::Runtime,
 }
 
 impl RunLoop {
@@ -29,6 +38,11 @@ impl RunLoop {
         Self {
             platform_run_loop: Rc::new(PlatformRunLoop::new()),
             context: context.weak(),
+            #[cfg(feature = "tokio")]
+            tokio_runtime: tokio::runtime::Builder::new_multi_thread()
+                .enable_all()
+                .build()
+                .expect("Failed to create tokio runtime"),
         }
     }
 
@@ -37,11 +51,7 @@ impl RunLoop {
     where
         F: FnOnce() + 'static,
     {
-        let run_loop = self.platform_run_loop.clone();
-        let handle = run_loop.schedule(in_time, callback);
-        Handle::new(move || {
-            run_loop.unschedule(handle);
-        })
+        schedule_on(&self.platform_run_loop, in_time, callback)
     }
 
     // Convenience method to schedule callback on next run loop turn
@@ -53,9 +63,50 @@ impl RunLoop {
         self.schedule(Duration::from_secs(0), callback)
     }
 
+    // Future that completes after given duration
+    pub fn sleep(&self, duration: Duration) -> Sleep {
+        Sleep::new(&self.platform_run_loop, duration)
+    }
+
+    // Stream that yields every period; Period must be non-zero.
+    pub fn interval(&self, period: Duration) -> Interval {
+        Interval::new(&self.platform_run_loop, period)
+    }
+
+    // Invokes callback on run loop thread whenever the descriptor is ready;
+    // Returning false from callback stops watching, as does dropping the handle.
+    #[cfg(fd_watch)]
+    pub fn watch_fd<F>(
+        &self,
+        fd: RawFd,
+        condition: FdCondition,
+        callback: F,
+    ) -> crate::Result<Handle>
+    where
+        F: FnMut(FdCondition) -> bool + 'static,
+    {
+        watch_fd_on(&self.platform_run_loop, fd, condition, callback)
+    }
+
+    // Future that completes once the descriptor is ready
+    #[cfg(fd_watch)]
+    pub fn fd_ready(&self, fd: RawFd, condition: FdCondition) -> crate::Result<FdReady> {
+        FdReady::new(&self.platform_run_loop, fd, condition)
+    }
+
+    // Spawns the command with stdout and stderr piped; Output lines and exit
+    // status are streamed on run loop thread.
+    #[cfg(fd_watch)]
+    pub fn spawn_process(&self, command: Command) -> crate::Result<ChildProcess> {
+        ChildProcess::spawn(&self.platform_run_loop, command)
+    }
+
     pub fn run(&self) {
         // set context as current
         let _handle = self.context.get().unwrap().set_as_current();
+        // allow tokio::spawn and tokio resources to be used from run loop thread
+        #[cfg(feature = "tokio")]
+        let _guard = self.tokio_runtime.enter();
         self.platform_run_loop.run()
     }
 
@@ -74,9 +125,11 @@ impl RunLoop {
         let future = future.boxed_local();
         let task = Arc::new(Task {
             sender: self.new_sender(),
-            future: UnsafeCell::new(future),
+            future: UnsafeCell::new(Some(future)),
             value: RefCell::new(None),
             waker: RefCell::new(None),
+            finished: Cell::new(false),
+            polling: Cell::new(false),
         });
         ArcWake::wake_by_ref(&task);
         JoinHandle {
@@ -84,6 +137,48 @@ impl RunLoop {
             _data: PhantomData {},
         }
     }
+
+    // Spawns the future on tokio runtime (worker threads). Returned handle can be
+    // awaited from futures running on run loop (i.e. AsyncMethodCallHandler), in
+    // which case the result is delivered back on run loop thread.
+    #[cfg(feature = "tokio")]
+    pub fn spawn_tokio<F>(&self, future: F) -> tokio::task::JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.tokio_runtime.spawn(future)
+    }
+
+    // Runs blocking callback on tokio blocking thread pool.
+    #[cfg(feature = "tokio")]
+    pub fn spawn_blocking<F, R>(&self, callback: F) -> tokio::task::JoinHandle<R>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        self.tokio_runtime.spawn_blocking(callback)
+    }
+
+    #[cfg(feature = "tokio")]
+    pub fn tokio_handle(&self) -> tokio::runtime::Handle {
+        self.tokio_runtime.handle().clone()
+    }
+}
+
+pub(super) fn schedule_on<F>(
+    platform_run_loop: &Rc<PlatformRunLoop>,
+    in_time: Duration,
+    callback: F,
+) -> Handle
+where
+    F: FnOnce() + 'static,
+{
+    let run_loop = platform_run_loop.clone();
+    let handle = run_loop.schedule(in_time, callback);
+    Handle::new(move || {
+        run_loop.unschedule(handle);
+    })
 }
 
 // Can be used to send callbacks from other threads to be executed on run loop thread
@@ -107,9 +202,12 @@ impl RunLoopSender {
 
 struct Task<T> {
     sender: RunLoopSender,
-    future: UnsafeCell<LocalBoxFuture<'static, T>>,
-    value: RefCell<Option<T>>,
+    // None after task is finished or aborted
+    future: UnsafeCell<Option<LocalBoxFuture<'static, T>>>,
+    value: RefCell<Option<Result<T, JoinError>>>,
     waker: RefCell<Option<std::task::Waker>>,
+    finished: Cell<bool>,
+    polling: Cell<bool>,
 }
 
 // Tasks can only be spawned on run loop thread and will only be executed
@@ -118,13 +216,68 @@ unsafe impl<T> Send for Task<T> {}
 unsafe impl<T> Sync for Task<T> {}
 
 impl<T: 'static> Task<T> {
-    fn poll(self: &std::sync::Arc<Self>) -> Poll<T> {
+    fn run(self: &std::sync::Arc<Self>) {
+        if self.finished.get() {
+            return;
+        }
+        match self.poll() {
+            Some(result) => self.finish(result),
+            // aborted while being polled
+            None if self.finished.get() => self.drop_future(),
+            None => {}
+        }
+    }
+
+    // Returns result when the future completes or panics.
+    fn poll(self: &std::sync::Arc<Self>) -> Option<Result<T, JoinError>> {
         let waker = waker_ref(self).clone();
         let context = &mut core::task::Context::from_waker(&waker);
-        unsafe {
-            let future = &mut *self.future.get();
-            future.as_mut().poll(context)
+        let future = unsafe { &mut *self.future.get() }.as_mut()?;
+        self.polling.set(true);
+        let res = catch_unwind(AssertUnwindSafe(|| future.as_mut().poll(context)));
+        self.polling.set(false);
+        if self.finished.get() {
+            return None;
         }
+        match res {
+            Ok(Poll::Ready(value)) => Some(Ok(value)),
+            Ok(Poll::Pending) => None,
+            Err(panic) => Some(Err(JoinError::Panicked(panic_message(&*panic)))),
+        }
+    }
+
+    fn finish(&self, result: Result<T, JoinError>) {
+        self.finished.set(true);
+        self.value.borrow_mut().replace(result);
+        if !self.polling.get() {
+            self.drop_future();
+        }
+        let waker = self.waker.borrow_mut().take();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    fn abort(&self) {
+        if !self.finished.get() {
+            self.finish(Err(JoinError::Aborted));
+        }
+    }
+
+    fn drop_future(&self) {
+        // Dropping the future may run arbitrary code, don't keep it borrowed
+        let future = unsafe { &mut *self.future.get() }.take();
+        drop(future);
+    }
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        (*message).into()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Unknown panic".into()
     }
 }
 
@@ -133,20 +286,30 @@ impl<T: 'static> ArcWake for Task<T> {
         let arc_self = arc_self.clone();
         let sender = arc_self.sender.clone();
         sender.send(move || {
-            if arc_self.value.borrow().is_none() {
-                if let Poll::Ready(value) = arc_self.poll() {
-                    *arc_self.value.borrow_mut() = Some(value);
-                }
-            }
-            if arc_self.value.borrow().is_some() {
-                if let Some(waker) = arc_self.waker.borrow_mut().take() {
-                    waker.wake();
-                }
-            }
+            arc_self.run();
         });
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JoinError {
+    Aborted,
+    Panicked(String),
+}
+
+impl std::fmt::Display for JoinError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JoinError::Aborted => write!(f, "Task was aborted"),
+            JoinError::Panicked(message) => write!(f, "Task panicked: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for JoinError {}
+
+// Dropping the JoinHandle detaches the task; Use abort_handle() to get a guard
+// that aborts the task when dropped.
 pub struct JoinHandle<T> {
     task: Arc<Task<T>>,
     // Task has unsafe `Send` and `Sync`, but that is only because we know
@@ -155,20 +318,120 @@ pub struct JoinHandle<T> {
     _data: PhantomData<*const ()>,
 }
 
+impl<T: 'static> JoinHandle<T> {
+    // Drops the future without polling it again; Awaiting the handle results
+    // in JoinError::Aborted unless the task has already finished.
+    pub fn abort(&self) {
+        self.task.abort();
+    }
+
+    // Returns whether the task has completed, panicked or was aborted.
+    pub fn is_finished(&self) -> bool {
+        self.task.finished.get()
+    }
+
+    // Returns handle that aborts the task when dropped or cancelled.
+    pub fn abort_handle(&self) -> Handle {
+        let task = Arc::downgrade(&self.task);
+        Handle::new(move || {
+            if let Some(task) = task.upgrade() {
+                task.abort();
+            }
+        })
+    }
+}
+
 impl<T: 'static> Future for JoinHandle<T> {
-    type Output = T;
+    type Output = Result<T, JoinError>;
 
     fn poll(self: std::pin::Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Self::Output> {
         let value = self.task.value.borrow_mut().take();
         match value {
             Some(value) => Poll::Ready(value),
             None => {
-                self.task
-                    .waker
-                    .borrow_mut()
-                    .get_or_insert_with(|| cx.waker().clone());
+                self.task.waker.borrow_mut().replace(cx.waker().clone());
                 Poll::Pending
             }
         }
     }
 }
+
+#[cfg(all(test, feature = "null-backend"))]
+mod tests {
+    use std::{cell::RefCell, rc::Rc, time::Duration};
+
+    use super::JoinError;
+    use crate::shell::{Context, ContextOptions, FakeDart};
+
+    #[test]
+    fn test_abort_and_panic() {
+        let context = Context::new(ContextOptions::default()).unwrap();
+        let dart = FakeDart::new(&context).unwrap();
+        let result = Rc::new(RefCell::new(Vec::new()));
+
+        let run_loop = context.run_loop.borrow();
+        let sleeping = run_loop.spawn(run_loop.sleep(Duration::from_secs(60)));
+        let panicking = run_loop.spawn(async {
+            panic!("task failed");
+        });
+        let guarded = run_loop.spawn(run_loop.sleep(Duration::from_secs(60)));
+        let guard = guarded.abort_handle();
+        drop(run_loop);
+
+        dart.pump();
+        assert!(!sleeping.is_finished());
+        assert!(panicking.is_finished());
+        sleeping.abort();
+        assert!(sleeping.is_finished());
+        drop(guard);
+        assert!(guarded.is_finished());
+
+        let result_clone = result.clone();
+        context.run_loop.borrow().spawn(async move {
+            result_clone.borrow_mut().push(sleeping.await);
+            result_clone.borrow_mut().push(panicking.await);
+            result_clone.borrow_mut().push(guarded.await);
+        });
+        dart.pump();
+        assert_eq!(
+            *result.borrow(),
+            vec![
+                Err(JoinError::Aborted),
+                Err(JoinError::Panicked("task failed".into())),
+                Err(JoinError::Aborted),
+            ]
+        );
+
+        dart.shut_down().unwrap();
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn test_tokio_bridge() {
+        let context = Context::new(ContextOptions::default()).unwrap();
+        let dart = FakeDart::new(&context).unwrap();
+        let result = Rc::new(RefCell::new(Vec::new()));
+
+        let run_loop = context.run_loop.borrow();
+        let computed = run_loop.spawn_tokio(async { 6 * 7 });
+        let blocking = run_loop.spawn_blocking(|| std::thread::current().id());
+        let result_clone = result.clone();
+        run_loop.spawn(async move {
+            result_clone.borrow_mut().push(computed.await.unwrap());
+            let thread = blocking.await.unwrap();
+            assert_ne!(thread, std::thread::current().id());
+            result_clone.borrow_mut().push(0);
+        });
+        drop(run_loop);
+
+        for _ in 0..100 {
+            if result.borrow().len() == 2 {
+                break;
+            }
+            dart.pump_for(Duration::from_millis(10));
+        }
+        assert_eq!(*result.borrow(), vec![42, 0]);
+
+        dart.shut_down().unwrap();
+    }
+}
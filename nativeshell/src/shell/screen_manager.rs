@@ -1,18 +1,20 @@
-use std::{cell::RefCell, collections::HashSet, rc::Weak};
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    rc::{Rc, Weak},
+};
 
 use crate::{
-    codec::{
-        value::{from_value, to_value},
-        MethodCallResult, Value,
-    },
+    codec::Value,
     util::{Late, OkLog},
-    Context, Error, Result,
+    Context, Error,
 };
 
 use super::{
     api_constants::{channel, method},
     platform::screen_manager::PlatformScreenManager,
     EngineHandle, MethodCallHandler, MethodInvokerProvider, RegisteredMethodCallHandler,
+    TypedMethodChannel,
 };
 
 pub trait ScreenManagerDelegate {
@@ -23,6 +25,7 @@ pub struct ScreenManager {
     platform_manager: Late<PlatformScreenManager>,
     invoker_provider: Late<MethodInvokerProvider>,
     engines: HashSet<EngineHandle>,
+    methods: Rc<TypedMethodChannel<Self>>,
 }
 
 impl ScreenManager {
@@ -31,15 +34,29 @@ impl ScreenManager {
             platform_manager: Late::new(),
             invoker_provider: Late::new(),
             engines: HashSet::new(),
+            methods: Rc::new(Self::methods()),
         }
         .register(context, channel::SCREEN_MANAGER)
     }
 
-    fn map_result<T>(result: Result<T>) -> MethodCallResult<Value>
-    where
-        T: serde::Serialize,
-    {
-        result.map(|v| to_value(v).unwrap()).map_err(|e| e.into())
+    fn methods() -> TypedMethodChannel<Self> {
+        TypedMethodChannel::<Self>::new()
+            .method(method::screen_manager::GET_SCREENS, |s, _: Value, _| {
+                s.platform_manager.get_screens().map_err(Error::from)
+            })
+            .method(method::screen_manager::GET_MAIN_SCREEN, |s, _: Value, _| {
+                s.platform_manager.get_main_screen().map_err(Error::from)
+            })
+            .method(method::screen_manager::LOGICAL_TO_SYSTEM, |s, offset, _| {
+                s.platform_manager
+                    .logical_to_system(offset)
+                    .map_err(Error::from)
+            })
+            .method(method::screen_manager::SYSTEM_TO_LOGICAL, |s, offset, _| {
+                s.platform_manager
+                    .system_to_logical(offset)
+                    .map_err(Error::from)
+            })
     }
 }
 
@@ -51,29 +68,7 @@ impl MethodCallHandler for ScreenManager {
         engine: super::EngineHandle,
     ) {
         self.engines.insert(engine);
-        match call.method.as_str() {
-            method::screen_manager::GET_SCREENS => {
-                let screens = self.platform_manager.get_screens();
-                reply.send(Self::map_result(screens.map_err(Error::from)));
-            }
-            method::screen_manager::GET_MAIN_SCREEN => {
-                let id = self.platform_manager.get_main_screen();
-                reply.send(Self::map_result(id.map_err(Error::from)));
-            }
-            method::screen_manager::LOGICAL_TO_SYSTEM => {
-                let offset = self
-                    .platform_manager
-                    .logical_to_system(from_value(&call.args).unwrap());
-                reply.send(Self::map_result(offset.map_err(Error::from)));
-            }
-            method::screen_manager::SYSTEM_TO_LOGICAL => {
-                let offset = self
-                    .platform_manager
-                    .system_to_logical(from_value(&call.args).unwrap());
-                reply.send(Self::map_result(offset.map_err(Error::from)));
-            }
-            _ => {}
-        }
+        self.methods.clone().dispatch(self, call, reply, engine);
     }
 
     fn on_engine_destroyed(&mut self, engine: EngineHandle) {
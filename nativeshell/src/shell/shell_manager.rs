@@ -0,0 +1,134 @@
+use std::{
+    cell::RefCell,
+    rc::{Rc, Weak},
+};
+
+use crate::{codec::Value, Context};
+
+use super::{
+    api_constants::{api_feature, channel, method},
+    api_model::Capabilities,
+    platform::capabilities,
+    EngineHandle, MethodCallHandler, RegisteredMethodCallHandler, TypedMethodChannel,
+};
+
+// Returns features supported by current platform backend.
+pub fn get_capabilities() -> Capabilities {
+    capabilities::get_capabilities()
+}
+
+pub struct ShellManager {
+    context: Context,
+    methods: Rc<TypedMethodChannel<Self>>,
+}
+
+impl ShellManager {
+    pub(super) fn new(context: Context) -> RegisteredMethodCallHandler<Self> {
+        Self {
+            context: context.clone(),
+            methods: Rc::new(Self::methods()),
+        }
+        .register(context, channel::SHELL)
+    }
+
+    fn methods() -> TypedMethodChannel<Self> {
+        TypedMethodChannel::<Self>::new().method_with_reply(
+            method::shell::GET_CAPABILITIES,
+            |this, _: Value, reply, engine| {
+                if this.is_feature_enabled(engine, api_feature::CAPABILITIES) {
+                    reply.send_ok(get_capabilities());
+                } else {
+                    reply.send_error(
+                        "feature-not-negotiated",
+                        Some("capabilities feature was not negotiated"),
+                        Value::Null,
+                    );
+                }
+            },
+        )
+    }
+
+    fn is_feature_enabled(&self, engine: EngineHandle, feature: &str) -> bool {
+        self.context
+            .get()
+            .map(|context| {
+                context
+                    .engine_manager
+                    .borrow()
+                    .is_api_feature_enabled(engine, feature)
+            })
+            .unwrap_or(false)
+    }
+}
+
+impl MethodCallHandler for ShellManager {
+    fn on_method_call(
+        &mut self,
+        call: crate::codec::MethodCall<Value>,
+        reply: crate::codec::MethodCallReply<Value>,
+        engine: EngineHandle,
+    ) {
+        self.methods.clone().dispatch(self, call, reply, engine);
+    }
+
+    fn assign_weak_self(&mut self, _weak_self: Weak<RefCell<Self>>) {}
+}
+
+#[cfg(all(test, feature = "null-backend"))]
+mod tests {
+    use crate::{
+        codec::Value,
+        shell::{
+            api_constants::{api_feature, channel, method, CURRENT_API_VERSION},
+            api_model::ApiNegotiationResponse,
+            Context, ContextOptions, FakeDart,
+        },
+    };
+
+    #[test]
+    fn test_get_capabilities() {
+        let context = Context::new(ContextOptions::default()).unwrap();
+        let dart = FakeDart::new(&context).unwrap();
+        context.engine_manager.borrow_mut().set_negotiated_api(
+            dart.engine(),
+            ApiNegotiationResponse {
+                version: CURRENT_API_VERSION,
+                features: vec![api_feature::CAPABILITIES.into()],
+            },
+        );
+
+        let res = dart.invoke_method(channel::SHELL, method::shell::GET_CAPABILITIES, Value::Null);
+        let map = match res {
+            Some(Ok(Value::Map(map))) => map,
+            res => panic!("unexpected reply {:?}", res),
+        };
+        let mut keys: Vec<_> = map
+            .iter()
+            .map(|(key, value)| {
+                assert_eq!(value, &Value::Bool(true));
+                match key {
+                    Value::String(key) => key.as_str(),
+                    key => panic!("unexpected key {:?}", key),
+                }
+            })
+            .collect();
+        keys.sort_unstable();
+        assert_eq!(
+            keys,
+            vec![
+                "appMenu",
+                "collectionBehavior",
+                "fdWatch",
+                "fullScreen",
+                "hotKeys",
+                "statusItemHighlight",
+                "statusItems",
+                "systemMenu",
+                "windowMenu",
+                "windowPositioning",
+            ]
+        );
+
+        dart.shut_down().unwrap();
+    }
+}
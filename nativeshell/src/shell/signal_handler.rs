@@ -0,0 +1,149 @@
+use std::{
+    io::ErrorKind,
+    mem::MaybeUninit,
+    os::unix::io::RawFd,
+    sync::atomic::{AtomicI32, Ordering},
+};
+
+use log::error;
+
+use crate::{
+    util::errno::{errno, set_errno},
+    Error, Result,
+};
+
+use super::{FdCondition, Handle, RunLoop};
+
+// Write end of the pipe signal handler writes signal numbers to; Only one
+// SignalHandler can be installed at a time.
+static SIGNAL_PIPE: AtomicI32 = AtomicI32::new(-1);
+
+extern "C" fn on_signal(signal: libc::c_int) {
+    // Only async-signal-safe calls are allowed here; write() may clobber errno
+    // of the interrupted code
+    let saved_errno = errno();
+    let fd = SIGNAL_PIPE.load(Ordering::SeqCst);
+    if fd >= 0 {
+        let signal = signal as u8;
+        unsafe {
+            libc::write(fd, &signal as *const u8 as *const libc::c_void, 1);
+        }
+    }
+    set_errno(saved_errno);
+}
+
+// Forwards POSIX signals to run loop (self-pipe). Previous signal handlers are
+// restored when dropped.
+pub(super) struct SignalHandler {
+    read_fd: RawFd,
+    write_fd: RawFd,
+    previous: Vec<(libc::c_int, libc::sigaction)>,
+    watch: Option<Handle>,
+}
+
+impl SignalHandler {
+    pub fn install<F>(run_loop: &RunLoop, signals: &[libc::c_int], callback: F) -> Result<Self>
+    where
+        F: Fn(libc::c_int) + 'static,
+    {
+        let mut fds = [0 as RawFd; 2];
+        if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        let mut res = Self {
+            read_fd: fds[0],
+            write_fd: fds[1],
+            previous: Vec::new(),
+            watch: None,
+        };
+        for fd in &fds {
+            set_flags(*fd)?;
+        }
+        if SIGNAL_PIPE
+            .compare_exchange(-1, res.write_fd, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            return Err(Error::Io("Signal handler is already installed".into()));
+        }
+
+        let read_fd = res.read_fd;
+        res.watch = Some(
+            run_loop.watch_fd(read_fd, FdCondition::readable(), move |_| {
+                for signal in read_signals(read_fd) {
+                    callback(signal);
+                }
+                true
+            })?,
+        );
+
+        for signal in signals {
+            unsafe {
+                let mut action: libc::sigaction = MaybeUninit::zeroed().assume_init();
+                let handler: extern "C" fn(libc::c_int) = on_signal;
+                action.sa_sigaction = handler as libc::sighandler_t;
+                action.sa_flags = libc::SA_RESTART;
+                libc::sigemptyset(&mut action.sa_mask);
+                let mut previous: libc::sigaction = MaybeUninit::zeroed().assume_init();
+                if libc::sigaction(*signal, &action, &mut previous) != 0 {
+                    return Err(std::io::Error::last_os_error().into());
+                }
+                res.previous.push((*signal, previous));
+            }
+        }
+        Ok(res)
+    }
+}
+
+impl Drop for SignalHandler {
+    fn drop(&mut self) {
+        for (signal, previous) in self.previous.drain(..) {
+            unsafe {
+                libc::sigaction(signal, &previous, std::ptr::null_mut());
+            }
+        }
+        SIGNAL_PIPE
+            .compare_exchange(self.write_fd, -1, Ordering::SeqCst, Ordering::SeqCst)
+            .ok();
+        // remove the watch before closing the descriptor
+        self.watch.take();
+        unsafe {
+            libc::close(self.read_fd);
+            libc::close(self.write_fd);
+        }
+    }
+}
+
+fn set_flags(fd: RawFd) -> Result<()> {
+    unsafe {
+        let flags = libc::fcntl(fd, libc::F_GETFL);
+        if flags < 0
+            || libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) < 0
+            || libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) < 0
+        {
+            return Err(std::io::Error::last_os_error().into());
+        }
+    }
+    Ok(())
+}
+
+fn read_signals(fd: RawFd) -> Vec<libc::c_int> {
+    let mut res = Vec::new();
+    let mut buf = [0u8; 16];
+    loop {
+        let len = unsafe { libc::read(fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len()) };
+        if len > 0 {
+            res.extend(buf[..len as usize].iter().map(|s| *s as libc::c_int));
+            continue;
+        }
+        if len < 0 {
+            let error = std::io::Error::last_os_error();
+            match error.kind() {
+                ErrorKind::Interrupted => continue,
+                ErrorKind::WouldBlock => {}
+                _ => error!("Failed to read signal pipe: {error}"),
+            }
+        }
+        break;
+    }
+    res
+}
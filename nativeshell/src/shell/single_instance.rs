@@ -0,0 +1,559 @@
+use std::{cell::RefCell, rc::Weak};
+
+#[cfg(fd_watch)]
+use std::collections::HashMap;
+
+#[cfg(fd_watch)]
+use log::warn;
+
+use crate::{
+    codec::{value::to_value, MethodCall, MethodCallReply, Value},
+    util::{Late, OkLog},
+    Context,
+};
+
+use super::{
+    api_constants::{channel, method},
+    api_model::SecondaryInstanceLaunch,
+    EngineHandle, MethodCallHandler, MethodInvokerProvider, RegisteredMethodCallHandler,
+};
+
+#[cfg(fd_watch)]
+pub(super) use listener::*;
+
+impl SecondaryInstanceLaunch {
+    // Launch arguments and working directory of current process.
+    pub fn current() -> Self {
+        Self {
+            arguments: std::env::args_os()
+                .map(|a| a.to_string_lossy().into_owned())
+                .collect(),
+            working_directory: std::env::current_dir()
+                .map(|d| d.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+// Keeps current process the only running instance of the application when
+// enabled in ContextOptions. Launches of secondary instances are forwarded to
+// all engines on nativeshell/single-instance channel.
+pub struct SingleInstanceManager {
+    context: Context,
+    #[cfg_attr(not(fd_watch), allow(dead_code))]
+    weak_self: Late<Weak<RefCell<Self>>>,
+    invoker_provider: Late<MethodInvokerProvider>,
+    #[cfg(fd_watch)]
+    listener: Option<(super::Handle, InstanceListener)>,
+    // Connections from secondary instances that are still being read
+    #[cfg(fd_watch)]
+    connections: HashMap<usize, super::Handle>,
+    #[cfg(fd_watch)]
+    next_connection: usize,
+}
+
+impl SingleInstanceManager {
+    pub(super) fn new(context: Context) -> RegisteredMethodCallHandler<Self> {
+        Self {
+            context: context.clone(),
+            weak_self: Late::new(),
+            invoker_provider: Late::new(),
+            #[cfg(fd_watch)]
+            listener: None,
+            #[cfg(fd_watch)]
+            connections: HashMap::new(),
+            #[cfg(fd_watch)]
+            next_connection: 0,
+        }
+        .register(context, channel::SINGLE_INSTANCE)
+    }
+
+    // Starts accepting connections from secondary instances.
+    #[cfg(fd_watch)]
+    pub(super) fn listen(&mut self, listener: InstanceListener) -> crate::Result<()> {
+        use std::os::unix::io::AsRawFd;
+
+        let context = self.context.get().ok_or(crate::Error::InvalidContext)?;
+        let weak_self = self.weak_self.clone();
+        let watch = context.run_loop.borrow().watch_fd(
+            listener.as_raw_fd(),
+            super::FdCondition::readable(),
+            move |_| {
+                if let Some(manager) = weak_self.upgrade() {
+                    manager.borrow_mut().accept_connections();
+                }
+                true
+            },
+        )?;
+        self.listener.replace((watch, listener));
+        Ok(())
+    }
+
+    #[cfg(fd_watch)]
+    fn accept_connections(&mut self) {
+        let connections = match &self.listener {
+            Some((_, listener)) => listener.accept_connections(),
+            None => return,
+        };
+        for connection in connections {
+            self.read_connection(connection).ok_log();
+        }
+    }
+
+    // Reads launch from connection as data becomes available; Connection is
+    // closed once the launch is read, which lets secondary instance exit.
+    #[cfg(fd_watch)]
+    fn read_connection(&mut self, mut connection: LaunchConnection) -> crate::Result<()> {
+        use std::os::unix::io::AsRawFd;
+
+        let context = self.context.get().ok_or(crate::Error::InvalidContext)?;
+        let id = self.next_connection;
+        self.next_connection += 1;
+        let weak_self = self.weak_self.clone();
+        let watch = context.run_loop.borrow().watch_fd(
+            connection.as_raw_fd(),
+            super::FdCondition::readable(),
+            move |_| {
+                let launch = match connection.read() {
+                    Some(launch) => launch,
+                    None => return true,
+                };
+                if let Some(manager) = weak_self.upgrade() {
+                    // this drops the watch handle, but the watch is being
+                    // removed anyway as we return false
+                    manager.borrow_mut().connections.remove(&id);
+                    match launch {
+                        Ok(launch) => manager.borrow().on_secondary_instance(launch),
+                        Err(err) => warn!("Failed to read secondary instance launch: {err}"),
+                    }
+                }
+                false
+            },
+        )?;
+        self.connections.insert(id, watch);
+        Ok(())
+    }
+
+    #[cfg_attr(not(fd_watch), allow(dead_code))]
+    fn on_secondary_instance(&self, launch: SecondaryInstanceLaunch) {
+        if let Some(context) = self.context.get() {
+            let value = to_value(&launch).unwrap();
+            for engine in context.engine_manager.borrow().get_all_engines() {
+                self.invoker_provider
+                    .get_method_invoker_for_engine(engine)
+                    .call_method(
+                        method::single_instance::ON_SECONDARY_INSTANCE,
+                        value.clone(),
+                        |_| {},
+                    )
+                    .ok_log();
+            }
+            context
+                .application_manager
+                .borrow()
+                .borrow()
+                .on_secondary_instance(launch);
+        }
+    }
+}
+
+impl MethodCallHandler for SingleInstanceManager {
+    fn on_method_call(
+        &mut self,
+        _call: MethodCall<Value>,
+        _reply: MethodCallReply<Value>,
+        _engine: EngineHandle,
+    ) {
+    }
+
+    fn assign_weak_self(&mut self, weak_self: Weak<RefCell<Self>>) {
+        self.weak_self.set(weak_self);
+    }
+
+    fn assign_invoker_provider(&mut self, provider: MethodInvokerProvider) {
+        self.invoker_provider.set(provider);
+    }
+}
+
+#[cfg(fd_watch)]
+mod listener {
+    use std::{
+        fs::File,
+        io::{ErrorKind, Read, Write},
+        net::Shutdown,
+        os::unix::{
+            io::{AsRawFd, RawFd},
+            net::{UnixListener, UnixStream},
+        },
+        path::{Path, PathBuf},
+        time::Duration,
+    };
+
+    use log::error;
+
+    use crate::{
+        shell::{api_model::SecondaryInstanceLaunch, ContextOptions},
+        Error, Result,
+    };
+
+    // Maximum time spent by secondary instance sending launch information
+    const IO_TIMEOUT: Duration = Duration::from_secs(1);
+
+    // Launches larger than this are rejected by primary instance
+    const MAX_LAUNCH_SIZE: usize = 64 * 1024;
+
+    pub(in crate::shell) enum Instance {
+        Primary(InstanceListener),
+        // Launch was forwarded to primary instance
+        Secondary,
+    }
+
+    // Listening socket of primary instance; Socket file is removed when
+    // dropped.
+    pub(in crate::shell) struct InstanceListener {
+        listener: UnixListener,
+        path: PathBuf,
+    }
+
+    // Connection from secondary instance; Launch is read without blocking as
+    // the data arrives.
+    pub(in crate::shell) struct LaunchConnection {
+        stream: UnixStream,
+        data: Vec<u8>,
+    }
+
+    // Socket in XDG_RUNTIME_DIR (or temporary directory) keyed by app_namespace.
+    pub(in crate::shell) fn socket_path(app_namespace: &str) -> PathBuf {
+        let name = match app_namespace {
+            "" => "nativeshell",
+            namespace => namespace,
+        }
+        .replace('/', "_");
+        match std::env::var_os("XDG_RUNTIME_DIR") {
+            Some(dir) => PathBuf::from(dir).join(format!("{name}.instance")),
+            None => {
+                // temporary directory is shared between users
+                let uid = unsafe { libc::getuid() };
+                std::env::temp_dir().join(format!("{name}-{uid}.instance"))
+            }
+        }
+    }
+
+    // Exclusive lock on a file next to the socket; Serializes instances
+    // launched at the same time so that only one of them can decide that
+    // the socket is stale and replace it. Released when dropped (or when the
+    // process exits). The lock file itself is left in place, removing it
+    // would let another instance lock a different file.
+    struct InstanceLock {
+        _file: File,
+    }
+
+    impl InstanceLock {
+        fn acquire(socket_path: &Path) -> Result<Self> {
+            let mut path = socket_path.as_os_str().to_owned();
+            path.push(".lock");
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(path)?;
+            loop {
+                if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } == 0 {
+                    return Ok(Self { _file: file });
+                }
+                let err = std::io::Error::last_os_error();
+                if err.kind() != ErrorKind::Interrupted {
+                    return Err(err.into());
+                }
+            }
+        }
+    }
+
+    // Becomes primary instance if no other instance is listening on given
+    // path, otherwise forwards launch to the primary instance.
+    pub(in crate::shell) fn acquire_instance(
+        path: &Path,
+        launch: &SecondaryInstanceLaunch,
+    ) -> Result<Instance> {
+        let stream = {
+            let _lock = InstanceLock::acquire(path)?;
+            match UnixStream::connect(path) {
+                Ok(stream) => stream,
+                Err(err)
+                    if err.kind() == ErrorKind::NotFound
+                        || err.kind() == ErrorKind::ConnectionRefused =>
+                {
+                    // Nobody is listening; Socket file might be left behind by
+                    // instance that crashed
+                    match std::fs::remove_file(path) {
+                        Err(err) if err.kind() != ErrorKind::NotFound => return Err(err.into()),
+                        _ => {}
+                    }
+                    let listener = UnixListener::bind(path)?;
+                    listener.set_nonblocking(true)?;
+                    return Ok(Instance::Primary(InstanceListener {
+                        listener,
+                        path: path.into(),
+                    }));
+                }
+                Err(err) => return Err(err.into()),
+            }
+        };
+        forward_launch(stream, launch)?;
+        Ok(Instance::Secondary)
+    }
+
+    // Called during context initialization; Exits the process if another
+    // instance is already running.
+    pub(in crate::shell) fn acquire_instance_or_exit(
+        options: &ContextOptions,
+    ) -> Result<Option<InstanceListener>> {
+        if !options.single_instance {
+            return Ok(None);
+        }
+        let path = socket_path(&options.app_namespace);
+        match acquire_instance(&path, &SecondaryInstanceLaunch::current())? {
+            Instance::Primary(listener) => Ok(Some(listener)),
+            Instance::Secondary => std::process::exit(0),
+        }
+    }
+
+    fn forward_launch(mut stream: UnixStream, launch: &SecondaryInstanceLaunch) -> Result<()> {
+        stream.set_read_timeout(Some(IO_TIMEOUT))?;
+        stream.set_write_timeout(Some(IO_TIMEOUT))?;
+        stream.write_all(&serde_json::to_vec(launch).unwrap())?;
+        stream.shutdown(Shutdown::Write)?;
+        // Wait until primary instance closes the connection so that the
+        // launch is not lost if this process exits right away
+        stream.read_to_end(&mut Vec::new())?;
+        Ok(())
+    }
+
+    impl InstanceListener {
+        // Accepts all pending connections without blocking.
+        pub fn accept_connections(&self) -> Vec<LaunchConnection> {
+            let mut res = Vec::new();
+            loop {
+                match self.listener.accept() {
+                    Ok((stream, _)) => match stream.set_nonblocking(true) {
+                        Ok(()) => res.push(LaunchConnection {
+                            stream,
+                            data: Vec::new(),
+                        }),
+                        Err(err) => error!("Failed to accept secondary instance: {err}"),
+                    },
+                    Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                    Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                    Err(err) => {
+                        error!("Failed to accept secondary instance: {err}");
+                        break;
+                    }
+                }
+            }
+            res
+        }
+    }
+
+    impl LaunchConnection {
+        // Reads available data; Returns None while secondary instance has not
+        // finished sending the launch.
+        pub fn read(&mut self) -> Option<Result<SecondaryInstanceLaunch>> {
+            let mut buffer = [0u8; 4096];
+            loop {
+                match self.stream.read(&mut buffer) {
+                    Ok(0) => {
+                        return Some(
+                            serde_json::from_slice(&self.data)
+                                .map_err(|err| Error::Io(err.to_string())),
+                        )
+                    }
+                    Ok(len) => {
+                        if self.data.len() + len > MAX_LAUNCH_SIZE {
+                            return Some(Err(Error::Io(format!(
+                                "Launch exceeds {MAX_LAUNCH_SIZE} bytes"
+                            ))));
+                        }
+                        self.data.extend_from_slice(&buffer[..len]);
+                    }
+                    Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                    Err(err) if err.kind() == ErrorKind::WouldBlock => return None,
+                    Err(err) => return Some(Err(err.into())),
+                }
+            }
+        }
+    }
+
+    impl AsRawFd for InstanceListener {
+        fn as_raw_fd(&self) -> RawFd {
+            self.listener.as_raw_fd()
+        }
+    }
+
+    impl AsRawFd for LaunchConnection {
+        fn as_raw_fd(&self) -> RawFd {
+            self.stream.as_raw_fd()
+        }
+    }
+
+    impl Drop for InstanceListener {
+        fn drop(&mut self) {
+            std::fs::remove_file(&self.path).ok();
+        }
+    }
+}
+
+#[cfg(all(test, fd_watch, feature = "null-backend"))]
+mod tests {
+    use std::{
+        io::Write,
+        os::unix::net::UnixStream,
+        path::Path,
+        sync::{Arc, Barrier},
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use super::{acquire_instance, socket_path, Instance, InstanceListener};
+    use crate::{
+        codec::{value::from_value, Value},
+        shell::{
+            api_model::{OpenFilesEvent, SecondaryInstanceLaunch},
+            Context, ContextOptions, FakeDart,
+        },
+    };
+
+    #[test]
+    fn test_single_instance() {
+        let app_namespace = format!("nativeshell-test-{}", std::process::id());
+        let path = socket_path(&app_namespace);
+        let context = Context::new(ContextOptions {
+            app_namespace,
+            single_instance: true,
+            ..Default::default()
+        })
+        .unwrap();
+        let dart = FakeDart::new(&context).unwrap();
+        dart.set_method_handler("nativeshell/single-instance", |_| Ok(Value::Null));
+        assert!(path.exists());
+
+        let launch = SecondaryInstanceLaunch {
+            arguments: vec!["app".into(), "file.txt".into()],
+            working_directory: "/home".into(),
+        };
+        let launch_clone = launch.clone();
+        let path_clone = path.clone();
+        let secondary = thread::spawn(move || {
+            matches!(
+                acquire_instance(&path_clone, &launch_clone),
+                Ok(Instance::Secondary)
+            )
+        });
+
+        let mut calls = Vec::new();
+        for _ in 0..50 {
+            dart.pump_for(Duration::from_millis(20));
+            calls.extend(dart.take_method_calls("nativeshell/single-instance"));
+            if !calls.is_empty() {
+                break;
+            }
+        }
+        assert!(secondary.join().unwrap());
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].method, "SingleInstance.onSecondaryInstance");
+        let received: SecondaryInstanceLaunch = from_value(&calls[0].args).unwrap();
+        assert_eq!(received, launch);
+
+        // files are also forwarded to nativeshell/application channel
+        dart.pump();
+        let calls = dart.take_method_calls("nativeshell/application");
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].method, "Application.onOpenFiles");
+        let received: OpenFilesEvent = from_value(&calls[0].args).unwrap();
+        assert_eq!(received.files, vec!["/home/file.txt"]);
+
+        dart.shut_down().unwrap();
+        drop(context);
+        assert!(!path.exists());
+        remove_lock_file(&path);
+    }
+
+    fn remove_lock_file(socket_path: &Path) {
+        std::fs::remove_file(format!("{}.lock", socket_path.display())).ok();
+    }
+
+    // Reads given number of launches from listener without run loop.
+    fn read_launches(
+        listener: &InstanceListener,
+        count: usize,
+    ) -> Vec<crate::Result<SecondaryInstanceLaunch>> {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut connections = Vec::new();
+        let mut res = Vec::new();
+        while res.len() < count && Instant::now() < deadline {
+            connections.extend(listener.accept_connections());
+            connections.retain_mut(|connection| match connection.read() {
+                Some(launch) => {
+                    res.push(launch);
+                    false
+                }
+                None => true,
+            });
+            thread::sleep(Duration::from_millis(5));
+        }
+        res
+    }
+
+    #[test]
+    fn test_simultaneous_launch() {
+        let path = socket_path(&format!("nativeshell-test-race-{}", std::process::id()));
+        let count = 8;
+        let barrier = Arc::new(Barrier::new(count));
+        let threads: Vec<_> = (0..count)
+            .map(|_| {
+                let barrier = barrier.clone();
+                let path = path.clone();
+                thread::spawn(move || {
+                    barrier.wait();
+                    match acquire_instance(&path, &SecondaryInstanceLaunch::current()).unwrap() {
+                        Instance::Primary(listener) => {
+                            let launches = read_launches(&listener, count - 1);
+                            assert_eq!(launches.len(), count - 1);
+                            assert!(launches.iter().all(|l| l.is_ok()));
+                            true
+                        }
+                        Instance::Secondary => false,
+                    }
+                })
+            })
+            .collect();
+        let primary = threads
+            .into_iter()
+            .map(|t| t.join().unwrap())
+            .filter(|primary| *primary)
+            .count();
+        assert_eq!(primary, 1);
+        assert!(!path.exists());
+        remove_lock_file(&path);
+    }
+
+    #[test]
+    fn test_launch_too_large() {
+        let path = socket_path(&format!("nativeshell-test-large-{}", std::process::id()));
+        let listener = match acquire_instance(&path, &SecondaryInstanceLaunch::current()) {
+            Ok(Instance::Primary(listener)) => listener,
+            _ => panic!("expected primary instance"),
+        };
+        let path_clone = path.clone();
+        let sender = thread::spawn(move || {
+            let mut stream = UnixStream::connect(path_clone).unwrap();
+            // fails once primary instance closes the connection
+            stream.write_all(&vec![b' '; 1024 * 1024]).ok();
+        });
+        let launches = read_launches(&listener, 1);
+        assert_eq!(launches.len(), 1);
+        assert!(launches[0].is_err());
+        sender.join().unwrap();
+        drop(listener);
+        remove_lock_file(&path);
+    }
+}
@@ -5,10 +5,7 @@ use std::{
 };
 
 use crate::{
-    codec::{
-        value::{from_value, to_value},
-        MethodCall, MethodCallReply, MethodCallResult, Value,
-    },
+    codec::{value::to_value, MethodCall, MethodCallReply, Value},
     util::{Late, OkLog},
     Error, Result,
 };
@@ -23,7 +20,7 @@ use super::{
     },
     platform::status_item::{PlatformStatusItem, PlatformStatusItemManager},
     Context, EngineHandle, MenuDelegate, MethodCallHandler, MethodInvokerProvider, Point, Rect,
-    RegisteredMethodCallHandler,
+    RegisteredMethodCallHandler, TypedMethodChannel,
 };
 
 #[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -40,6 +37,7 @@ pub struct StatusItemManager {
     next_handle: StatusItemHandle,
     weak_self: Late<Weak<RefCell<StatusItemManager>>>,
     invoker_provider: Late<MethodInvokerProvider>,
+    methods: Rc<TypedMethodChannel<Self>>,
 }
 
 impl StatusItemManager {
@@ -53,10 +51,42 @@ impl StatusItemManager {
             platform_manager,
             weak_self: Late::new(),
             invoker_provider: Late::new(),
+            methods: Rc::new(Self::methods()),
         }
         .register(context, channel::STATUS_ITEM_MANAGER)
     }
 
+    fn methods() -> TypedMethodChannel<Self> {
+        TypedMethodChannel::<Self>::new()
+            .method(method::status_item::INIT, |s, _: Value, engine| {
+                s.init(engine);
+                Ok(())
+            })
+            .method(method::status_item::CREATE, Self::on_create)
+            .method(method::status_item::DESTROY, |s, request, _| {
+                s.on_destroy(request);
+                Ok(())
+            })
+            .method(method::status_item::SET_IMAGE, |s, request, _| {
+                s.set_image(request)
+            })
+            .method(method::status_item::SET_HINT, |s, request, _| {
+                s.set_hint(request)
+            })
+            .method_with_reply(method::status_item::SHOW_MENU, |s, request, reply, _| {
+                s.show_menu(request, move |res| reply.send(res))
+            })
+            .method(method::status_item::SET_HIGHLIGHTED, |s, request, _| {
+                s.set_highlighted(request)
+            })
+            .method(method::status_item::GET_GEOMETRY, |s, request, _| {
+                s.get_geometry(request)
+            })
+            .method(method::status_item::GET_SCREEN_ID, |s, request, _| {
+                s.get_screen_id(request)
+            })
+    }
+
     fn init(&mut self, engine: EngineHandle) {
         // Remove all status items from this engine (useful for hot restart)
         let items: Vec<StatusItemHandle> = self
@@ -96,6 +126,13 @@ impl StatusItemManager {
         Ok(handle)
     }
 
+    fn on_destroy(&mut self, request: StatusItemDestroyRequest) {
+        let item = self.status_item_map.remove(&request.handle);
+        if let Some(item) = item {
+            self.platform_manager.unregister_status_item(&item);
+        }
+    }
+
     fn get_platform_status_item(&self, item: StatusItemHandle) -> Result<Rc<PlatformStatusItem>> {
         self.status_item_map
             .get(&item)
@@ -156,13 +193,6 @@ impl StatusItemManager {
         let item = self.get_platform_status_item(request.handle)?;
         item.get_screen_id().map_err(Error::from)
     }
-
-    fn map_result<T>(result: Result<T>) -> MethodCallResult<Value>
-    where
-        T: serde::Serialize,
-    {
-        result.map(|v| to_value(v).unwrap()).map_err(|e| e.into())
-    }
 }
 
 impl MethodCallHandler for StatusItemManager {
@@ -172,50 +202,7 @@ impl MethodCallHandler for StatusItemManager {
         reply: MethodCallReply<Value>,
         engine: EngineHandle,
     ) {
-        match call.method.as_str() {
-            method::status_item::INIT => {
-                self.init(engine);
-                reply.send_ok(Value::Null);
-            }
-            method::status_item::CREATE => {
-                let request: StatusItemCreateRequest = from_value(&call.args).unwrap();
-                let res = self.on_create(request, engine);
-                reply.send(Self::map_result(res));
-            }
-            method::status_item::DESTROY => {
-                let request: StatusItemDestroyRequest = from_value(&call.args).unwrap();
-                let item = self.status_item_map.remove(&request.handle);
-                if let Some(item) = item {
-                    self.platform_manager.unregister_status_item(&item);
-                }
-                reply.send_ok(Value::Null);
-            }
-            method::status_item::SET_IMAGE => {
-                let request: StatusItemSetImageRequest = from_value(&call.args).unwrap();
-                reply.send(Self::map_result(self.set_image(request)));
-            }
-            method::status_item::SET_HINT => {
-                let request: StatusItemSetHintRequest = from_value(&call.args).unwrap();
-                reply.send(Self::map_result(self.set_hint(request)));
-            }
-            method::status_item::SHOW_MENU => {
-                let request: StatusItemShowMenuRequest = from_value(&call.args).unwrap();
-                self.show_menu(request, |res| reply.send(Self::map_result(res)));
-            }
-            method::status_item::SET_HIGHLIGHTED => {
-                let request: StatusItemSetHighlightedRequest = from_value(&call.args).unwrap();
-                reply.send(Self::map_result(self.set_highlighted(request)));
-            }
-            method::status_item::GET_GEOMETRY => {
-                let request: StatusItemGetGeometryRequest = from_value(&call.args).unwrap();
-                reply.send(Self::map_result(self.get_geometry(request)));
-            }
-            method::status_item::GET_SCREEN_ID => {
-                let request: StatusItemGetScreenIdRequest = from_value(&call.args).unwrap();
-                reply.send(Self::map_result(self.get_screen_id(request)));
-            }
-            _ => {}
-        }
+        self.methods.clone().dispatch(self, call, reply, engine);
     }
 
     fn assign_weak_self(&mut self, weak_self: Weak<RefCell<Self>>) {
@@ -0,0 +1,197 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use futures::{
+    future::{abortable, AbortHandle},
+    stream::{self, LocalBoxStream},
+    Stream, StreamExt,
+};
+
+use crate::codec::{MethodCallError, Value};
+
+use super::{Context, EventChannelHandler, EventSink, RegisteredEventChannel};
+
+// Item of a stream forwarded to event channel; Errors are delivered to Dart
+// listener as PlatformException without closing the stream.
+pub trait EventStreamItem {
+    fn into_event(self) -> std::result::Result<Value, MethodCallError<Value>>;
+}
+
+impl EventStreamItem for Value {
+    fn into_event(self) -> std::result::Result<Value, MethodCallError<Value>> {
+        Ok(self)
+    }
+}
+
+impl EventStreamItem for std::result::Result<Value, MethodCallError<Value>> {
+    fn into_event(self) -> std::result::Result<Value, MethodCallError<Value>> {
+        self
+    }
+}
+
+type StreamFactory =
+    dyn Fn(Value) -> LocalBoxStream<'static, std::result::Result<Value, MethodCallError<Value>>>;
+
+// Event channel handler backed by streams. A new stream is created for every
+// listener (from the listen argument) and polled on the run loop. When Dart
+// cancels the subscription the stream is dropped; When the stream ends, the
+// Dart stream is closed.
+pub struct StreamEventChannelHandler {
+    context: Context,
+    factory: Box<StreamFactory>,
+    subscriptions: HashMap<i64, AbortHandle>,
+}
+
+impl StreamEventChannelHandler {
+    pub fn new<F, S>(context: Context, factory: F) -> Self
+    where
+        F: Fn(Value) -> S + 'static,
+        S: Stream + 'static,
+        S::Item: EventStreamItem,
+    {
+        Self {
+            context,
+            factory: Box::new(move |argument| {
+                factory(argument)
+                    .map(|item| item.into_event())
+                    .boxed_local()
+            }),
+            subscriptions: HashMap::new(),
+        }
+    }
+
+    // Handler for single stream; Only first listener receives the items,
+    // streams of subsequent listeners end immediately.
+    pub fn from_stream<S>(context: Context, stream: S) -> Self
+    where
+        S: Stream + 'static,
+        S::Item: EventStreamItem,
+    {
+        let stream = RefCell::new(Some(stream));
+        Self::new(context, move |_| {
+            stream::iter(stream.borrow_mut().take()).flatten()
+        })
+    }
+
+    // Convenience for creating and registering the handler.
+    pub fn register_with_factory<F, S>(
+        context: Context,
+        channel: &str,
+        factory: F,
+    ) -> RegisteredEventChannel<Self>
+    where
+        F: Fn(Value) -> S + 'static,
+        S: Stream + 'static,
+        S::Item: EventStreamItem,
+    {
+        Self::new(context.clone(), factory).register(context, channel)
+    }
+}
+
+impl EventChannelHandler for StreamEventChannelHandler {
+    fn register_event_sink(&mut self, sink: EventSink, listen_argument: Value) {
+        let context = match self.context.get() {
+            Some(context) => context,
+            None => return,
+        };
+        let sink_id = sink.id();
+        let mut stream = (self.factory)(listen_argument);
+        let (future, abort_handle) = abortable(async move {
+            while let Some(event) = stream.next().await {
+                let res = match event {
+                    Ok(value) => sink.send_message(&value),
+                    Err(error) => sink.send_error(&error),
+                };
+                // Engine is gone, no point in polling the stream further
+                if res.is_err() {
+                    return;
+                }
+            }
+            sink.end_of_stream().ok();
+        });
+        context.run_loop.borrow().spawn(future);
+        self.subscriptions.insert(sink_id, abort_handle);
+    }
+
+    fn unregister_event_sink(&mut self, sink_id: i64) {
+        if let Some(abort_handle) = self.subscriptions.remove(&sink_id) {
+            abort_handle.abort();
+        }
+    }
+}
+
+#[cfg(all(test, feature = "null-backend"))]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use futures::channel::mpsc;
+
+    use super::StreamEventChannelHandler;
+    use crate::{
+        codec::{MethodCallError, MethodCallResult, MethodCodec, StandardMethodCodec, Value},
+        shell::{Context, ContextOptions, FakeDart},
+    };
+
+    type Item = std::result::Result<Value, MethodCallError<Value>>;
+
+    #[test]
+    fn test_stream_event_channel() {
+        let context = Context::new(ContextOptions::default()).unwrap();
+        let dart = FakeDart::new(&context).unwrap();
+
+        // Every listener gets new stream fed by the test
+        let senders = Rc::new(RefCell::new(Vec::<mpsc::UnboundedSender<Item>>::new()));
+        let senders_clone = senders.clone();
+        let _channel =
+            StreamEventChannelHandler::register_with_factory(context.weak(), "events", move |_| {
+                let (sender, receiver) = mpsc::unbounded();
+                senders_clone.borrow_mut().push(sender);
+                receiver
+            });
+        let take_events = || -> Vec<Option<MethodCallResult<Value>>> {
+            dart.pump();
+            dart.take_messages("events")
+                .iter()
+                .map(|m| {
+                    if m.is_empty() {
+                        None
+                    } else {
+                        StandardMethodCodec.decode_envelope(m)
+                    }
+                })
+                .collect()
+        };
+        let sender = |index: usize| senders.borrow()[index].clone();
+
+        // Listen forwards stream items, including errors
+        dart.invoke_method("events", "listen", Value::Null);
+        sender(0).unbounded_send(Ok(Value::I64(1))).unwrap();
+        sender(0)
+            .unbounded_send(Err(MethodCallError::from_code_message("error", "failed")))
+            .unwrap();
+        let events = take_events();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0], Some(Ok(Value::I64(1))));
+        assert!(matches!(&events[1], Some(Err(error)) if error.code == "error"));
+
+        // Listening again replaces the previous stream
+        dart.invoke_method("events", "listen", Value::Null);
+        dart.pump();
+        assert!(sender(0).is_closed());
+        sender(1).unbounded_send(Ok(Value::I64(2))).unwrap();
+        assert_eq!(take_events(), vec![Some(Ok(Value::I64(2)))]);
+
+        // Cancel aborts the stream task
+        dart.invoke_method("events", "cancel", Value::Null);
+        dart.pump();
+        assert!(sender(1).is_closed());
+        assert!(take_events().is_empty());
+
+        // Stream ending closes the Dart stream
+        dart.invoke_method("events", "listen", Value::Null);
+        sender(2).unbounded_send(Ok(Value::I64(3))).unwrap();
+        sender(2).close_channel();
+        assert_eq!(take_events(), vec![Some(Ok(Value::I64(3))), None]);
+
+        dart.shut_down().unwrap();
+    }
+}
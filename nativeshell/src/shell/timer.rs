@@ -0,0 +1,321 @@
+use std::{
+    cell::RefCell,
+    future::Future,
+    pin::Pin,
+    rc::{Rc, Weak},
+    task::{Poll, Waker},
+    time::{Duration, Instant},
+};
+
+use futures::Stream;
+
+use super::{platform::run_loop::PlatformRunLoop, run_loop::schedule_on, Handle, RunLoop};
+
+#[derive(Default)]
+struct TimerState {
+    fired: bool,
+    waker: Option<Waker>,
+}
+
+impl TimerState {
+    fn fire(state: &RefCell<TimerState>) {
+        let waker = {
+            let mut state = state.borrow_mut();
+            state.fired = true;
+            state.waker.take()
+        };
+        // Waker might poll synchronously, must not be called while borrowed
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    fn poll_fired(&mut self, cx: &mut std::task::Context<'_>) -> bool {
+        if !self.fired {
+            self.waker.replace(cx.waker().clone());
+        }
+        self.fired
+    }
+}
+
+// Future that completes after given duration; Created by RunLoop::sleep.
+// Dropping the future cancels the underlying run loop timer.
+pub struct Sleep {
+    state: Rc<RefCell<TimerState>>,
+    _handle: Handle,
+}
+
+impl Sleep {
+    pub(super) fn new(run_loop: &Rc<PlatformRunLoop>, duration: Duration) -> Self {
+        let state = Rc::new(RefCell::new(TimerState::default()));
+        let state_clone = state.clone();
+        let handle = schedule_on(run_loop, duration, move || {
+            TimerState::fire(&state_clone);
+        });
+        Self {
+            state,
+            _handle: handle,
+        }
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Self::Output> {
+        if self.state.borrow_mut().poll_fired(cx) {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+struct IntervalState {
+    timer: Rc<RefCell<TimerState>>,
+    run_loop: Rc<PlatformRunLoop>,
+    period: Duration,
+    next_tick: Instant,
+    handle: Option<Handle>,
+}
+
+// Stream that yields every period; Created by RunLoop::interval. First tick
+// is after one period. Ticks missed while the stream was not polled are
+// coalesced into single tick. Panics if period is zero.
+pub struct Interval {
+    state: Rc<RefCell<IntervalState>>,
+}
+
+impl Interval {
+    pub(super) fn new(run_loop: &Rc<PlatformRunLoop>, period: Duration) -> Self {
+        assert!(period > Duration::ZERO, "Interval period must be non-zero");
+        let state = Rc::new(RefCell::new(IntervalState {
+            timer: Rc::new(RefCell::new(TimerState::default())),
+            run_loop: run_loop.clone(),
+            period,
+            next_tick: Instant::now() + period,
+            handle: None,
+        }));
+        Self::schedule_tick(&state);
+        Self { state }
+    }
+
+    fn schedule_tick(state: &Rc<RefCell<IntervalState>>) {
+        let weak_state = Rc::downgrade(state);
+        let mut state = state.borrow_mut();
+        let delay = state.next_tick.saturating_duration_since(Instant::now());
+        let handle = schedule_on(&state.run_loop, delay, move || {
+            Self::on_tick(weak_state);
+        });
+        state.handle.replace(handle);
+    }
+
+    fn on_tick(state: Weak<RefCell<IntervalState>>) {
+        let state = match state.upgrade() {
+            Some(state) => state,
+            None => return,
+        };
+        let timer = {
+            let mut state = state.borrow_mut();
+            if let Some(mut handle) = state.handle.take() {
+                // Timer is being executed, nothing to cancel
+                handle.detach();
+            }
+            let now = Instant::now();
+            let period = state.period;
+            while state.next_tick <= now {
+                state.next_tick += period;
+            }
+            state.timer.clone()
+        };
+        Self::schedule_tick(&state);
+        TimerState::fire(&timer);
+    }
+}
+
+impl Stream for Interval {
+    type Item = ();
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let timer = self.state.borrow().timer.clone();
+        let mut timer = timer.borrow_mut();
+        if timer.poll_fired(cx) {
+            timer.fired = false;
+            Poll::Ready(Some(()))
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+// Postpones callback until no other call was made for given delay; Each call
+// replaces the pending callback.
+pub struct Debouncer {
+    run_loop: Rc<PlatformRunLoop>,
+    delay: Duration,
+    pending: Option<Handle>,
+}
+
+impl Debouncer {
+    pub fn new(run_loop: &RunLoop, delay: Duration) -> Self {
+        Self {
+            run_loop: run_loop.platform_run_loop.clone(),
+            delay,
+            pending: None,
+        }
+    }
+
+    pub fn call<F>(&mut self, callback: F)
+    where
+        F: FnOnce() + 'static,
+    {
+        // replacing the handle cancels previously scheduled callback
+        self.pending
+            .replace(schedule_on(&self.run_loop, self.delay, callback));
+    }
+
+    pub fn cancel(&mut self) {
+        self.pending.take();
+    }
+}
+
+struct ThrottlerState {
+    run_loop: Rc<PlatformRunLoop>,
+    period: Duration,
+    last_call: Option<Instant>,
+    trailing: Option<Box<dyn FnOnce()>>,
+    handle: Option<Handle>,
+}
+
+// Executes callback at most once per period. First call is executed
+// immediately; Calls made during the period replace each other and the last
+// one is executed when the period ends.
+pub struct Throttler {
+    state: Rc<RefCell<ThrottlerState>>,
+}
+
+impl Throttler {
+    pub fn new(run_loop: &RunLoop, period: Duration) -> Self {
+        Self {
+            state: Rc::new(RefCell::new(ThrottlerState {
+                run_loop: run_loop.platform_run_loop.clone(),
+                period,
+                last_call: None,
+                trailing: None,
+                handle: None,
+            })),
+        }
+    }
+
+    pub fn call<F>(&mut self, callback: F)
+    where
+        F: FnOnce() + 'static,
+    {
+        let now = Instant::now();
+        let mut state = self.state.borrow_mut();
+        match state.last_call.map(|last_call| last_call + state.period) {
+            Some(next_call) if next_call > now => {
+                state.trailing.replace(Box::new(callback));
+                if state.handle.is_none() {
+                    let weak_state = Rc::downgrade(&self.state);
+                    let handle = schedule_on(&state.run_loop, next_call - now, move || {
+                        Self::call_trailing(weak_state);
+                    });
+                    state.handle.replace(handle);
+                }
+            }
+            _ => {
+                state.last_call.replace(now);
+                drop(state);
+                callback();
+            }
+        }
+    }
+
+    // Drops pending trailing callback.
+    pub fn cancel(&mut self) {
+        let mut state = self.state.borrow_mut();
+        state.trailing.take();
+        state.handle.take();
+    }
+
+    fn call_trailing(state: Weak<RefCell<ThrottlerState>>) {
+        let state = match state.upgrade() {
+            Some(state) => state,
+            None => return,
+        };
+        let callback = {
+            let mut state = state.borrow_mut();
+            if let Some(mut handle) = state.handle.take() {
+                handle.detach();
+            }
+            state.last_call.replace(Instant::now());
+            state.trailing.take()
+        };
+        if let Some(callback) = callback {
+            callback();
+        }
+    }
+}
+
+#[cfg(all(test, feature = "null-backend"))]
+mod tests {
+    use std::{cell::RefCell, rc::Rc, time::Duration};
+
+    use futures::StreamExt;
+
+    use super::{Debouncer, Throttler};
+    use crate::shell::{Context, ContextOptions, FakeDart};
+
+    #[test]
+    fn test_timers() {
+        let context = Context::new(ContextOptions::default()).unwrap();
+        let dart = FakeDart::new(&context).unwrap();
+        let events = Rc::new(RefCell::new(Vec::<&'static str>::new()));
+
+        {
+            let run_loop = context.run_loop.borrow();
+            let sleep = run_loop.sleep(Duration::from_millis(10));
+            let mut interval = run_loop.interval(Duration::from_millis(5));
+            let events = events.clone();
+            run_loop.spawn(async move {
+                sleep.await;
+                events.borrow_mut().push("sleep");
+                for _ in 0..3 {
+                    interval.next().await;
+                    events.borrow_mut().push("tick");
+                }
+            });
+        }
+        dart.pump_for(Duration::from_millis(100));
+        assert_eq!(*events.borrow(), vec!["sleep", "tick", "tick", "tick"]);
+        events.borrow_mut().clear();
+
+        let mut debouncer = Debouncer::new(&context.run_loop.borrow(), Duration::from_millis(20));
+        let mut throttler = Throttler::new(&context.run_loop.borrow(), Duration::from_millis(20));
+        for name in &["1", "2", "3"] {
+            let events_clone = events.clone();
+            debouncer.call(move || events_clone.borrow_mut().push("debounced"));
+            let events_clone = events.clone();
+            throttler.call(move || events_clone.borrow_mut().push(name));
+        }
+        assert_eq!(*events.borrow(), vec!["1"]);
+        dart.pump_for(Duration::from_millis(60));
+        let mut events = events.borrow().clone();
+        events.sort_unstable();
+        assert_eq!(events, vec!["1", "3", "debounced"]);
+
+        dart.shut_down().unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "Interval period must be non-zero")]
+    fn test_zero_interval() {
+        let context = Context::new(ContextOptions::default()).unwrap();
+        let run_loop = context.run_loop.borrow();
+        let _interval = run_loop.interval(Duration::ZERO);
+    }
+}
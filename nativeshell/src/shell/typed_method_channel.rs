@@ -0,0 +1,202 @@
+use std::{collections::HashMap, marker::PhantomData};
+
+use crate::{
+    codec::{
+        value::{from_value_owned, to_value},
+        MethodCall, MethodCallError, MethodCallReply, MethodCallResult, Value,
+    },
+    Error, Result,
+};
+
+use super::EngineHandle;
+
+type TypedMethodCallback<T> = dyn Fn(&mut T, Value, MethodCallReply<Value>, EngineHandle);
+
+// Dispatch table for a method channel. Each method is registered with a callback
+// that receives deserialized arguments and returns a serializable result; Decoding
+// and encoding (including mapping errors to MethodCallError) is done by the table.
+//
+// The table is meant to be owned by a MethodCallHandler and consulted from its
+// on_method_call implementation:
+//
+//   fn on_method_call(&mut self, call, reply, engine) {
+//       self.methods.clone().dispatch(self, call, reply, engine);
+//   }
+pub struct TypedMethodChannel<T> {
+    methods: HashMap<String, Box<TypedMethodCallback<T>>>,
+}
+
+impl<T: 'static> TypedMethodChannel<T> {
+    pub fn new() -> Self {
+        Self {
+            methods: HashMap::new(),
+        }
+    }
+
+    // Registers synchronous method; The result is sent back to caller immediately
+    // after callback returns.
+    pub fn method<A, R, F>(self, method: &str, callback: F) -> Self
+    where
+        A: serde::de::DeserializeOwned,
+        R: serde::Serialize,
+        F: Fn(&mut T, A, EngineHandle) -> Result<R> + 'static,
+    {
+        self.method_with_reply(method, move |target, args, reply, engine| {
+            reply.send(callback(target, args, engine));
+        })
+    }
+
+    // Registers method that replies at later point (i.e. after showing a menu).
+    pub fn method_with_reply<A, R, F>(mut self, method: &str, callback: F) -> Self
+    where
+        A: serde::de::DeserializeOwned,
+        R: serde::Serialize,
+        F: Fn(&mut T, A, TypedMethodCallReply<R>, EngineHandle) + 'static,
+    {
+        self.methods.insert(
+            method.into(),
+            Box::new(
+                move |target, args, reply, engine| match from_value_owned::<A>(&args) {
+                    Ok(args) => {
+                        let reply = TypedMethodCallReply {
+                            reply,
+                            _data: PhantomData {},
+                        };
+                        callback(target, args, reply, engine);
+                    }
+                    Err(error) => reply.send(Err(MethodCallError::from(Error::from(error)))),
+                },
+            ),
+        );
+        self
+    }
+
+    pub fn handles_method(&self, method: &str) -> bool {
+        self.methods.contains_key(method)
+    }
+
+    // Dispatches the call to registered method. Returns false if there is no method
+    // registered for the call, in which case the reply is dropped, which results
+    // in MissingPluginException on Dart side.
+    pub fn dispatch(
+        &self,
+        target: &mut T,
+        call: MethodCall<Value>,
+        reply: MethodCallReply<Value>,
+        engine: EngineHandle,
+    ) -> bool {
+        match self.methods.get(&call.method) {
+            Some(method) => {
+                method(target, call.args, reply, engine);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl<T: 'static> Default for TypedMethodChannel<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct TypedMethodCallReply<R> {
+    reply: MethodCallReply<Value>,
+    _data: PhantomData<R>,
+}
+
+impl<R: serde::Serialize> TypedMethodCallReply<R> {
+    pub fn send(self, result: Result<R>) {
+        self.reply.send(encode_method_call_result(result));
+    }
+
+    pub fn send_ok(self, value: R) {
+        self.send(Ok(value))
+    }
+
+    pub fn send_error(self, code: &str, message: Option<&str>, details: Value) {
+        self.reply.send_error(code, message, details)
+    }
+}
+
+// Converts result to value that can be sent to Dart. Serialization failure is
+// reported as method call error.
+pub fn encode_method_call_result<T>(result: Result<T>) -> MethodCallResult<Value>
+where
+    T: serde::Serialize,
+{
+    result
+        .and_then(|v| to_value(v).map_err(Error::from))
+        .map_err(|e| e.into())
+}
+
+#[cfg(all(test, feature = "null-backend"))]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::TypedMethodChannel;
+    use crate::{
+        codec::Value,
+        shell::{Context, ContextOptions, FakeDart},
+    };
+
+    #[derive(Default)]
+    struct Counter {
+        value: i64,
+    }
+
+    #[test]
+    fn test_dispatch() {
+        let context = Context::new(ContextOptions::default()).unwrap();
+        let dart = FakeDart::new(&context).unwrap();
+
+        let methods = TypedMethodChannel::<Counter>::default()
+            .method("add", |counter, value: i64, _| {
+                counter.value += value;
+                Ok(counter.value)
+            })
+            .method_with_reply("get", |counter, (), reply, _| reply.send_ok(counter.value));
+        assert!(methods.handles_method("add"));
+        assert!(!methods.handles_method("remove"));
+
+        let counter = Rc::new(RefCell::new(Counter::default()));
+        let dispatched = Rc::new(RefCell::new(Vec::new()));
+        let dispatched_clone = dispatched.clone();
+        context
+            .message_manager
+            .borrow_mut()
+            .register_method_handler("counter", move |call, reply, engine| {
+                let method = call.method.clone();
+                let res = methods.dispatch(&mut counter.borrow_mut(), call, reply, engine);
+                dispatched_clone.borrow_mut().push((method, res));
+            });
+
+        assert_eq!(
+            dart.invoke_method("counter", "add", Value::I64(2)),
+            Some(Ok(Value::I64(2)))
+        );
+        assert_eq!(
+            dart.invoke_method("counter", "get", Value::Null),
+            Some(Ok(Value::I64(2)))
+        );
+
+        // Arguments that fail to deserialize are reported as error
+        let res = dart.invoke_method("counter", "add", "two".into());
+        assert!(matches!(res, Some(Err(error)) if error.code.starts_with("Value")));
+
+        // Unknown method is not handled; Dropped reply means not implemented
+        assert_eq!(dart.invoke_method("counter", "remove", Value::Null), None);
+        assert_eq!(
+            *dispatched.borrow(),
+            vec![
+                ("add".to_owned(), true),
+                ("get".to_owned(), true),
+                ("add".to_owned(), true),
+                ("remove".to_owned(), false),
+            ]
+        );
+
+        dart.shut_down().unwrap();
+    }
+}
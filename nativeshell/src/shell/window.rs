@@ -451,6 +451,11 @@ impl PlatformWindowDelegate for Window {
     fn will_close(&self) {
         if let Some(context) = self.context.get() {
             self.broadcast_message(event::window::CLOSE, Value::Null);
+            context
+                .application_manager
+                .borrow()
+                .borrow()
+                .window_activation_changed(self.window_handle, false);
             context.window_manager.borrow_mut().remove_window(self);
         }
     }
@@ -458,6 +463,13 @@ impl PlatformWindowDelegate for Window {
     fn state_flags_changed(&self) {
         let flags = self.platform_window.borrow().get_window_state_flags();
         if let Ok(flags) = flags {
+            if let Some(context) = self.context.get() {
+                context
+                    .application_manager
+                    .borrow()
+                    .borrow()
+                    .window_activation_changed(self.window_handle, flags.active);
+            }
             self.broadcast_message(event::window::STATE_FLAGS_CHANGED, to_value(flags).unwrap());
         }
     }
@@ -13,6 +13,7 @@ use crate::{
 
 use super::{
     api_constants::*,
+    api_model::{ApiNegotiationRequest, ApiNegotiationResponse},
     platform::window::{PlatformWindow, PlatformWindowType},
     Context, ContextRef, EngineHandle, PlatformWindowDelegate, Window, WindowHandle,
     WindowMethodCall, WindowMethodCallReply, WindowMethodCallResult,
@@ -23,6 +24,8 @@ pub struct WindowManager {
     windows: HashMap<WindowHandle, Rc<Window>>,
     next_handle: WindowHandle,
     engine_to_window: HashMap<EngineHandle, WindowHandle>,
+    // Windows whose Dart side requested incompatible API; initWindow fails for these
+    api_mismatch: HashMap<WindowHandle, MethodCallError<Value>>,
 }
 
 #[derive(serde::Deserialize)]
@@ -65,6 +68,7 @@ impl WindowManager {
             windows: HashMap::new(),
             next_handle: WindowHandle(1),
             engine_to_window: HashMap::new(),
+            api_mismatch: HashMap::new(),
         }
     }
 
@@ -167,6 +171,55 @@ impl WindowManager {
                 .detach();
 
             self.windows.remove(&window.window_handle);
+            self.api_mismatch.remove(&window.window_handle);
+        }
+    }
+
+    fn negotiate_api(
+        request: &ApiNegotiationRequest,
+    ) -> std::result::Result<ApiNegotiationResponse, MethodCallError<Value>> {
+        let version = request.max_version.min(CURRENT_API_VERSION);
+        if version < request.min_version.max(MIN_API_VERSION) {
+            return Err(MethodCallError {
+                code: "api-version-mismatch".into(),
+                message: Some(format!(
+                    "Dart side supports API versions {}-{}, native side supports {}-{}; \
+                     Make sure that nativeshell Rust and Dart packages are up to date",
+                    request.min_version, request.max_version, MIN_API_VERSION, CURRENT_API_VERSION
+                )),
+                details: Value::Null,
+            });
+        }
+        let features = request
+            .features
+            .iter()
+            .filter(|f| API_FEATURES.contains(&f.as_str()))
+            .cloned()
+            .collect();
+        Ok(ApiNegotiationResponse { version, features })
+    }
+
+    fn on_negotiate_api(
+        &mut self,
+        argument: &Value,
+        engine: EngineHandle,
+    ) -> std::result::Result<ApiNegotiationResponse, MethodCallError<Value>> {
+        let request: ApiNegotiationRequest =
+            from_value(argument).map_err(|e| MethodCallError::from(Error::from(e)))?;
+        let window = self.engine_to_window.get(&engine).cloned();
+        match Self::negotiate_api(&request) {
+            Ok(response) => {
+                if let Some(window) = window {
+                    self.api_mismatch.remove(&window);
+                }
+                Ok(response)
+            }
+            Err(error) => {
+                if let Some(window) = window {
+                    self.api_mismatch.insert(window, error.clone());
+                }
+                Err(error)
+            }
         }
     }
 
@@ -222,6 +275,29 @@ impl WindowManager {
             method::window_manager::GET_API_VERSION => {
                 reply.send(Ok(Value::I64(CURRENT_API_VERSION as i64)));
             }
+            method::window_manager::NEGOTIATE_API => {
+                let result = context
+                    .window_manager
+                    .borrow_mut()
+                    .on_negotiate_api(&call.arguments, engine);
+                if let Ok(api) = &result {
+                    let buffering = {
+                        let mut engine_manager = context.engine_manager.borrow_mut();
+                        engine_manager.set_negotiated_api(engine, api.clone());
+                        engine_manager.channel_buffering().clone()
+                    };
+                    if !api
+                        .features
+                        .iter()
+                        .any(|f| f == api_feature::CHANNEL_READINESS)
+                    {
+                        // Dart side will never signal readiness; Don't hold
+                        // messages until buffering times out
+                        buffering.disable_for_engine(engine);
+                    }
+                }
+                reply.send(result.map(|api| to_value(api).unwrap()));
+            }
             method::window_manager::INIT_WINDOW => {
                 let window = context
                     .window_manager
@@ -229,8 +305,17 @@ impl WindowManager {
                     .engine_to_window
                     .get(&engine)
                     .cloned();
-                match window {
-                    Some(window) => {
+                let mismatch = window.and_then(|window| {
+                    context
+                        .window_manager
+                        .borrow()
+                        .api_mismatch
+                        .get(&window)
+                        .cloned()
+                });
+                match (window, mismatch) {
+                    (Some(_), Some(error)) => reply.send(Err(error)),
+                    (Some(window), None) => {
                         reply.send(Ok(context.window_manager.borrow().on_init(window)));
                         context
                             .window_method_channel
@@ -238,7 +323,7 @@ impl WindowManager {
                             .get_message_broadcaster(window, channel::win::WINDOW_MANAGER)
                             .broadcast_message(event::window::INITIALIZE, Value::Null);
                     }
-                    None => reply.send(Err(MethodCallError {
+                    (None, _) => reply.send(Err(MethodCallError {
                         code: "no-window".into(),
                         message: Some("No window associated with engine".into()),
                         details: Value::Null,
@@ -297,3 +382,96 @@ impl WindowManager {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ApiNegotiationRequest, WindowManager, API_FEATURES, CURRENT_API_VERSION};
+
+    #[test]
+    fn test_negotiate_api() {
+        let response = WindowManager::negotiate_api(&ApiNegotiationRequest {
+            min_version: 1,
+            max_version: CURRENT_API_VERSION + 5,
+            features: vec![API_FEATURES[0].into(), "unknownFeature".into()],
+        })
+        .unwrap();
+        assert_eq!(response.version, CURRENT_API_VERSION);
+        assert_eq!(response.features, vec![API_FEATURES[0].to_owned()]);
+
+        let error = WindowManager::negotiate_api(&ApiNegotiationRequest {
+            min_version: CURRENT_API_VERSION + 1,
+            max_version: CURRENT_API_VERSION + 2,
+            features: Vec::new(),
+        })
+        .unwrap_err();
+        assert_eq!(error.code, "api-version-mismatch");
+    }
+
+    #[test]
+    #[cfg(feature = "null-backend")]
+    fn test_negotiated_features() {
+        use std::time::Duration;
+
+        use velcro::hash_map;
+
+        use crate::{
+            codec::{MessageCodec, StandardMethodCodec, Value},
+            shell::{
+                api_constants::{api_feature, channel, method},
+                Context, ContextOptions, FakeDart,
+            },
+        };
+
+        let context = Context::new(ContextOptions::default()).unwrap();
+        let dart = FakeDart::new(&context).unwrap();
+        context
+            .message_manager
+            .borrow_mut()
+            .enable_buffering("buffered", Duration::from_secs(60));
+        let messenger = context
+            .message_manager
+            .borrow()
+            .get_binary_message_sender(dart.engine(), "buffered");
+        messenger.send_message(&[1], |_| {}).unwrap();
+        dart.pump();
+        assert!(dart.take_messages("buffered").is_empty());
+
+        let res = dart.invoke_method(channel::SHELL, method::shell::GET_CAPABILITIES, Value::Null);
+        assert!(matches!(res, Some(Err(error)) if error.code == "feature-not-negotiated"));
+
+        let negotiate = |features: Vec<Value>| {
+            let call = Value::Map(hash_map! {
+                "targetWindowHandle".into(): Value::I64(-1),
+                "method".into(): method::window_manager::NEGOTIATE_API.into(),
+                "channel".into(): channel::win::WINDOW_MANAGER.into(),
+                "arguments".into(): Value::Map(hash_map! {
+                    "minVersion".into(): Value::I64(1),
+                    "maxVersion".into(): Value::I64(CURRENT_API_VERSION as i64),
+                    "features".into(): Value::List(features),
+                }),
+            });
+            let reply = dart
+                .send_message(
+                    channel::DISPATCHER,
+                    &StandardMethodCodec.encode_message(&call),
+                )
+                .unwrap();
+            StandardMethodCodec.decode_message(&reply).unwrap()
+        };
+
+        // Dart that doesn't signal readiness gets buffered messages right away
+        negotiate(vec![api_feature::CAPABILITIES.into()]);
+        dart.pump();
+        assert_eq!(dart.take_messages("buffered"), vec![vec![1]]);
+        let engine_manager = context.engine_manager.borrow();
+        let api = engine_manager.get_negotiated_api(dart.engine()).unwrap();
+        assert_eq!(api.version, CURRENT_API_VERSION);
+        assert_eq!(api.features, vec![api_feature::CAPABILITIES.to_owned()]);
+        drop(engine_manager);
+
+        let res = dart.invoke_method(channel::SHELL, method::shell::GET_CAPABILITIES, Value::Null);
+        assert!(matches!(res, Some(Ok(Value::Map(_)))));
+
+        dart.shut_down().unwrap();
+    }
+}
@@ -2,6 +2,7 @@ library nativeshell;
 
 export 'src/accelerator.dart';
 export 'src/api_model.dart';
+export 'src/channel_readiness.dart';
 export 'src/drag_drop.dart';
 export 'src/drag_session.dart';
 export 'src/hot_key.dart';
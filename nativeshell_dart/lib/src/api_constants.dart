@@ -11,6 +11,8 @@ class Channels {
   static final hotKeyManager = 'nativeshell/hot-key-manager';
   static final screenManager = 'nativeshell/screen-manager';
   static final statusItemManager = 'nativeshell/status-item-manager';
+  static final channelReadiness = 'nativeshell/channel-readiness';
+  static final shell = 'nativeshell/shell';
 }
 
 class Events {
@@ -24,9 +26,19 @@ class Events {
 
 const currentApiVersion = 1;
 
+// Oldest native API version this package can work with
+const minApiVersion = 1;
+
+// Optional features requested during API negotiation
+class ApiFeatures {
+  static final channelReadiness = 'channelReadiness';
+  static final capabilities = 'capabilities';
+}
+
 class Methods {
   // WindowManager
   static final windowManagerGetApiVersion = 'WindowManager.getApiVersion';
+  static final windowManagerNegotiateApi = 'WindowManager.negotiateApi';
   static final windowManagerCreateWindow = 'WindowManager.createWindow';
   static final windowManagerInitWindow = 'WindowManager.initWindow';
 
@@ -110,6 +122,12 @@ class Methods {
   static final statusItemGetGeometry = 'StatusItem.getGeometry';
   static final statusItemGetScreenId = 'StatusItem.getScreenId';
   static final statusItemOnAction = 'StatusItem.onAction';
+
+  // ChannelReadiness
+  static final channelReadinessReady = 'ChannelReadiness.ready';
+
+  // Shell
+  static final shellGetCapabilities = 'Shell.getCapabilities';
 }
 
 class Keys {
@@ -370,3 +370,65 @@ class WindowStateFlags {
     return serialize().toString();
   }
 }
+
+// Features supported by current platform backend. Calling methods that are not
+// supported results in an error.
+class Capabilities {
+  Capabilities({
+    required this.hotKeys,
+    required this.statusItems,
+    required this.statusItemHighlight,
+    required this.appMenu,
+    required this.windowMenu,
+    required this.systemMenu,
+    required this.collectionBehavior,
+    required this.fullScreen,
+    required this.windowPositioning,
+    required this.fdWatch,
+  });
+
+  final bool hotKeys;
+  final bool statusItems;
+  final bool statusItemHighlight;
+  final bool appMenu;
+  final bool windowMenu;
+  final bool systemMenu;
+  final bool collectionBehavior;
+  final bool fullScreen;
+  // Whether window can be moved to arbitrary position (not possible under
+  // Wayland)
+  final bool windowPositioning;
+  final bool fdWatch;
+
+  static Capabilities deserialize(dynamic value) {
+    final map = value as Map;
+    return Capabilities(
+      hotKeys: map['hotKeys'],
+      statusItems: map['statusItems'],
+      statusItemHighlight: map['statusItemHighlight'],
+      appMenu: map['appMenu'],
+      windowMenu: map['windowMenu'],
+      systemMenu: map['systemMenu'],
+      collectionBehavior: map['collectionBehavior'],
+      fullScreen: map['fullScreen'],
+      windowPositioning: map['windowPositioning'],
+      fdWatch: map['fdWatch'],
+    );
+  }
+
+  dynamic serialize() => {
+        'hotKeys': hotKeys,
+        'statusItems': statusItems,
+        'statusItemHighlight': statusItemHighlight,
+        'appMenu': appMenu,
+        'windowMenu': windowMenu,
+        'systemMenu': systemMenu,
+        'collectionBehavior': collectionBehavior,
+        'fullScreen': fullScreen,
+        'windowPositioning': windowPositioning,
+        'fdWatch': fdWatch,
+      };
+
+  @override
+  String toString() => serialize().toString();
+}
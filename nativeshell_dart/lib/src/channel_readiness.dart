@@ -0,0 +1,40 @@
+import 'package:flutter/services.dart';
+
+import 'api_constants.dart';
+
+// Native side may buffer messages sent on a channel until Dart signals that
+// the channel handler has been registered (see
+// MessageManager::enable_buffering). Use these helpers instead of setting the
+// handler directly so that buffered messages are delivered right away.
+class ChannelReadiness {
+  ChannelReadiness._();
+
+  static final instance = ChannelReadiness._();
+
+  final _channel = MethodChannel(Channels.channelReadiness);
+
+  // Signals that handler for given channel has been registered.
+  Future<void> ready(String channel) async {
+    try {
+      await _channel.invokeMethod(Methods.channelReadinessReady, channel);
+    } on MissingPluginException {
+      // Native side doesn't buffer any channel
+    }
+  }
+
+  Future<void> setMethodCallHandler(MethodChannel channel,
+      Future<dynamic> Function(MethodCall call)? handler) async {
+    channel.setMethodCallHandler(handler);
+    if (handler != null) {
+      await ready(channel.name);
+    }
+  }
+
+  Future<void> setMessageHandler<T>(BasicMessageChannel<T> channel,
+      Future<T> Function(T? message)? handler) async {
+    channel.setMessageHandler(handler);
+    if (handler != null) {
+      await ready(channel.name);
+    }
+  }
+}
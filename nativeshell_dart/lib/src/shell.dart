@@ -1,8 +1,21 @@
 import 'dart:io';
 
+import 'package:flutter/services.dart';
+
+import 'api_constants.dart';
+import 'api_model.dart';
+
 class Shell {
   static final instance = Shell._();
 
+  final _channel = MethodChannel(Channels.shell);
+
+  /// Returns features supported by current platform backend.
+  Future<Capabilities> getCapabilities() async {
+    return Capabilities.deserialize(
+        await _channel.invokeMethod(Methods.shellGetCapabilities));
+  }
+
   /// Reveals file on given path in graphical shell. Note that on Linix the file will
   /// not be preselected as no shell supports that.
   void revealPath(String path) async {
@@ -1,4 +1,5 @@
 import 'package:flutter/scheduler.dart';
+import 'package:flutter/services.dart';
 import 'package:flutter/widgets.dart';
 import 'dart:io';
 
@@ -25,17 +26,39 @@ class WindowManager {
     return instance._init();
   }
 
+  // API version negotiated with native side.
+  int get apiVersion => _apiVersion;
+
+  // Optional features enabled by native side during API negotiation.
+  Set<String> get apiFeatures => _apiFeatures;
+
+  late int _apiVersion;
+  var _apiFeatures = <String>{};
+
+  // Optional features this package requests during API negotiation
+  static final _requestedFeatures = <String>[
+    ApiFeatures.channelReadiness,
+    ApiFeatures.capabilities,
+  ];
+
   Future<void> _checkApiVersion(WindowMethodDispatcher dispatcher) async {
-    final version = await dispatcher.invokeMethod(
-        channel: Channels.windowManager,
-        method: Methods.windowManagerGetApiVersion,
-        targetWindowHandle: WindowHandle.invalid);
-    if (version != currentApiVersion) {
-      print('Warning: Mismatched API version!');
-      print('  NativeShell Rust crate API version: $version');
-      print('  NativeShell Dart package API version: $currentApiVersion.');
-      print(
-          '  Please update the ${version > currentApiVersion ? 'Dart package' : 'Rust crate'}.');
+    try {
+      final result = await dispatcher.invokeMethod(
+          channel: Channels.windowManager,
+          method: Methods.windowManagerNegotiateApi,
+          arguments: {
+            'minVersion': minApiVersion,
+            'maxVersion': currentApiVersion,
+            'features': _requestedFeatures,
+          },
+          targetWindowHandle: WindowHandle.invalid);
+      _apiVersion = result['version'] as int;
+      _apiFeatures = (result['features'] as List).cast<String>().toSet();
+    } on PlatformException catch (e) {
+      if (e.code != 'api-version-mismatch') {
+        rethrow;
+      }
+      throw StateError('Mismatched NativeShell API version: ${e.message}');
     }
   }
 